@@ -0,0 +1,46 @@
+//! Decrypts a log file written through an `EncryptingWriter` to stdout.
+//!
+//! usage: LOG_KEY=<64 hex digits> cargo run --example decrypt_log --features encryption -- audit.log
+
+#[cfg(feature = "encryption")]
+use simplelog::*;
+
+#[cfg(feature = "encryption")]
+fn main() {
+    let path = match std::env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!(
+                "usage: decrypt_log <file>, with the key in the LOG_KEY environment variable"
+            );
+            std::process::exit(2);
+        }
+    };
+    let key = match std::env::var("LOG_KEY")
+        .ok()
+        .and_then(|hex| EncryptionKey::from_hex(&hex))
+    {
+        Some(key) => key,
+        None => {
+            eprintln!("LOG_KEY has to contain the key as 64 hexadecimal digits");
+            std::process::exit(2);
+        }
+    };
+
+    let result = std::fs::File::open(&path).and_then(|file| {
+        decrypt(
+            &key,
+            std::io::BufReader::new(file),
+            std::io::stdout().lock(),
+        )
+    });
+    if let Err(err) = result {
+        eprintln!("{}: {}", path, err);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+fn main() {
+    println!("this example requires the encryption feature.");
+}
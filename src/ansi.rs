@@ -0,0 +1,47 @@
+//! Module providing support for ANSI escape sequences on Windows consoles
+
+/// Enables the processing of ANSI escape sequences by the consoles attached to stdout and stderr.
+///
+/// This is necessary on Windows to print colors written as escape sequences, e.g. by a `WriteLogger`
+/// with `ConfigBuilder::set_write_log_enable_colors`, and is supported since Windows 10.
+/// The `TermLogger` calls this function itself, if it writes escape sequences.
+/// On other platforms this does nothing, since their terminals support escape sequences anyway.
+///
+/// Returns `false`, if a stream is not a console or the console does not support escape sequences.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// if enable_ansi_support() {
+///     // print colored output
+/// }
+/// # }
+/// ```
+pub fn enable_ansi_support() -> bool {
+    #[cfg(windows)]
+    return enable_virtual_terminal_processing();
+    #[cfg(not(windows))]
+    return true;
+}
+
+#[cfg(windows)]
+fn enable_virtual_terminal_processing() -> bool {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+        STD_ERROR_HANDLE, STD_OUTPUT_HANDLE,
+    };
+
+    let mut enabled = true;
+    for std_handle in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+        unsafe {
+            let handle = GetStdHandle(std_handle);
+            let mut mode = 0;
+            enabled &= GetConsoleMode(handle, &mut mode) != 0
+                && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                    || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0);
+        }
+    }
+    enabled
+}
@@ -1,12 +1,34 @@
 #[cfg(feature = "termcolor")]
-use log::Level;
-use log::LevelFilter;
+use crate::loggers::logging::{degrade_spec, ColorDepth};
+use crate::loggers::logging::{RateLimiter, RepeatFolder, Sampler};
+use crate::RuntimeLevelFilters;
+use log::{Level, LevelFilter, Record};
+#[cfg(feature = "regex")]
+use regex::Regex;
 
 use std::borrow::Cow;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 #[cfg(feature = "termcolor")]
-use termcolor::Color;
+use termcolor::{Color, ColorSpec};
+use time::format_description::OwnedFormatItem;
 pub use time::{format_description::FormatItem, macros::format_description, UtcOffset};
 
+/// Where [`ConfigBuilder::set_level_symbols`] places the symbols of the levels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelSymbols {
+    /// Write the symbol followed by the label, e.g. `[⚠ WARN]`
+    Before,
+    /// Write the symbol instead of the label, e.g. `[⚠]`
+    Instead,
+}
+
+/// Symbols used by [`ConfigBuilder::set_level_symbols`], ordered from `Error` to `Trace`
+const LEVEL_SYMBOLS: [&str; 5] = ["✖", "⚠", "ℹ", "●", "○"];
+
 #[derive(Debug, Clone, Copy)]
 /// Padding to be used for logging the level
 pub enum LevelPadding {
@@ -51,14 +73,618 @@ pub enum ThreadLogMode {
     Both,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Mode for logging the file of the source code reference
+pub enum LocationMode {
+    /// Log the path of the file as passed by the compiler
+    Full,
+    /// Log the name of the file only, e.g. `main.rs`
+    FileName,
+    /// Log absolute paths starting at the directory of the crate, e.g. `serde-1.0.0/src/de.rs`
+    /// instead of the full path into the cargo registry. Relative paths are logged as they are.
+    Crate,
+}
+
+/// Colors and styles used for printing the levels (if the logger supports it)
+///
+/// Besides the foreground color, a `ColorSpec` may set a background color and the
+/// attributes bold, dimmed, italic and underline. These are supported by the `TermLogger`
+/// and, with the `ansi_term` feature, by loggers writing colors as escape sequences.
+///
+/// Colors may also be one of 256 colors (`Color::Ansi256`) or 24-bit colors (`Color::Rgb`).
+/// 24-bit colors are only written if the terminal advertises support using `COLORTERM=truecolor`,
+/// 256 colors if it does so using a `TERM` like `xterm-256color`. Otherwise they are replaced
+/// by the closest supported color, down to the basic 8 colors.
+///
+/// # Usage
+///
+/// ```
+/// # use simplelog::{Color, ColorSpec, ColorTheme, ConfigBuilder, Level};
+/// let mut theme = ColorTheme::default();
+/// theme
+///     .set_level(Level::Info, ColorSpec::new().set_fg(Some(Color::Green)).clone())
+///     .set_level(Level::Trace, ColorSpec::new().set_dimmed(true).clone());
+/// let config = ConfigBuilder::new().set_color_theme(theme).build();
+/// ```
+#[cfg(feature = "termcolor")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorTheme {
+    levels: [ColorSpec; 5],
+}
+
+#[cfg(feature = "termcolor")]
+impl ColorTheme {
+    /// A theme printing all levels in the default style of the terminal
+    pub fn plain() -> ColorTheme {
+        ColorTheme {
+            levels: Default::default(),
+        }
+    }
+
+    /// Sets the style used for printing `level`
+    pub fn set_level(&mut self, level: Level, spec: ColorSpec) -> &mut ColorTheme {
+        self.levels[level as usize - 1] = spec;
+        self
+    }
+
+    /// Returns the style used for printing `level`
+    pub fn level(&self, level: Level) -> &ColorSpec {
+        &self.levels[level as usize - 1]
+    }
+
+    /// Returns the theme with colors the terminal does not support replaced by the closest supported ones
+    pub(crate) fn degraded(&self, depth: ColorDepth) -> ColorTheme {
+        let mut theme = self.clone();
+        for spec in &mut theme.levels {
+            degrade_spec(spec, depth);
+        }
+        theme
+    }
+}
+
+/// The default theme printing errors red, warnings yellow, infos blue, debug cyan and trace white
+#[cfg(feature = "termcolor")]
+impl Default for ColorTheme {
+    fn default() -> ColorTheme {
+        let fg = |color| ColorSpec::new().set_fg(Some(color)).clone();
+        ColorTheme {
+            levels: [
+                fg(Color::Red),
+                fg(Color::Yellow),
+                fg(Color::Blue),
+                fg(Color::Cyan),
+                fg(Color::White),
+            ],
+        }
+    }
+}
+
+/// Amount of fractional digits of the seconds in RFC 3339 timestamps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimePrecision {
+    /// Whole seconds, e.g. `2024-05-03T12:34:56Z`
+    Seconds,
+    /// Milliseconds, e.g. `2024-05-03T12:34:56.123Z`
+    Millis,
+    /// Microseconds, e.g. `2024-05-03T12:34:56.123456Z`
+    Micros,
+    /// Nanoseconds, e.g. `2024-05-03T12:34:56.123456789Z`
+    Nanos,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum TimeFormat {
     Rfc2822,
     Rfc3339,
+    Rfc3339Precision(TimePrecision),
     Custom(&'static [time::format_description::FormatItem<'static>]),
+    Owned(OwnedFormatItem),
+}
+
+/// Part of the naming pattern of rotated files
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NamePart {
+    Literal(String),
+    /// File name of the log file, e.g. `app.log`
+    Name,
+    /// File name without the extension, e.g. `app`
+    Stem,
+    /// Extension of the file name, e.g. `log`
+    Ext,
+    Date,
+    Index,
+}
+
+/// Parses a naming pattern for rotated files like `{stem}-{date}-{index}.{ext}`
+pub(crate) fn parse_name_pattern(pattern: &str) -> Result<Vec<NamePart>, FormatError> {
+    let mut parts = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err(FormatError::Unmatched);
+        }
+        if start > 0 {
+            parts.push(NamePart::Literal(String::from(&rest[..start])));
+        }
+        let end = rest[start..].find('}').ok_or(FormatError::Unclosed)? + start;
+        parts.push(match &rest[start + 1..end] {
+            "name" => NamePart::Name,
+            "stem" => NamePart::Stem,
+            "ext" => NamePart::Ext,
+            "date" => NamePart::Date,
+            "index" => NamePart::Index,
+            field => return Err(FormatError::UnknownField(String::from(field))),
+        });
+        rest = &rest[end + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(NamePart::Literal(String::from(rest)));
+    }
+    Ok(parts)
+}
+
+/// When the `WriteLogger` writes buffered records into its writer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// Write every record right away, without buffering
+    EveryRecord,
+    /// Write the buffered records after the given amount of records
+    EveryRecords(usize),
+    /// Write the buffered records with the first record logged after the given time passed since they were last written
+    Interval(Duration),
+    /// Write the buffered records after records of the given level or more severe ones
+    AtLevel(Level),
+}
+
+/// Callback invoked when a rotated log file could not be compressed
+#[cfg(feature = "flate2")]
+#[derive(Clone)]
+pub(crate) struct CompressionErrorHandler(pub(crate) Arc<CompressionErrorFn>);
+
+#[cfg(feature = "flate2")]
+type CompressionErrorFn = dyn Fn(&Path, &io::Error) + Send + Sync;
+
+#[cfg(feature = "flate2")]
+impl fmt::Debug for CompressionErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CompressionErrorHandler")
+    }
+}
+
+/// Callback invoked when the log file could not be rotated or reopened
+#[derive(Clone)]
+pub(crate) struct FileErrorHandler(pub(crate) Arc<FileErrorFn>);
+
+type FileErrorFn = dyn Fn(&Path, &io::Error) + Send + Sync;
+
+impl fmt::Debug for FileErrorHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileErrorHandler")
+    }
+}
+
+/// Callback wrapping every write of the terminal loggers
+#[derive(Clone)]
+pub(crate) struct PrintGate(pub(crate) Arc<PrintGateFn>);
+
+type PrintGateFn = dyn Fn(&mut dyn FnMut()) + Send + Sync;
+
+impl fmt::Debug for PrintGate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PrintGate")
+    }
+}
+
+#[cfg(feature = "termcolor")]
+#[derive(Clone)]
+pub(crate) struct ColorCallback(pub(crate) Arc<ColorCallbackFn>);
+
+#[cfg(feature = "termcolor")]
+type ColorCallbackFn = dyn Fn(&Record<'_>) -> Option<ColorSpec> + Send + Sync;
+
+#[cfg(feature = "termcolor")]
+impl fmt::Debug for ColorCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ColorCallback")
+    }
+}
+
+/// A record passed through the [`RecordTransform`]s of a `Config`, owning all its parts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedRecord {
+    /// The level of the record
+    pub level: Level,
+    /// The target of the record
+    pub target: String,
+    /// The formatted message of the record
+    pub message: String,
+    /// The key-values of the record, only written by the loggers if the `kv` feature is enabled.
+    /// The key-values of the diagnostic context (see [`crate::context`]) are not included
+    pub key_values: Vec<(String, String)>,
+}
+
+/// A step of the pipeline every record passes, before it is formatted.
+///
+/// Transforms are added using [`ConfigBuilder::add_transform`] and applied in the order
+/// they were added, after the filters of the `Config`. They may change the level, target,
+/// message and key-values of a record, e.g. to redact or enrich it, or drop the record altogether.
+///
+/// Closures taking a `&mut OwnedRecord` and returning a `bool` implement this trait.
+pub trait RecordTransform: Send + Sync {
+    /// Transforms the record, returns `false` to drop it
+    fn transform(&self, record: &mut OwnedRecord) -> bool;
+}
+
+impl<F> RecordTransform for F
+where
+    F: Fn(&mut OwnedRecord) -> bool + Send + Sync,
+{
+    fn transform(&self, record: &mut OwnedRecord) -> bool {
+        self(record)
+    }
+}
+
+/// The layout of records, used by all loggers, that write records as text.
+///
+/// By default records are written by the [`DefaultFormatter`](crate::DefaultFormatter) of their config,
+/// a fully custom layout replacing it is set using [`ConfigBuilder::set_formatter`].
+/// A formatter writes a record into `buf`, which is a buffer reused for every record,
+/// so formatting does not need to allocate. The backtrace of errors and the line ending
+/// of the `Config` are appended afterwards, the filters of the `Config` are applied before.
+/// The `TermLogger` additionally colors the level of the default layout.
+///
+/// Closures taking a `&Record` and a `&mut dyn Write` implement this trait.
+pub trait Formatter: Send + Sync {
+    /// Writes `record` into `buf`, without a line ending
+    fn format(&self, record: &Record<'_>, buf: &mut dyn io::Write) -> io::Result<()>;
+}
+
+impl<F> Formatter for F
+where
+    F: Fn(&Record<'_>, &mut dyn io::Write) -> io::Result<()> + Send + Sync,
+{
+    fn format(&self, record: &Record<'_>, buf: &mut dyn io::Write) -> io::Result<()> {
+        self(record, buf)
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct CustomFormatter(pub(crate) Arc<dyn Formatter>);
+
+impl fmt::Debug for CustomFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Formatter")
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Transform(pub(crate) Arc<dyn RecordTransform>);
+
+impl fmt::Debug for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Transform")
+    }
+}
+
+/// Callback deciding if a record is logged, before it is formatted
+#[derive(Clone)]
+pub(crate) struct RecordFilter(pub(crate) Arc<RecordFilterFn>);
+
+type RecordFilterFn = dyn Fn(&Record<'_>) -> bool + Send + Sync;
+
+impl fmt::Debug for RecordFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RecordFilter")
+    }
+}
+
+/// Callback rewriting the message of every record, before it is written
+#[derive(Clone)]
+pub(crate) struct Redactor(pub(crate) Arc<RedactFn>);
+
+type RedactFn = dyn for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync;
+
+impl fmt::Debug for Redactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Redactor")
+    }
+}
+
+/// A field, that can be referenced in a format template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FormatField {
+    Time,
+    Hostname,
+    Level,
+    Thread,
+    Pid,
+    Target,
+    Location,
+    File,
+    Line,
+    Module,
+    Message,
+    KeyValues,
+    /// The message followed by the key-values like in the default layout, cannot be referenced by name
+    Args,
+}
+
+impl FormatField {
+    fn from_name(name: &str) -> Option<FormatField> {
+        Some(match name {
+            "time" => FormatField::Time,
+            "hostname" => FormatField::Hostname,
+            "level" => FormatField::Level,
+            "thread" => FormatField::Thread,
+            "pid" => FormatField::Pid,
+            "target" => FormatField::Target,
+            "location" => FormatField::Location,
+            "file" => FormatField::File,
+            "line" => FormatField::Line,
+            "module" => FormatField::Module,
+            "message" => FormatField::Message,
+            "kv" => FormatField::KeyValues,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FormatAlign {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FormatSegment {
+    Literal(String),
+    Field {
+        field: FormatField,
+        align: FormatAlign,
+        /// Minimum width, `0` disables padding
+        width: usize,
+        /// Maximum width, longer values are truncated
+        max: Option<usize>,
+    },
+}
+
+/// Header fields of records written in the Common Event Format
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CefHeader {
+    pub(crate) vendor: String,
+    pub(crate) product: String,
+    pub(crate) version: String,
+}
+
+/// Error returned, when a format template passed to [`ConfigBuilder::set_format`]
+/// or a time format passed to [`ConfigBuilder::set_time_format_str`] is invalid
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatError {
+    /// The template references a field, that does not exist
+    UnknownField(String),
+    /// The alignment/width specification of a field could not be parsed
+    InvalidSpec(String),
+    /// A `{` was not closed by a matching `}`
+    Unclosed,
+    /// A `}` was found without a preceding `{`, use `}}` to print a literal `}`
+    Unmatched,
+    /// The time format contains a `%` specifier, that is not supported
+    UnknownSpecifier(String),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnknownField(name) => write!(f, "unknown field `{}` in format", name),
+            FormatError::InvalidSpec(spec) => write!(f, "invalid format spec `{}`", spec),
+            FormatError::Unclosed => write!(f, "unclosed `{{` in format"),
+            FormatError::Unmatched => write!(f, "unmatched `}}` in format"),
+            FormatError::UnknownSpecifier(spec) => {
+                write!(f, "unknown specifier `{}` in time format", spec)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+pub(crate) fn parse_format(format: &str) -> Result<Vec<FormatSegment>, FormatError> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '}' => return Err(FormatError::Unmatched),
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(FormatError::Unclosed),
+                    }
+                }
+                if !literal.is_empty() {
+                    segments.push(FormatSegment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(parse_placeholder(&placeholder)?);
+            }
+            c => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(FormatSegment::Literal(literal));
+    }
+
+    Ok(segments)
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<FormatSegment, FormatError> {
+    let (name, spec) = match placeholder.split_once(':') {
+        Some((name, spec)) => (name.trim(), spec.trim()),
+        None => (placeholder.trim(), ""),
+    };
+    let field =
+        FormatField::from_name(name).ok_or_else(|| FormatError::UnknownField(name.to_string()))?;
+
+    let (align, width) = match spec.chars().next() {
+        Some('<') => (FormatAlign::Left, &spec[1..]),
+        Some('>') => (FormatAlign::Right, &spec[1..]),
+        Some('^') => (FormatAlign::Center, &spec[1..]),
+        _ => (FormatAlign::Left, spec),
+    };
+    let (width, max) = match width.split_once('.') {
+        Some((width, max)) => (width, Some(max)),
+        None => (width, None),
+    };
+    let parse = |number: &str| {
+        number
+            .parse()
+            .map_err(|_| FormatError::InvalidSpec(spec.to_string()))
+    };
+    let width = match width {
+        "" => 0,
+        width => parse(width)?,
+    };
+    let max = max.map(parse).transpose()?;
+
+    Ok(FormatSegment::Field {
+        field,
+        align,
+        width,
+        max,
+    })
+}
+
+/// Translates a strftime-like time format into a format description of the `time` crate
+pub(crate) fn parse_time_format(format: &str) -> Result<OwnedFormatItem, FormatError> {
+    let mut description = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '%' => {}
+            '[' => {
+                description.push_str("[[");
+                continue;
+            }
+            c => {
+                description.push(c);
+                continue;
+            }
+        }
+
+        let mut spec = String::from("%");
+        let mut next = || {
+            let c = chars.next();
+            spec.extend(c);
+            c
+        };
+        let mut c = next();
+        let padding = match c {
+            Some('-') => Some("none"),
+            Some('_') => Some("space"),
+            Some('0') => Some("zero"),
+            _ => None,
+        };
+        if padding.is_some() {
+            c = next();
+        }
+
+        let numeric = match c {
+            Some('Y') => Some(("year", "zero")),
+            Some('y') => Some(("year repr:last_two", "zero")),
+            Some('m') => Some(("month", "zero")),
+            Some('d') => Some(("day", "zero")),
+            Some('e') => Some(("day", "space")),
+            Some('j') => Some(("ordinal", "zero")),
+            Some('H') => Some(("hour", "zero")),
+            Some('k') => Some(("hour", "space")),
+            Some('I') => Some(("hour repr:12", "zero")),
+            Some('l') => Some(("hour repr:12", "space")),
+            Some('M') => Some(("minute", "zero")),
+            Some('S') => Some(("second", "zero")),
+            _ => None,
+        };
+        let item = match (numeric, padding, c) {
+            (Some((component, default)), padding, _) => {
+                format!("[{} padding:{}]", component, padding.unwrap_or(default))
+            }
+            (None, None, Some(c)) => match c {
+                'b' | 'h' => String::from("[month repr:short]"),
+                'B' => String::from("[month repr:long]"),
+                'a' => String::from("[weekday repr:short]"),
+                'A' => String::from("[weekday repr:long]"),
+                'u' => String::from("[weekday repr:monday]"),
+                'w' => String::from("[weekday repr:sunday one_indexed:false]"),
+                'p' => String::from("[period]"),
+                'P' => String::from("[period case:lower]"),
+                'f' => String::from("[subsecond digits:9]"),
+                's' => String::from("[unix_timestamp]"),
+                'z' => String::from("[offset_hour sign:mandatory][offset_minute]"),
+                'F' => String::from("[year]-[month]-[day]"),
+                'D' => String::from("[month]/[day]/[year repr:last_two]"),
+                'T' => String::from("[hour]:[minute]:[second]"),
+                'R' => String::from("[hour]:[minute]"),
+                'n' => String::from("\n"),
+                't' => String::from("\t"),
+                '%' => String::from("%"),
+                ':' if next() == Some('z') => {
+                    String::from("[offset_hour sign:mandatory]:[offset_minute]")
+                }
+                '.' => match next() {
+                    Some('f') => String::from(".[subsecond]"),
+                    Some(digits @ ('3' | '6' | '9')) if next() == Some('f') => {
+                        format!(".[subsecond digits:{}]", digits)
+                    }
+                    _ => return Err(FormatError::UnknownSpecifier(spec)),
+                },
+                digits @ ('3' | '6' | '9') if next() == Some('f') => {
+                    format!("[subsecond digits:{}]", digits)
+                }
+                _ => return Err(FormatError::UnknownSpecifier(spec)),
+            },
+            _ => return Err(FormatError::UnknownSpecifier(spec)),
+        };
+        description.push_str(&item);
+    }
+
+    time::format_description::parse_owned::<1>(&description)
+        .map_err(|_| FormatError::UnknownSpecifier(format.to_string()))
+}
+
+/// Parses level filters written in `env_logger`'s `RUST_LOG` directive syntax,
+/// see `ConfigBuilder::add_level_filters_from_spec`
+pub(crate) fn parse_level_filters(spec: &str) -> Vec<(String, LevelFilter)> {
+    let spec = spec.split('/').next().unwrap_or_default();
+    let mut list = Vec::new();
+    for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        list.push(match directive.split_once('=') {
+            Some((target, level)) => match level.trim().parse() {
+                Ok(level) => (target.trim().to_string(), level),
+                Err(_) => continue,
+            },
+            None => match directive.parse() {
+                Ok(level) => (String::new(), level),
+                Err(_) => (directive.to_string(), LevelFilter::Trace),
+            },
+        });
+    }
+    list
 }
 
 /// UTF-8 end of line character sequences
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LineEnding {
     /// Line feed
     LF,
@@ -91,25 +717,78 @@ pub enum LineEnding {
 #[derive(Debug, Clone)]
 pub struct Config {
     pub(crate) time: LevelFilter,
+    pub(crate) hostname: LevelFilter,
+    pub(crate) hostname_override: Option<String>,
     pub(crate) level: LevelFilter,
     pub(crate) level_padding: LevelPadding,
+    pub(crate) level_labels: [Cow<'static, str>; 5],
     pub(crate) thread: LevelFilter,
     pub(crate) thread_log_mode: ThreadLogMode,
+    pub(crate) location_mode: LocationMode,
     pub(crate) thread_padding: ThreadPadding,
+    pub(crate) pid: LevelFilter,
+    pub(crate) stderr: LevelFilter,
     pub(crate) target: LevelFilter,
     pub(crate) target_padding: TargetPadding,
     pub(crate) location: LevelFilter,
     pub(crate) module: LevelFilter,
+    pub(crate) backtrace: LevelFilter,
     pub(crate) time_format: TimeFormat,
     pub(crate) time_offset: UtcOffset,
+    #[cfg_attr(not(feature = "local-offset"), allow(dead_code))]
+    pub(crate) time_local: bool,
     pub(crate) filter_allow: Cow<'static, [Cow<'static, str>]>,
     pub(crate) filter_ignore: Cow<'static, [Cow<'static, str>]>,
+    #[cfg(feature = "regex")]
+    pub(crate) filter_allow_regex: Vec<Regex>,
+    #[cfg(feature = "regex")]
+    pub(crate) filter_ignore_regex: Vec<Regex>,
+    #[cfg(feature = "regex")]
+    pub(crate) message_filter_allow: Vec<Regex>,
+    #[cfg(feature = "regex")]
+    pub(crate) message_filter_ignore: Vec<Regex>,
+    pub(crate) level_filters: Cow<'static, [(Cow<'static, str>, LevelFilter)]>,
+    pub(crate) runtime_level_filters: Option<RuntimeLevelFilters>,
+    #[cfg(feature = "termcolor")]
+    pub(crate) color_theme: ColorTheme,
+    #[cfg(feature = "termcolor")]
+    pub(crate) color_callback: Option<ColorCallback>,
     #[cfg(feature = "termcolor")]
-    pub(crate) level_color: [Option<Color>; 6],
+    pub(crate) honor_color_env: bool,
+    #[cfg(feature = "termcolor")]
+    pub(crate) location_link: Option<String>,
+    #[cfg_attr(not(feature = "termcolor"), allow(dead_code))]
     pub(crate) write_log_enable_colors: bool,
     #[cfg(feature = "paris")]
     pub(crate) enable_paris_formatting: bool,
     pub(crate) line_ending: String,
+    pub(crate) format: Option<Vec<FormatSegment>>,
+    pub(crate) level_formats: Vec<(Level, Vec<FormatSegment>)>,
+    pub(crate) formatter: Option<CustomFormatter>,
+    pub(crate) cef: Option<CefHeader>,
+    pub(crate) continuation_prefix: Option<String>,
+    pub(crate) max_message_len: Option<usize>,
+    pub(crate) file_locking: bool,
+    pub(crate) max_rotated_files: Option<usize>,
+    pub(crate) max_rotated_bytes: Option<u64>,
+    pub(crate) rotated_name: Option<Vec<NamePart>>,
+    pub(crate) file_truncate: bool,
+    pub(crate) create_dirs: bool,
+    #[cfg(unix)]
+    pub(crate) file_mode: Option<u32>,
+    #[cfg(feature = "flate2")]
+    pub(crate) compress_rotated: bool,
+    #[cfg(feature = "flate2")]
+    pub(crate) compression_error_handler: Option<CompressionErrorHandler>,
+    pub(crate) file_error_handler: Option<FileErrorHandler>,
+    pub(crate) print_gate: Option<PrintGate>,
+    pub(crate) rate_limit: Option<RateLimiter>,
+    pub(crate) fold_repeats: Option<RepeatFolder>,
+    pub(crate) sampling: Option<Sampler>,
+    pub(crate) redactor: Option<Redactor>,
+    pub(crate) transforms: Vec<Transform>,
+    pub(crate) record_filters: Vec<RecordFilter>,
+    pub(crate) flush_policy: FlushPolicy,
 }
 
 impl Config {
@@ -117,6 +796,63 @@ impl Config {
     pub fn builder() -> ConfigBuilder {
         ConfigBuilder::new()
     }
+
+    /// Create a default `Config` with level filters parsed from the `RUST_LOG` environment variable
+    ///
+    /// See [`ConfigBuilder::add_level_filters_from_env`] for details.
+    pub fn from_env() -> Config {
+        ConfigBuilder::new().add_level_filters_from_env().build()
+    }
+
+    /// Create a `Config` writing nothing but the message of every record
+    pub fn minimal() -> Config {
+        ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_max_level(LevelFilter::Off)
+            .set_thread_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Off)
+            .set_location_level(LevelFilter::Off)
+            .build()
+    }
+
+    /// Create a `Config` writing everything known about every record: the time with microseconds,
+    /// the level, process and thread (name and ID), the target, the location and the module
+    pub fn verbose() -> Config {
+        ConfigBuilder::new()
+            .set_time_format_rfc3339_precision(TimePrecision::Micros)
+            .set_time_level(LevelFilter::Error)
+            .set_max_level(LevelFilter::Error)
+            .set_pid_level(LevelFilter::Error)
+            .set_thread_level(LevelFilter::Error)
+            .set_thread_mode(ThreadLogMode::Both)
+            .set_target_level(LevelFilter::Error)
+            .set_location_level(LevelFilter::Error)
+            .set_module_level(LevelFilter::Error)
+            .build()
+    }
+
+    /// Create a `Config` suitable for services: the time in UTC according to RFC 3339 with milliseconds,
+    /// the level and the target of every record, but no thread or location
+    pub fn production() -> Config {
+        ConfigBuilder::new()
+            .set_time_format_rfc3339_precision(TimePrecision::Millis)
+            .set_time_offset(UtcOffset::UTC)
+            .set_time_level(LevelFilter::Error)
+            .set_max_level(LevelFilter::Error)
+            .set_thread_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Error)
+            .set_location_level(LevelFilter::Off)
+            .build()
+    }
+
+    /// The template records of `level` are written with, if any
+    pub(crate) fn format_of(&self, level: Level) -> Option<&[FormatSegment]> {
+        self.level_formats
+            .iter()
+            .find(|(l, _)| *l == level)
+            .map(|(_, format)| &**format)
+            .or(self.format.as_deref())
+    }
 }
 
 /// Builder for the Logger Configurations (`Config`)
@@ -157,6 +893,58 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set a prefix written at the start of every continuation line of a message spanning multiple lines,
+    /// e.g. `"  | "`, so the lines stay visually attached to their record and can be
+    /// told apart from the start of a new one by line oriented tools (default is none).
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// let config = ConfigBuilder::new()
+    ///     .set_continuation_prefix("  | ")
+    ///     .build();
+    /// ```
+    pub fn set_continuation_prefix<S: Into<String>>(&mut self, prefix: S) -> &mut ConfigBuilder {
+        self.0.continuation_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Removes a prefix set by [`ConfigBuilder::set_continuation_prefix`]
+    pub fn clear_continuation_prefix(&mut self) -> &mut ConfigBuilder {
+        self.0.continuation_prefix = None;
+        self
+    }
+
+    /// Set the maximum length of a message in bytes (default is unlimited).
+    ///
+    /// Longer messages are cut off at the last character fitting into the limit, followed by
+    /// an ellipsis and their original length, e.g. `Response: {"items":[… (12345678 bytes)`,
+    /// which protects terminals and network sinks from accidentally logged huge payloads.
+    /// Key-values and backtraces are not counted.
+    pub fn set_max_message_len(&mut self, max_len: Option<usize>) -> &mut ConfigBuilder {
+        self.0.max_message_len = max_len;
+        self
+    }
+
+    /// Set a custom sequence terminating every record, e.g. `"\r\n\r\n"` to separate records by empty lines.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// let config = ConfigBuilder::new()
+    ///     .set_line_ending_custom("\u{1e}")
+    ///     .build();
+    /// ```
+    pub fn set_line_ending_custom<S: Into<String>>(
+        &mut self,
+        line_ending: S,
+    ) -> &mut ConfigBuilder {
+        self.0.line_ending = line_ending.into();
+        self
+    }
+
     /// Set at which level and above (more verbose) the level itself shall be logged (default is Error)
     pub fn set_max_level(&mut self, level: LevelFilter) -> &mut ConfigBuilder {
         self.0.level = level;
@@ -169,12 +957,43 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set at which level and above (more verbose) the name of the host shall be logged. (default is Off)
+    pub fn set_hostname_level(&mut self, hostname: LevelFilter) -> &mut ConfigBuilder {
+        self.0.hostname = hostname;
+        self
+    }
+
+    /// Overrides the name of the host, that is logged and sent by network loggers like the `GelfLogger`.
+    ///
+    /// By default the name is looked up once, using the `HOSTNAME` environment variable,
+    /// `/etc/hostname` or the `COMPUTERNAME` environment variable, whichever is found first.
+    pub fn set_hostname<S: Into<String>>(&mut self, hostname: S) -> &mut ConfigBuilder {
+        self.0.hostname_override = Some(hostname.into());
+        self
+    }
+
     /// Set at which level and above (more verbose) the thread id shall be logged. (default is Debug)
     pub fn set_thread_level(&mut self, thread: LevelFilter) -> &mut ConfigBuilder {
         self.0.thread = thread;
         self
     }
 
+    /// Set at which level and above (more verbose) the id of the current process shall be logged. (default is Off)
+    pub fn set_pid_level(&mut self, pid: LevelFilter) -> &mut ConfigBuilder {
+        self.0.pid = pid;
+        self
+    }
+
+    /// Set up to which level records are written to stderr instead of stdout
+    /// by the `SimpleLogger` and the `TermLogger` in `TerminalMode::Mixed`. (default is Error)
+    ///
+    /// E.g. `LevelFilter::Warn` writes errors and warnings to stderr, `LevelFilter::Off` writes
+    /// all records to stdout and `LevelFilter::Trace` all records to stderr.
+    pub fn set_stderr_level(&mut self, stderr: LevelFilter) -> &mut ConfigBuilder {
+        self.0.stderr = stderr;
+        self
+    }
+
     /// Set at which level and above (more verbose) the target shall be logged. (default is Debug)
     pub fn set_target_level(&mut self, target: LevelFilter) -> &mut ConfigBuilder {
         self.0.target = target;
@@ -193,12 +1012,51 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set how the file of a source code reference shall be logged (default is the full path)
+    pub fn set_location_mode(&mut self, mode: LocationMode) -> &mut ConfigBuilder {
+        self.0.location_mode = mode;
+        self
+    }
+
     /// Set at which level and above (more verbose) a module shall be logged (default is Off)
     pub fn set_module_level(&mut self, module: LevelFilter) -> &mut ConfigBuilder {
         self.0.module = module;
         self
     }
 
+    /// Set at which level and below (more severe) a backtrace shall be appended to the message (default is Off)
+    ///
+    /// E.g. `set_backtrace_level(LevelFilter::Error)` captures a backtrace for every error record.
+    /// Backtraces are captured regardless of the `RUST_BACKTRACE` environment variable,
+    /// which makes logging noticeably slower.
+    pub fn set_backtrace_level(&mut self, backtrace: LevelFilter) -> &mut ConfigBuilder {
+        self.0.backtrace = backtrace;
+        self
+    }
+
+    /// Set the label written for `level` instead of its name, e.g. `"WRN"` for `Level::Warn`
+    ///
+    /// Padded levels are padded to the length of the longest label.
+    pub fn set_level_label<S>(&mut self, level: Level, label: S) -> &mut ConfigBuilder
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        self.0.level_labels[level as usize - 1] = label.into();
+        self
+    }
+
+    /// Write a symbol for every level (✖ for Error, ⚠ for Warn, ℹ for Info, ● for Debug
+    /// and ○ for Trace) before or instead of the labels set so far
+    pub fn set_level_symbols(&mut self, symbols: LevelSymbols) -> &mut ConfigBuilder {
+        for (label, symbol) in self.0.level_labels.iter_mut().zip(LEVEL_SYMBOLS) {
+            *label = match symbols {
+                LevelSymbols::Before => Cow::Owned(format!("{} {}", symbol, label)),
+                LevelSymbols::Instead => Cow::Borrowed(symbol),
+            };
+        }
+        self
+    }
+
     /// Set how the levels should be padded, when logging (default is Off)
     pub fn set_level_padding(&mut self, padding: LevelPadding) -> &mut ConfigBuilder {
         self.0.level_padding = padding;
@@ -221,52 +1079,352 @@ impl ConfigBuilder {
     /// or None to use the default foreground color
     #[cfg(feature = "termcolor")]
     pub fn set_level_color(&mut self, level: Level, color: Option<Color>) -> &mut ConfigBuilder {
-        self.0.level_color[level as usize] = color;
+        self.0.color_theme.levels[level as usize - 1].set_fg(color);
         self
     }
 
-    /// Sets the time format to a custom representation.
-    ///
-    /// The easiest way to satisfy the static lifetime of the argument is to directly use the
-    /// re-exported [`time::macros::format_description`] macro.
+    /// Set if a `TermLogger` created with `ColorChoice::Auto` shall honor the `NO_COLOR`,
+    /// `CLICOLOR_FORCE` and `CLICOLOR` environment variables and detect dumb terminals
+    /// and CI systems like GitHub Actions (default is true)
+    #[cfg(feature = "termcolor")]
+    pub fn set_honor_color_env(&mut self, honor: bool) -> &mut ConfigBuilder {
+        self.0.honor_color_env = honor;
+        self
+    }
+
+    /// Set a URL template the `TermLogger` links the source code reference of a record to,
+    /// so clicking it in terminals supporting OSC 8 hyperlinks opens the source (default is no links).
     ///
-    /// *Note*: The default time format is `[hour]:[minute]:[second]`.
+    /// `{path}` is replaced by the absolute path of the file, always starting with `/`,
+    /// and `{line}` by the line, e.g. `"vscode://file{path}:{line}"` or `"file://{path}"`.
+    /// Relative paths are resolved against the current directory.
     ///
-    /// The syntax for the format_description macro can be found in the
-    /// [`time` crate book](https://time-rs.github.io/book/api/format-description.html).
+    /// Links are only written to streams, colors are written to (see `ColorChoice`), and not
+    /// if `TERM` is `dumb`. Terminals without support for OSC 8 usually print the reference without the link.
+    /// Templates set by [`ConfigBuilder::set_format`] are written without links.
     ///
     /// # Usage
     ///
     /// ```
-    /// # use simplelog::{ConfigBuilder, format_description};
+    /// # use simplelog::ConfigBuilder;
     /// let config = ConfigBuilder::new()
-    ///     .set_time_format_custom(format_description!("[hour]:[minute]:[second].[subsecond]"))
+    ///     .set_location_link("vscode://file{path}:{line}")
     ///     .build();
     /// ```
-    pub fn set_time_format_custom(
-        &mut self,
-        time_format: &'static [FormatItem<'static>],
-    ) -> &mut ConfigBuilder {
-        self.0.time_format = TimeFormat::Custom(time_format);
+    #[cfg(feature = "termcolor")]
+    pub fn set_location_link<S: Into<String>>(&mut self, template: S) -> &mut ConfigBuilder {
+        self.0.location_link = Some(template.into());
         self
     }
 
-    /// Set time format string to use rfc2822.
-    pub fn set_time_format_rfc2822(&mut self) -> &mut ConfigBuilder {
-        self.0.time_format = TimeFormat::Rfc2822;
+    /// Removes a link template set by [`ConfigBuilder::set_location_link`]
+    #[cfg(feature = "termcolor")]
+    pub fn clear_location_link(&mut self) -> &mut ConfigBuilder {
+        self.0.location_link = None;
         self
     }
 
-    /// Set time format string to use rfc3339.
-    pub fn set_time_format_rfc3339(&mut self) -> &mut ConfigBuilder {
-        self.0.time_format = TimeFormat::Rfc3339;
+    /// Set the colors and styles used for printing the levels (if the logger supports it),
+    /// replacing colors set by [`ConfigBuilder::set_level_color`]
+    #[cfg(feature = "termcolor")]
+    pub fn set_color_theme(&mut self, theme: ColorTheme) -> &mut ConfigBuilder {
+        self.0.color_theme = theme;
         self
     }
 
-    /// Set offset used for logging time (default is UTC)
-    pub fn set_time_offset(&mut self, offset: UtcOffset) -> &mut ConfigBuilder {
-        self.0.time_offset = offset;
-        self
+    /// Set a callback choosing the style used for printing the level of a record (if the logger supports it),
+    /// e.g. depending on its target or message. If it returns `None`, the style of the theme is used.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::{Color, ColorSpec, ConfigBuilder};
+    /// # use log::Record;
+    /// let config = ConfigBuilder::new()
+    ///     .set_color_callback(|record: &Record<'_>| {
+    ///         record
+    ///             .target()
+    ///             .starts_with("payments::")
+    ///             .then(|| ColorSpec::new().set_fg(Some(Color::Magenta)).clone())
+    ///     })
+    ///     .build();
+    /// ```
+    #[cfg(feature = "termcolor")]
+    pub fn set_color_callback<F>(&mut self, callback: F) -> &mut ConfigBuilder
+    where
+        F: Fn(&Record<'_>) -> Option<ColorSpec> + Send + Sync + 'static,
+    {
+        self.0.color_callback = Some(ColorCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Removes a callback set by [`ConfigBuilder::set_color_callback`]
+    #[cfg(feature = "termcolor")]
+    pub fn clear_color_callback(&mut self) -> &mut ConfigBuilder {
+        self.0.color_callback = None;
+        self
+    }
+
+    /// Sets the time format to a custom representation.
+    ///
+    /// The easiest way to satisfy the static lifetime of the argument is to directly use the
+    /// re-exported [`time::macros::format_description`] macro.
+    ///
+    /// *Note*: The default time format is `[hour]:[minute]:[second]`.
+    ///
+    /// The syntax for the format_description macro can be found in the
+    /// [`time` crate book](https://time-rs.github.io/book/api/format-description.html).
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::{ConfigBuilder, format_description};
+    /// let config = ConfigBuilder::new()
+    ///     .set_time_format_custom(format_description!("[hour]:[minute]:[second].[subsecond]"))
+    ///     .build();
+    /// ```
+    pub fn set_time_format_custom(
+        &mut self,
+        time_format: &'static [FormatItem<'static>],
+    ) -> &mut ConfigBuilder {
+        self.0.time_format = TimeFormat::Custom(time_format);
+        self
+    }
+
+    /// Sets a template describing the layout of every record, replacing the default layout.
+    ///
+    /// Fields are referenced by name in curly braces, optionally followed by an alignment
+    /// (`<` left, `>` right, `^` center), a minimum width and a maximum width after a `.`, like in Rust's `format!`.
+    /// Longer values are truncated by replacing their beginning with `…`, keeping their most specific part,
+    /// e.g. `{target:<9.9}` writes `my_app::db::pool` as `…db::pool`.
+    /// Use `{{` and `}}` to print literal braces. The available fields are
+    /// `time`, `hostname`, `level`, `thread`, `pid`, `target`, `location` (`file:line`), `file`, `line`, `module`,
+    /// `message` and `kv` (the key-values of the record as `key=value` pairs).
+    ///
+    /// Fields referenced by the template are always printed, the levels set via
+    /// `set_time_level`, `set_target_level`, etc. only apply to the default layout.
+    /// The line ending is appended after the template.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// let config = ConfigBuilder::new()
+    ///     .set_format("{time} [{level:<5}] {target} — {message}")
+    ///     .unwrap()
+    ///     .build();
+    /// ```
+    pub fn set_format(&mut self, format: &str) -> Result<&mut ConfigBuilder, FormatError> {
+        self.0.format = Some(parse_format(format)?);
+        Ok(self)
+    }
+
+    /// Sets a template writing the time, level, target and location in columns of fixed width,
+    /// so the messages of all records start in the same column (see [`ConfigBuilder::set_format`]).
+    ///
+    /// Targets and locations are padded or truncated to `target_width` and `location_width` characters,
+    /// a width of `0` leaves out the column.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// // 12:00:00 [INFO ] my_app::db::pool         src/db/pool.rs:42    connected
+    /// let config = ConfigBuilder::new()
+    ///     .set_column_layout(24, 20)
+    ///     .build();
+    /// ```
+    pub fn set_column_layout(
+        &mut self,
+        target_width: usize,
+        location_width: usize,
+    ) -> &mut ConfigBuilder {
+        let mut format = String::from("{time} [{level:<5}] ");
+        if target_width > 0 {
+            format.push_str(&format!("{{target:<{0}.{0}}} ", target_width));
+        }
+        if location_width > 0 {
+            format.push_str(&format!("{{location:<{0}.{0}}} ", location_width));
+        }
+        let mut format = parse_format(&format).expect("valid column layout");
+        format.push(FormatSegment::Field {
+            field: FormatField::Args,
+            align: FormatAlign::Left,
+            width: 0,
+            max: None,
+        });
+        self.0.format = Some(format);
+        self
+    }
+
+    /// Removes a template set by [`ConfigBuilder::set_format`], restoring the default layout
+    pub fn clear_format(&mut self) -> &mut ConfigBuilder {
+        self.0.format = None;
+        self
+    }
+
+    /// Sets a template describing the layout of records of `level`, taking precedence over
+    /// the one set by [`ConfigBuilder::set_format`] and the default layout.
+    ///
+    /// See [`ConfigBuilder::set_format`] for the syntax of the template.
+    /// This allows e.g. writing the location only for errors, while other records stay terse.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// # use log::Level;
+    /// let config = ConfigBuilder::new()
+    ///     .set_format("{time} [{level}] {message}")
+    ///     .unwrap()
+    ///     .set_level_format(Level::Error, "{time} [{level}] {target} ({location}): {message} {kv}")
+    ///     .unwrap()
+    ///     .build();
+    /// ```
+    pub fn set_level_format(
+        &mut self,
+        level: Level,
+        format: &str,
+    ) -> Result<&mut ConfigBuilder, FormatError> {
+        let format = parse_format(format)?;
+        self.0.level_formats.retain(|(l, _)| *l != level);
+        self.0.level_formats.push((level, format));
+        Ok(self)
+    }
+
+    /// Removes all templates set by [`ConfigBuilder::set_level_format`]
+    pub fn clear_level_formats(&mut self) -> &mut ConfigBuilder {
+        self.0.level_formats.clear();
+        self
+    }
+
+    /// Sets a formatter writing records in a fully custom layout, taking precedence over
+    /// templates and the default layout. See [`Formatter`] for details.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// # use log::Record;
+    /// # use std::io::Write;
+    /// let config = ConfigBuilder::new()
+    ///     .set_formatter(|record: &Record<'_>, buf: &mut dyn Write| {
+    ///         write!(buf, "{} {} {}", record.level().as_str().to_lowercase(), record.target(), record.args())
+    ///     })
+    ///     .build();
+    /// ```
+    pub fn set_formatter<F>(&mut self, formatter: F) -> &mut ConfigBuilder
+    where
+        F: Formatter + 'static,
+    {
+        self.0.formatter = Some(CustomFormatter(Arc::new(formatter)));
+        self
+    }
+
+    /// Removes a formatter set by [`ConfigBuilder::set_formatter`], restoring the default layout
+    pub fn clear_formatter(&mut self) -> &mut ConfigBuilder {
+        self.0.formatter = None;
+        self
+    }
+
+    /// Writes records in the Common Event Format (CEF), as understood by SIEMs like ArcSight or QRadar,
+    /// instead of the default layout or a template set by [`ConfigBuilder::set_format`].
+    ///
+    /// Every record becomes a line like
+    /// `CEF:0|<vendor>|<product>|<version>|<target>|<message>|<severity>|rt=<time> dvchost=<hostname> dvcpid=<pid>`,
+    /// followed by the key-values of the record. The severity ranges from `0` for `Trace` to `8` for `Error`,
+    /// the time is given in milliseconds since the unix epoch.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// let config = ConfigBuilder::new()
+    ///     .set_cef_format("Acme", "Gateway", env!("CARGO_PKG_VERSION"))
+    ///     .build();
+    /// ```
+    pub fn set_cef_format<V, P, S>(
+        &mut self,
+        vendor: V,
+        product: P,
+        version: S,
+    ) -> &mut ConfigBuilder
+    where
+        V: Into<String>,
+        P: Into<String>,
+        S: Into<String>,
+    {
+        self.0.cef = Some(CefHeader {
+            vendor: vendor.into(),
+            product: product.into(),
+            version: version.into(),
+        });
+        self
+    }
+
+    /// Stops writing records in the Common Event Format, restoring the previous layout
+    pub fn clear_cef_format(&mut self) -> &mut ConfigBuilder {
+        self.0.cef = None;
+        self
+    }
+
+    /// Sets the time format using a strftime-like format string, e.g. `"%Y-%m-%d %H:%M:%S%.3f"`.
+    ///
+    /// Supported are the date specifiers `%Y`, `%y`, `%m`, `%b`, `%B`, `%d`, `%e`, `%j`,
+    /// `%a`, `%A`, `%u`, `%w`, the time specifiers `%H`, `%k`, `%I`, `%l`, `%M`, `%S`, `%p`, `%P`,
+    /// the fractional seconds `%f` (nanoseconds), `%3f`, `%6f`, `%9f`, `%.3f`, `%.6f`, `%.9f`
+    /// and `%.f` (as many digits as needed, including the dot), the offset `%z` (`+0100`)
+    /// and `%:z` (`+01:00`), the unix timestamp `%s`, the shortcuts `%F`, `%D`, `%T`, `%R`,
+    /// as well as `%n`, `%t` and `%%`.
+    /// The padding of numeric values can be changed using the flags `-` (none), `_` (spaces)
+    /// and `0` (zeros), e.g. `%-d`.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// let config = ConfigBuilder::new()
+    ///     .set_time_format_str("%Y-%m-%d %H:%M:%S%.3f")
+    ///     .unwrap()
+    ///     .build();
+    /// ```
+    pub fn set_time_format_str(
+        &mut self,
+        time_format: &str,
+    ) -> Result<&mut ConfigBuilder, FormatError> {
+        self.0.time_format = TimeFormat::Owned(parse_time_format(time_format)?);
+        Ok(self)
+    }
+
+    /// Set time format string to use rfc2822.
+    pub fn set_time_format_rfc2822(&mut self) -> &mut ConfigBuilder {
+        self.0.time_format = TimeFormat::Rfc2822;
+        self
+    }
+
+    /// Set time format string to use rfc3339.
+    pub fn set_time_format_rfc3339(&mut self) -> &mut ConfigBuilder {
+        self.0.time_format = TimeFormat::Rfc3339;
+        self
+    }
+
+    /// Set time format to rfc3339 with a fixed amount of fractional digits,
+    /// e.g. `2024-05-03T12:34:56.123Z` for [`TimePrecision::Millis`].
+    ///
+    /// Other than [`ConfigBuilder::set_time_format_rfc3339`], which omits trailing zeros,
+    /// all timestamps have the same length, so they are sorted correctly as text.
+    pub fn set_time_format_rfc3339_precision(
+        &mut self,
+        precision: TimePrecision,
+    ) -> &mut ConfigBuilder {
+        self.0.time_format = TimeFormat::Rfc3339Precision(precision);
+        self
+    }
+
+    /// Set offset used for logging time (default is UTC)
+    pub fn set_time_offset(&mut self, offset: UtcOffset) -> &mut ConfigBuilder {
+        self.0.time_offset = offset;
+        self
     }
 
     /// Sets the offset used to the current local time offset
@@ -287,6 +1445,25 @@ impl ConfigBuilder {
         }
     }
 
+    /// Set if times shall be written in local time, instead of the offset set by
+    /// [`ConfigBuilder::set_time_offset`] (default is false, writing UTC).
+    ///
+    /// Other than [`ConfigBuilder::set_time_offset_to_local`], the local offset is determined
+    /// for every record, so changes like the begin of daylight saving time are taken into account.
+    /// The same restrictions apply though: if the offset cannot be determined soundly,
+    /// e.g. because the program is multi-threaded on some Unix systems, the local offset
+    /// determined by this function is used, falling back to the one set before.
+    #[cfg(feature = "local-offset")]
+    pub fn set_time_local(&mut self, local: bool) -> &mut ConfigBuilder {
+        self.0.time_local = local;
+        if local {
+            if let Ok(offset) = UtcOffset::current_local_offset() {
+                self.0.time_offset = offset;
+            }
+        }
+        self
+    }
+
     /// set if you want to write colors in the logfile (default is Off)
     #[cfg(feature = "ansi_term")]
     pub fn set_write_log_enable_colors(&mut self, local: bool) -> &mut ConfigBuilder {
@@ -303,6 +1480,284 @@ impl ConfigBuilder {
         self
     }
 
+    /// set if the `FileLogger` holds an advisory lock on its file while writing a record (default is Off)
+    ///
+    /// Enable this, if multiple processes append to the same file,
+    /// so their records don't interleave. All of them need to use locking for this to work.
+    pub fn set_file_locking(&mut self, locking: bool) -> &mut ConfigBuilder {
+        self.0.file_locking = locking;
+        self
+    }
+
+    /// set if an existing log file is truncated instead of appended to, when it is opened
+    /// by the `FileLogger` or [`WriteLogger::create`] (default is Off)
+    ///
+    /// [`WriteLogger::create`]: crate::WriteLogger::create
+    pub fn set_file_truncate(&mut self, truncate: bool) -> &mut ConfigBuilder {
+        self.0.file_truncate = truncate;
+        self
+    }
+
+    /// set if missing parent directories of a log file are created, when it is opened
+    /// by the `FileLogger` or [`WriteLogger::create`] (default is Off)
+    ///
+    /// [`WriteLogger::create`]: crate::WriteLogger::create
+    pub fn set_create_dirs(&mut self, create: bool) -> &mut ConfigBuilder {
+        self.0.create_dirs = create;
+        self
+    }
+
+    /// set the permissions of log files created by the `FileLogger` or [`WriteLogger::create`],
+    /// e.g. `0o600` to keep sensitive logs private (default is `0o666`, restricted by the umask)
+    ///
+    /// Existing files keep their permissions.
+    ///
+    /// [`WriteLogger::create`]: crate::WriteLogger::create
+    #[cfg(unix)]
+    pub fn set_file_mode(&mut self, mode: u32) -> &mut ConfigBuilder {
+        self.0.file_mode = Some(mode);
+        self
+    }
+
+    /// set the maximum amount of rotated files the `FileLogger` keeps (default is to keep all of them)
+    ///
+    /// The oldest rotated files exceeding the limit are removed on a background thread after every rotation.
+    pub fn set_max_rotated_files(&mut self, max_files: usize) -> &mut ConfigBuilder {
+        self.0.max_rotated_files = Some(max_files);
+        self
+    }
+
+    /// set the maximum total size in bytes of the rotated files the `FileLogger` keeps
+    /// (default is to keep all of them)
+    ///
+    /// The oldest rotated files exceeding the limit are removed on a background thread after every rotation.
+    /// The file currently written to is not included.
+    pub fn set_max_rotated_bytes(&mut self, max_bytes: u64) -> &mut ConfigBuilder {
+        self.0.max_rotated_bytes = Some(max_bytes);
+        self
+    }
+
+    /// set the naming pattern of files rotated by the `FileLogger`,
+    /// e.g. `{stem}-{date}-{index}.{ext}` to name the files `app-2024-05-03-1.log`, `app-2024-05-03-2.log`, ...
+    /// (default is to append a suffix, see [`Rotation`](crate::Rotation))
+    ///
+    /// The pattern is relative to the directory of the log file and may contain the following fields:
+    /// - `{name}`: the file name of the log file, e.g. `app.log`
+    /// - `{stem}`: the file name without its extension, e.g. `app`
+    /// - `{ext}`: the extension of the file name, e.g. `log`
+    /// - `{date}`: the period of a timed rotation (e.g. `2024-05-03-14` for hourly rotation)
+    ///   or the day of a size based rotation
+    /// - `{index}`: the lowest number starting from 1, that does not name an existing file
+    ///
+    /// Unlike the default naming, rotated files are never renamed again, so the highest index
+    /// is the most recent file. If the pattern has no `{index}`, a counter is appended
+    /// in case the file already exists.
+    ///
+    /// Fails if the pattern contains an unknown field.
+    pub fn set_rotated_name_pattern(
+        &mut self,
+        pattern: &str,
+    ) -> Result<&mut ConfigBuilder, FormatError> {
+        self.0.rotated_name = Some(parse_name_pattern(pattern)?);
+        Ok(self)
+    }
+
+    /// set if files rotated by the `FileLogger` shall be compressed using gzip (default is Off)
+    ///
+    /// Compression happens on a background thread and produces a file with an additional
+    /// `.gz` extension, the uncompressed file is removed afterwards.
+    #[cfg(feature = "flate2")]
+    pub fn set_compress_rotated(&mut self, compress: bool) -> &mut ConfigBuilder {
+        self.0.compress_rotated = compress;
+        self
+    }
+
+    /// Set a callback, that is invoked with the path of a rotated file and the error,
+    /// if compressing that file fails (default is to ignore the error)
+    #[cfg(feature = "flate2")]
+    pub fn set_compression_error_handler<F>(&mut self, handler: F) -> &mut ConfigBuilder
+    where
+        F: Fn(&Path, &io::Error) + Send + Sync + 'static,
+    {
+        self.0.compression_error_handler = Some(CompressionErrorHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Set a callback, that is invoked with the path of the log file and the error,
+    /// if the `FileLogger` fails to rotate or reopen it (default is to ignore the error)
+    ///
+    /// The logger keeps writing to the file it has open, so no records are lost,
+    /// a failed rotation is attempted again with the next record.
+    /// Every failure is also counted as a write error in the [`Stats`](crate::Stats).
+    pub fn set_file_error_handler<F>(&mut self, handler: F) -> &mut ConfigBuilder
+    where
+        F: Fn(&Path, &io::Error) + Send + Sync + 'static,
+    {
+        self.0.file_error_handler = Some(FileErrorHandler(Arc::new(handler)));
+        self
+    }
+
+    /// set when the `WriteLogger` writes buffered records into its writer (default is `FlushPolicy::EveryRecord`)
+    ///
+    /// Writing less often speeds up high-volume logging considerably,
+    /// records still buffered are written once the buffer is full or the logger is flushed.
+    /// The writer itself is only flushed when the logger is flushed, regardless of the policy.
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) -> &mut ConfigBuilder {
+        self.0.flush_policy = policy;
+        self
+    }
+
+    /// Set a callback, that the `TermLogger` and `SimpleLogger` call around writing a record,
+    /// passing a function doing the actual write (default is to write directly).
+    ///
+    /// This allows to hide and redraw progress bars or other output drawn on the terminal,
+    /// so records do not tear through them.
+    ///
+    /// # Usage
+    ///
+    /// ```
+    /// # use simplelog::ConfigBuilder;
+    /// # struct ProgressBar;
+    /// # impl ProgressBar { fn suspend<F: FnOnce()>(&self, f: F) { f() } }
+    /// let progress = ProgressBar;
+    /// let config = ConfigBuilder::new()
+    ///     .set_print_gate(move |print| progress.suspend(print))
+    ///     .build();
+    /// ```
+    pub fn set_print_gate<F>(&mut self, gate: F) -> &mut ConfigBuilder
+    where
+        F: Fn(&mut dyn FnMut()) + Send + Sync + 'static,
+    {
+        self.0.print_gate = Some(PrintGate(Arc::new(gate)));
+        self
+    }
+
+    /// Limit the records logged per target to `count` within `window` (default is no limit)
+    ///
+    /// Further records of a target are dropped until its window ends. The first record
+    /// of the target logged afterwards is preceded by a line saying how many messages were suppressed,
+    /// which keeps e.g. a warning logged in a hot loop from filling disks or flooding terminals.
+    ///
+    /// Every logger keeps its own count, even if built from clones of the same `Config`.
+    pub fn set_rate_limit(&mut self, count: u32, window: Duration) -> &mut ConfigBuilder {
+        self.0.rate_limit = Some(RateLimiter::new(count, window));
+        self
+    }
+
+    /// Fold consecutive records with the same level, target and message (default is to log all of them)
+    ///
+    /// Repeats of a record are dropped and reported as a single `last message repeated N times` line,
+    /// once a different record arrives. Repeats going on for longer than `timeout`
+    /// are reported by the next one arriving after it elapsed.
+    /// Repeats not reported yet, when the logger is dropped, are lost.
+    pub fn set_fold_repeats(&mut self, timeout: Duration) -> &mut ConfigBuilder {
+        self.0.fold_repeats = Some(RepeatFolder::new(timeout));
+        self
+    }
+
+    /// Only log one of every `every` records more verbose than `level` (default is to log all of them)
+    ///
+    /// Meant for high-volume `Debug` and `Trace` logging in production, e.g.
+    /// `set_sampling(LevelFilter::Info, 100)` keeps every hundredth `Debug` or `Trace` record,
+    /// while all others are passed through.
+    /// The records are counted without locking, regardless of their target.
+    pub fn set_sampling(&mut self, level: LevelFilter, every: u64) -> &mut ConfigBuilder {
+        self.0.sampling = Some(Sampler::new(level, every));
+        self
+    }
+
+    /// set a callback rewriting the message of every record, before any logger writes it,
+    /// e.g. to mask tokens, passwords or email addresses (default is to write messages as they are)
+    ///
+    /// The callback receives the formatted message and returns it unchanged as `Cow::Borrowed`
+    /// or the redacted message as `Cow::Owned`. Filters and the folding of repeated messages
+    /// still see the original message.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::borrow::Cow;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new()
+    ///     .set_redactor(|message| match message.find("password=") {
+    ///         Some(pos) => Cow::Owned(format!("{}password=***", &message[..pos])),
+    ///         None => Cow::Borrowed(message),
+    ///     })
+    ///     .build();
+    /// # }
+    /// ```
+    pub fn set_redactor<F>(&mut self, redactor: F) -> &mut ConfigBuilder
+    where
+        F: for<'a> Fn(&'a str) -> Cow<'a, str> + Send + Sync + 'static,
+    {
+        self.0.redactor = Some(Redactor(Arc::new(redactor)));
+        self
+    }
+
+    /// add a transform to the pipeline every record passes, before it is formatted.
+    /// See [`RecordTransform`] for details.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new()
+    ///     // drop the noisy health checks
+    ///     .add_transform(|record: &mut OwnedRecord| !record.message.starts_with("GET /health"))
+    ///     .add_transform(|record: &mut OwnedRecord| {
+    ///         record.key_values.push((String::from("service"), String::from("api")));
+    ///         true
+    ///     })
+    ///     .build();
+    /// # }
+    /// ```
+    pub fn add_transform<T>(&mut self, transform: T) -> &mut ConfigBuilder
+    where
+        T: RecordTransform + 'static,
+    {
+        self.0.transforms.push(Transform(Arc::new(transform)));
+        self
+    }
+
+    /// removes all transforms added by [`ConfigBuilder::add_transform`]
+    pub fn clear_transforms(&mut self) -> &mut ConfigBuilder {
+        self.0.transforms.clear();
+        self
+    }
+
+    /// add a filter deciding if a record is logged, for rules that cannot be expressed using levels and targets.
+    ///
+    /// The filter is called with every record passing the target filters, before it is formatted,
+    /// and returns `false` to drop it. All filters need to return `true` for a record to be logged.
+    /// Unlike a transform (see [`ConfigBuilder::add_transform`]) the record is not copied.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new()
+    ///     // drop records mentioning a customer id
+    ///     .add_record_filter(|record| !record.args().to_string().contains("customer="))
+    ///     .build();
+    /// # }
+    /// ```
+    pub fn add_record_filter<F>(&mut self, filter: F) -> &mut ConfigBuilder
+    where
+        F: Fn(&Record<'_>) -> bool + Send + Sync + 'static,
+    {
+        self.0.record_filters.push(RecordFilter(Arc::new(filter)));
+        self
+    }
+
+    /// removes all filters added by [`ConfigBuilder::add_record_filter`]
+    pub fn clear_record_filters(&mut self) -> &mut ConfigBuilder {
+        self.0.record_filters.clear();
+        self
+    }
+
     /// Add allowed target filters.
     /// If any are specified, only records from targets matching one of these entries will be printed
     ///
@@ -361,6 +1816,161 @@ impl ConfigBuilder {
         self
     }
 
+    /// Add an allowed target filter using a regular expression.
+    /// If any are specified, only records from targets matching one of these expressions will be printed.
+    /// Works in addition to the prefix filters of [`add_filter_allow`](ConfigBuilder::add_filter_allow),
+    /// a record has to pass both.
+    ///
+    /// For example, `add_filter_allow_regex("^(tokio|hyper)::")` would allow only logging from the `tokio` and `hyper` crates.
+    /// Fails if `pattern` is no valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn add_filter_allow_regex(
+        &mut self,
+        pattern: &str,
+    ) -> Result<&mut ConfigBuilder, regex::Error> {
+        self.0.filter_allow_regex.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Add a denied target filter using a regular expression.
+    /// If any are specified, records from targets matching one of these expressions will be ignored
+    ///
+    /// For example, `add_filter_ignore_regex("::(uds|tcp)$")` would deny logging from all `uds` and `tcp` modules.
+    /// Fails if `pattern` is no valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn add_filter_ignore_regex(
+        &mut self,
+        pattern: &str,
+    ) -> Result<&mut ConfigBuilder, regex::Error> {
+        self.0.filter_ignore_regex.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Add an allowed message filter using a regular expression.
+    /// If any are specified, only records with a formatted message matching one of these expressions will be printed
+    ///
+    /// Unlike target filters, message filters can only be applied once the record is logged,
+    /// so they do not influence `Log::enabled`.
+    /// Fails if `pattern` is no valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn add_message_filter_allow_regex(
+        &mut self,
+        pattern: &str,
+    ) -> Result<&mut ConfigBuilder, regex::Error> {
+        self.0.message_filter_allow.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Add a denied message filter using a regular expression.
+    /// If any are specified, records with a formatted message matching one of these expressions will be ignored
+    ///
+    /// For example, `add_message_filter_ignore_regex("(?i)health ?check")` would drop the noise of health checks.
+    /// Fails if `pattern` is no valid regular expression.
+    #[cfg(feature = "regex")]
+    pub fn add_message_filter_ignore_regex(
+        &mut self,
+        pattern: &str,
+    ) -> Result<&mut ConfigBuilder, regex::Error> {
+        self.0.message_filter_ignore.push(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Clear all target and message filters using regular expressions
+    #[cfg(feature = "regex")]
+    pub fn clear_regex_filters(&mut self) -> &mut ConfigBuilder {
+        self.0.filter_allow_regex.clear();
+        self.0.filter_ignore_regex.clear();
+        self.0.message_filter_allow.clear();
+        self.0.message_filter_ignore.clear();
+        self
+    }
+
+    /// Add a level filter for records of targets starting with the given prefix,
+    /// overriding the level passed to the logger for those targets.
+    ///
+    /// If multiple prefixes match a target, the longest one is used.
+    /// The level can be lower or higher than the level of the logger,
+    /// e.g. `add_level_filter_str("hyper", LevelFilter::Warn)` mutes hyper's info messages,
+    /// while `add_level_filter_str("my_app::db", LevelFilter::Trace)` enables tracing just for `my_app::db`.
+    pub fn add_level_filter_str(
+        &mut self,
+        target_prefix: &'static str,
+        level: LevelFilter,
+    ) -> &mut ConfigBuilder {
+        let mut list = Vec::from(&*self.0.level_filters);
+        list.push((Cow::Borrowed(target_prefix), level));
+        self.0.level_filters = Cow::Owned(list);
+        self
+    }
+
+    /// Add a level filter for records of targets starting with the given prefix,
+    /// overriding the level passed to the logger for those targets.
+    ///
+    /// See [`ConfigBuilder::add_level_filter_str`] for details.
+    pub fn add_level_filter(
+        &mut self,
+        target_prefix: String,
+        level: LevelFilter,
+    ) -> &mut ConfigBuilder {
+        let mut list = Vec::from(&*self.0.level_filters);
+        list.push((Cow::Owned(target_prefix), level));
+        self.0.level_filters = Cow::Owned(list);
+        self
+    }
+
+    /// Add level filters parsed from the `RUST_LOG` environment variable, if it is set.
+    ///
+    /// See [`ConfigBuilder::add_level_filters_from_spec`] for the supported syntax.
+    pub fn add_level_filters_from_env(&mut self) -> &mut ConfigBuilder {
+        match std::env::var("RUST_LOG") {
+            Ok(spec) => self.add_level_filters_from_spec(&spec),
+            Err(_) => self,
+        }
+    }
+
+    /// Add level filters parsed from `env_logger`'s `RUST_LOG` directive syntax.
+    ///
+    /// The spec is a comma separated list of directives of the form
+    /// `level` (setting the level for all targets), `target` (enabling all levels for `target`)
+    /// or `target=level`, e.g. `info,hyper=warn,my_app::db=trace`.
+    /// A level set for all targets overrides the level passed to the logger.
+    /// Invalid directives and `/filter` suffixes are ignored.
+    pub fn add_level_filters_from_spec(&mut self, spec: &str) -> &mut ConfigBuilder {
+        let mut list = Vec::from(&*self.0.level_filters);
+        for (target, level) in parse_level_filters(spec) {
+            list.push((Cow::Owned(target), level));
+        }
+        self.0.level_filters = Cow::Owned(list);
+        self
+    }
+
+    /// Clear level filters.
+    /// If none are specified, the level passed to the logger applies to all targets
+    pub fn clear_level_filters(&mut self) -> &mut ConfigBuilder {
+        self.0.level_filters = Cow::Borrowed(&[]);
+        self
+    }
+
+    /// make the logger follow level filters, that can be changed at runtime (default is none)
+    ///
+    /// The filters take precedence over the level and the level filters of the logger,
+    /// so they may make it more verbose than the level it was created with.
+    /// Loggers, whose config does not follow them, are not affected, e.g. a logger only
+    /// writing errors to a separate file keeps doing so, when debug messages are turned on.
+    pub fn set_runtime_level_filters(
+        &mut self,
+        filters: &RuntimeLevelFilters,
+    ) -> &mut ConfigBuilder {
+        self.0.runtime_level_filters = Some(filters.clone());
+        self
+    }
+
+    /// stop following the level filters set by `ConfigBuilder::set_runtime_level_filters`
+    pub fn clear_runtime_level_filters(&mut self) -> &mut ConfigBuilder {
+        self.0.runtime_level_filters = None;
+        self
+    }
+
     /// Build new `Config`
     pub fn build(&mut self) -> Config {
         self.0.clone()
@@ -377,34 +1987,84 @@ impl Default for Config {
     fn default() -> Config {
         Config {
             time: LevelFilter::Error,
+            hostname: LevelFilter::Off,
+            hostname_override: None,
             level: LevelFilter::Error,
             level_padding: LevelPadding::Off,
+            level_labels: [
+                Cow::Borrowed("ERROR"),
+                Cow::Borrowed("WARN"),
+                Cow::Borrowed("INFO"),
+                Cow::Borrowed("DEBUG"),
+                Cow::Borrowed("TRACE"),
+            ],
             thread: LevelFilter::Debug,
             thread_log_mode: ThreadLogMode::IDs,
+            location_mode: LocationMode::Full,
             thread_padding: ThreadPadding::Off,
+            pid: LevelFilter::Off,
+            stderr: LevelFilter::Error,
             target: LevelFilter::Debug,
             target_padding: TargetPadding::Off,
             location: LevelFilter::Trace,
             module: LevelFilter::Off,
+            backtrace: LevelFilter::Off,
             time_format: TimeFormat::Custom(format_description!("[hour]:[minute]:[second]")),
             time_offset: UtcOffset::UTC,
+            time_local: false,
             filter_allow: Cow::Borrowed(&[]),
             filter_ignore: Cow::Borrowed(&[]),
+            #[cfg(feature = "regex")]
+            filter_allow_regex: Vec::new(),
+            #[cfg(feature = "regex")]
+            filter_ignore_regex: Vec::new(),
+            #[cfg(feature = "regex")]
+            message_filter_allow: Vec::new(),
+            #[cfg(feature = "regex")]
+            message_filter_ignore: Vec::new(),
+            level_filters: Cow::Borrowed(&[]),
+            runtime_level_filters: None,
             write_log_enable_colors: false,
 
             #[cfg(feature = "termcolor")]
-            level_color: [
-                None,                // Default foreground
-                Some(Color::Red),    // Error
-                Some(Color::Yellow), // Warn
-                Some(Color::Blue),   // Info
-                Some(Color::Cyan),   // Debug
-                Some(Color::White),  // Trace
-            ],
+            color_theme: ColorTheme::default(),
+            #[cfg(feature = "termcolor")]
+            color_callback: None,
+            #[cfg(feature = "termcolor")]
+            honor_color_env: true,
+            #[cfg(feature = "termcolor")]
+            location_link: None,
 
             #[cfg(feature = "paris")]
             enable_paris_formatting: true,
             line_ending: String::from("\u{000A}"),
+            format: None,
+            level_formats: Vec::new(),
+            formatter: None,
+            cef: None,
+            continuation_prefix: None,
+            max_message_len: None,
+            file_locking: false,
+            max_rotated_files: None,
+            max_rotated_bytes: None,
+            rotated_name: None,
+            file_truncate: false,
+            create_dirs: false,
+            #[cfg(unix)]
+            file_mode: None,
+            #[cfg(feature = "flate2")]
+            compress_rotated: false,
+            #[cfg(feature = "flate2")]
+            compression_error_handler: None,
+            file_error_handler: None,
+            print_gate: None,
+            rate_limit: None,
+            fold_repeats: None,
+            sampling: None,
+            redactor: None,
+            transforms: Vec::new(),
+            record_filters: Vec::new(),
+            flush_policy: FlushPolicy::EveryRecord,
         }
     }
 }
@@ -0,0 +1,136 @@
+//! Module providing a diagnostic context of key-values appended to every record of a thread
+
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::marker::PhantomData;
+
+thread_local! {
+    static CONTEXT: RefCell<Vec<(String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The diagnostic context of the current thread, returned by [`context`].
+///
+/// Its key-values are appended to the key-values of every record logged on this thread,
+/// so they are written by all loggers, that write key-values, e.g. as `key=value` pairs by
+/// the default layout or as additional fields by the `GelfLogger`. They are kept until they
+/// are removed, so reset the context at the end of a task, if the thread is reused for others.
+pub struct Context {
+    // the context belongs to the thread it was created on
+    _thread: PhantomData<*const ()>,
+}
+
+impl Context {
+    /// Sets `key` to `value`, replacing a previous value of `key`
+    pub fn insert<K: Into<String>, V: Display>(&self, key: K, value: V) -> &Context {
+        let key = key.into();
+        let value = value.to_string();
+        CONTEXT.with(|context| {
+            let mut context = context.borrow_mut();
+            match context.iter_mut().find(|(k, _)| *k == key) {
+                Some(entry) => entry.1 = value,
+                None => context.push((key, value)),
+            }
+        });
+        self
+    }
+
+    /// Returns the value of `key`
+    pub fn get(&self, key: &str) -> Option<String> {
+        CONTEXT.with(|context| {
+            context
+                .borrow()
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.clone())
+        })
+    }
+
+    /// Removes `key`, returning its value
+    pub fn remove(&self, key: &str) -> Option<String> {
+        CONTEXT.with(|context| {
+            let mut context = context.borrow_mut();
+            let pos = context.iter().position(|(k, _)| k == key)?;
+            Some(context.remove(pos).1)
+        })
+    }
+
+    /// Removes all key-values
+    pub fn clear(&self) {
+        CONTEXT.with(|context| context.borrow_mut().clear());
+    }
+}
+
+/// Returns the diagnostic context of the current thread.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// # let request_id = 42;
+/// context().insert("request_id", request_id).insert("user", "alice");
+/// // every record logged on this thread now ends with `request_id=42 user=alice`
+/// context().clear();
+/// # }
+/// ```
+pub fn context() -> Context {
+    Context {
+        _thread: PhantomData,
+    }
+}
+
+/// Guard returned by [`scoped_context`], removing its key from the context when dropped
+#[must_use = "the key is removed again, when the guard is dropped"]
+pub struct ContextGuard {
+    key: String,
+    /// Value of the key before the guard was created, restored on drop
+    previous: Option<String>,
+    // the guard has to be dropped on the thread of the context
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        match self.previous.take() {
+            Some(previous) => {
+                context().insert(std::mem::take(&mut self.key), previous);
+            }
+            None => {
+                context().remove(&self.key);
+            }
+        }
+    }
+}
+
+/// Sets `key` to `value` in the diagnostic context of the current thread (see [`context`]),
+/// until the returned guard is dropped.
+///
+/// A previous value of `key` is restored, when the guard is dropped, so scopes may be nested.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// for job_id in 0..3 {
+///     let _job = scoped_context("job", job_id);
+///     // every record logged on this thread during the iteration ends with `job=<job_id>`
+/// }
+/// assert_eq!(context().get("job"), None);
+/// # }
+/// ```
+pub fn scoped_context<K: Into<String>, V: Display>(key: K, value: V) -> ContextGuard {
+    let key = key.into();
+    let previous = context().get(&key);
+    context().insert(key.clone(), value);
+    ContextGuard {
+        key,
+        previous,
+        _thread: PhantomData,
+    }
+}
+
+/// Returns the key-values of the context of the current thread
+pub(crate) fn entries() -> Vec<(String, String)> {
+    CONTEXT.with(|context| context.borrow().clone())
+}
@@ -0,0 +1,224 @@
+//! Module providing the deserialization of configurations and logger setups, e.g. from a configuration file
+
+#[cfg(feature = "termcolor")]
+use crate::{ColorChoice, TermLogger, TerminalMode};
+use crate::{
+    CombinedLogger, Config, ConfigBuilder, FileLogger, InitError, LoggerHandle, Rotation,
+    SharedLogger, SimpleLogger,
+};
+use log::LevelFilter;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use serde::Deserialize as DeriveDeserialize;
+use std::fmt;
+use std::path::PathBuf;
+
+const FIELDS: &[&str] = &[
+    "time",
+    "level",
+    "thread",
+    "target",
+    "location",
+    "module",
+    "pid",
+    "time_format",
+    "format",
+    "filters",
+    "allow",
+    "ignore",
+    "hostname",
+    "line_ending",
+    "max_message_len",
+];
+
+struct BuilderVisitor;
+
+impl<'de> Visitor<'de> for BuilderVisitor {
+    type Value = ConfigBuilder;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a logging configuration")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<ConfigBuilder, A::Error> {
+        let mut builder = ConfigBuilder::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "time" => builder.set_time_level(map.next_value()?),
+                "level" => builder.set_max_level(map.next_value()?),
+                "thread" => builder.set_thread_level(map.next_value()?),
+                "target" => builder.set_target_level(map.next_value()?),
+                "location" => builder.set_location_level(map.next_value()?),
+                "module" => builder.set_module_level(map.next_value()?),
+                "pid" => builder.set_pid_level(map.next_value()?),
+                "time_format" => match map.next_value::<String>()?.as_str() {
+                    "rfc2822" => builder.set_time_format_rfc2822(),
+                    "rfc3339" => builder.set_time_format_rfc3339(),
+                    format => builder
+                        .set_time_format_str(format)
+                        .map_err(de::Error::custom)?,
+                },
+                "format" => builder
+                    .set_format(&map.next_value::<String>()?)
+                    .map_err(de::Error::custom)?,
+                "filters" => builder.add_level_filters_from_spec(&map.next_value::<String>()?),
+                "allow" => {
+                    for target in map.next_value::<Vec<String>>()? {
+                        builder.add_filter_allow(target);
+                    }
+                    &mut builder
+                }
+                "ignore" => {
+                    for target in map.next_value::<Vec<String>>()? {
+                        builder.add_filter_ignore(target);
+                    }
+                    &mut builder
+                }
+                "hostname" => builder.set_hostname(map.next_value::<String>()?),
+                "line_ending" => builder.set_line_ending_custom(map.next_value::<String>()?),
+                "max_message_len" => builder.set_max_message_len(Some(map.next_value()?)),
+                key => return Err(de::Error::unknown_field(key, FIELDS)),
+            };
+        }
+        Ok(builder)
+    }
+}
+
+/// Deserializes a builder from a map of settings, unset ones keep their default values.
+///
+/// The settings `time`, `level`, `thread`, `target`, `location`, `module` and `pid` take a level
+/// like the corresponding `set_*_level` functions, `time_format` takes `rfc2822`, `rfc3339` or a format for
+/// [`ConfigBuilder::set_time_format_str`], `format` a template for [`ConfigBuilder::set_format`],
+/// `filters` level filters for [`ConfigBuilder::add_level_filters_from_spec`], `allow` and `ignore`
+/// lists of targets, `hostname` and `line_ending` strings and `max_message_len` a number of bytes.
+///
+/// Requires the `serde` feature.
+///
+/// # Examples
+/// ```toml
+/// time = "error"
+/// time_format = "rfc3339"
+/// format = "{time} [{level}] {target}: {message}"
+/// filters = "info,hyper=warn"
+/// ignore = ["mio"]
+/// ```
+impl<'de> Deserialize<'de> for ConfigBuilder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<ConfigBuilder, D::Error> {
+        deserializer.deserialize_map(BuilderVisitor)
+    }
+}
+
+/// Deserializes a `Config` with the settings described for [`ConfigBuilder`].
+///
+/// Requires the `serde` feature.
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Config, D::Error> {
+        Ok(ConfigBuilder::deserialize(deserializer)?.build())
+    }
+}
+
+/// Description of a logger in a [`LoggersDescription`], selected by its `type`
+#[derive(Debug, Clone, DeriveDeserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum LoggerDescription {
+    /// A [`TermLogger`] with colors, if the stream is a terminal
+    #[cfg(feature = "termcolor")]
+    Terminal {
+        /// Level of the logger
+        level: LevelFilter,
+        /// Configuration of the logger
+        #[serde(default)]
+        config: Config,
+        /// Streams to write to, `mixed` by default
+        #[serde(default)]
+        mode: TerminalMode,
+    },
+    /// A [`SimpleLogger`]
+    Simple {
+        /// Level of the logger
+        level: LevelFilter,
+        /// Configuration of the logger
+        #[serde(default)]
+        config: Config,
+    },
+    /// A [`FileLogger`]
+    File {
+        /// Level of the logger
+        level: LevelFilter,
+        /// Configuration of the logger
+        #[serde(default)]
+        config: Config,
+        /// Path of the log file
+        path: PathBuf,
+        /// When to rotate the file, never by default
+        #[serde(default = "never")]
+        rotation: Rotation,
+    },
+}
+
+fn never() -> Rotation {
+    Rotation::Never
+}
+
+impl LoggerDescription {
+    /// Creates the described logger
+    pub fn build(self) -> std::io::Result<Box<dyn SharedLogger>> {
+        Ok(match self {
+            #[cfg(feature = "termcolor")]
+            LoggerDescription::Terminal {
+                level,
+                config,
+                mode,
+            } => TermLogger::new(level, config, mode, ColorChoice::Auto) as Box<dyn SharedLogger>,
+            LoggerDescription::Simple { level, config } => SimpleLogger::new(level, config),
+            LoggerDescription::File {
+                level,
+                config,
+                path,
+                rotation,
+            } => FileLogger::new(level, config, path, rotation)?,
+        })
+    }
+}
+
+/// Description of the whole logging setup of an application, to be loaded from a configuration file.
+///
+/// Requires the `serde` feature.
+///
+/// # Examples
+/// ```toml
+/// [[loggers]]
+/// type = "terminal"
+/// level = "warn"
+///
+/// [[loggers]]
+/// type = "file"
+/// level = "debug"
+/// path = "app.log"
+/// rotation = "daily"
+/// config = { time_format = "rfc3339", filters = "debug,hyper=info" }
+/// ```
+#[derive(Debug, Clone, DeriveDeserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LoggersDescription {
+    /// The loggers to combine
+    pub loggers: Vec<LoggerDescription>,
+}
+
+impl LoggersDescription {
+    /// Creates the described loggers, combined in a `CombinedLogger`
+    pub fn build(self) -> std::io::Result<Box<CombinedLogger>> {
+        let loggers = self
+            .loggers
+            .into_iter()
+            .map(LoggerDescription::build)
+            .collect::<std::io::Result<_>>()?;
+        Ok(CombinedLogger::new(loggers))
+    }
+
+    /// Globally initializes the described loggers as the one and only used log facility.
+    ///
+    /// Fails if a log file cannot be opened or another Logger was already initialized.
+    pub fn init(self) -> Result<LoggerHandle<CombinedLogger>, InitError> {
+        Ok(crate::handle::init(self.build()?)?)
+    }
+}
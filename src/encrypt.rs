@@ -0,0 +1,328 @@
+//! Module providing a writer, that encrypts log files at rest using AES-256-GCM
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::io::{self, Read, Write};
+
+/// Marks the start of a segment, followed by the random nonce prefix of the segment
+const MAGIC: [u8; 4] = *b"SLE\x01";
+/// Size of the random part of the nonces of a segment
+const PREFIX_LEN: usize = 8;
+/// Size of the authentication tag appended to every chunk
+const TAG_LEN: usize = 16;
+/// Maximum amount of plaintext sealed into one chunk
+const MAX_CHUNK: usize = 64 * 1024;
+/// Authenticated data of every chunk but the last one of a segment
+const AAD_CHUNK: [u8; 1] = [0];
+/// Authenticated data of the last chunk of a segment, sealed once the writer is dropped
+const AAD_LAST: [u8; 1] = [1];
+
+/// A 256 bit key used to encrypt and decrypt log files.
+///
+/// The key is not printed by its `Debug` implementation.
+#[derive(Clone)]
+pub struct EncryptionKey(LessSafeKey);
+
+impl EncryptionKey {
+    /// Creates a key from its raw bytes
+    pub fn new(key: &[u8; 32]) -> EncryptionKey {
+        EncryptionKey(LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, key).expect("AES-256 keys have 32 bytes"),
+        ))
+    }
+
+    /// Creates a key from its 64 hexadecimal digits, returns `None` if `hex` is no valid key
+    pub fn from_hex(hex: &str) -> Option<EncryptionKey> {
+        let hex = hex.trim();
+        if hex.len() != 64 || !hex.is_ascii() {
+            return None;
+        }
+        let mut key = [0u8; 32];
+        for (i, byte) in key.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).ok()?;
+        }
+        Some(EncryptionKey::new(&key))
+    }
+
+    fn nonce(prefix: &[u8; PREFIX_LEN], counter: u32) -> Nonce {
+        let mut nonce = [0u8; 12];
+        nonce[..PREFIX_LEN].copy_from_slice(prefix);
+        nonce[PREFIX_LEN..].copy_from_slice(&counter.to_be_bytes());
+        Nonce::assume_unique_for_key(nonce)
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// A writer encrypting everything written to it, before passing it on to the inner writer.
+///
+/// The data of every `write` (of up to 64 KiB) is sealed as one chunk using AES-256-GCM right away.
+/// The `WriteLogger` writes every record at once, or the buffered records according to its
+/// [`FlushPolicy`](crate::FlushPolicy), so at most the records being written are lost if the process crashes. A chunk cannot be modified, reordered or moved
+/// to another file without decryption failing.
+///
+/// Every writer starts a new segment with a random nonce, so multiple writers may append
+/// to the same file one after another, e.g. after a restart of the application.
+/// Once the writer is dropped, the segment is closed with a marked last chunk, so [`decrypt`]
+/// detects a segment that has been cut off, whether by truncating the file or by a crash.
+/// Removing whole segments from the end of the file is not detected however, as every
+/// segment can be decrypted on its own.
+/// Use [`decrypt`] to read the file again, the `decrypt_log` example provides a small tool for that.
+///
+/// # Examples
+/// ```no_run
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use std::fs::OpenOptions;
+/// # fn main() {
+/// let key = EncryptionKey::from_hex(&std::env::var("LOG_KEY").unwrap()).unwrap();
+/// let file = OpenOptions::new().create(true).append(true).open("audit.log").unwrap();
+/// let _ = WriteLogger::init(
+///     LevelFilter::Info,
+///     Config::default(),
+///     EncryptingWriter::new(file, &key),
+/// );
+/// # }
+/// ```
+pub struct EncryptingWriter<W: Write> {
+    inner: W,
+    key: LessSafeKey,
+    /// Nonce prefix of the current segment, `None` until its header was written
+    prefix: Option<[u8; PREFIX_LEN]>,
+    counter: u32,
+    /// Data being sealed, kept to reuse its allocation
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> EncryptingWriter<W> {
+    /// Creates a writer encrypting with `key` into `inner`
+    pub fn new(inner: W, key: &EncryptionKey) -> EncryptingWriter<W> {
+        EncryptingWriter {
+            inner,
+            key: key.0.clone(),
+            prefix: None,
+            counter: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Returns a reference to the inner writer
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Seals the collected data into a chunk and writes it to the inner writer,
+    /// `last` closes the current segment, even if no data was collected
+    fn seal(&mut self, last: bool) -> io::Result<()> {
+        if self.buffer.is_empty() && !(last && self.prefix.is_some()) {
+            return Ok(());
+        }
+        if !last && self.prefix.is_some() && self.counter == u32::MAX {
+            // the last nonce of a segment is kept for its last chunk, close it and start a new one
+            let data = std::mem::take(&mut self.buffer);
+            self.seal(true)?;
+            self.buffer = data;
+        }
+        let prefix = match self.prefix {
+            Some(prefix) => prefix,
+            None => {
+                let mut prefix = [0u8; PREFIX_LEN];
+                SystemRandom::new()
+                    .fill(&mut prefix)
+                    .map_err(|_| io::Error::other("failed to generate a nonce"))?;
+                self.inner.write_all(&MAGIC)?;
+                self.inner.write_all(&prefix)?;
+                self.prefix = Some(prefix);
+                self.counter = 0;
+                prefix
+            }
+        };
+
+        let mut chunk = std::mem::take(&mut self.buffer);
+        let nonce = EncryptionKey::nonce(&prefix, self.counter);
+        self.counter = self.counter.wrapping_add(1);
+        let aad = if last { AAD_LAST } else { AAD_CHUNK };
+        self.key
+            .seal_in_place_append_tag(nonce, Aad::from(aad), &mut chunk)
+            .map_err(|_| io::Error::other("failed to encrypt"))?;
+        if last {
+            self.prefix = None;
+        }
+        self.inner.write_all(&(chunk.len() as u32).to_be_bytes())?;
+        self.inner.write_all(&chunk)?;
+        chunk.clear();
+        self.buffer = chunk;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(MAX_CHUNK);
+        self.buffer.extend_from_slice(&buf[..len]);
+        self.seal(false)?;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for EncryptingWriter<W> {
+    fn drop(&mut self) {
+        let _ = self.seal(true);
+        let _ = self.inner.flush();
+    }
+}
+
+/// Decrypts a log file written by an [`EncryptingWriter`] from `reader` into `writer`.
+///
+/// Fails with `InvalidData` if the data was not encrypted with `key` or has been tampered with,
+/// and with `UnexpectedEof` if a chunk is incomplete or a segment ends without its last chunk,
+/// e.g. because the application crashed or the file has been truncated. In the latter case the
+/// remaining segments are still decrypted before failing.
+/// Everything decrypted up to the error has been written to `writer` in all cases.
+///
+/// # Examples
+/// ```no_run
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use std::fs::File;
+/// # fn main() {
+/// let key = EncryptionKey::from_hex(&std::env::var("LOG_KEY").unwrap()).unwrap();
+/// decrypt(&key, File::open("audit.log").unwrap(), std::io::stdout()).unwrap();
+/// # }
+/// ```
+pub fn decrypt<R: Read, W: Write>(
+    key: &EncryptionKey,
+    mut reader: R,
+    mut writer: W,
+) -> io::Result<()> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let mut prefix = None;
+    let mut counter = 0u32;
+    let mut truncated = false;
+    let mut chunk = Vec::new();
+    let mut copy = Vec::new();
+    loop {
+        let mut frame = [0u8; 4];
+        match reader.read(&mut frame)? {
+            0 => break,
+            n => reader.read_exact(&mut frame[n..])?,
+        }
+
+        if frame == MAGIC {
+            let mut new_prefix = [0u8; PREFIX_LEN];
+            reader.read_exact(&mut new_prefix)?;
+            // the previous segment was not closed
+            truncated |= prefix.is_some();
+            prefix = Some(new_prefix);
+            counter = 0;
+            continue;
+        }
+        let segment = prefix.ok_or_else(|| {
+            if counter == 0 {
+                invalid("not an encrypted log")
+            } else {
+                invalid("data after the last chunk of a segment")
+            }
+        })?;
+        let len = u32::from_be_bytes(frame) as usize;
+        if !(TAG_LEN..=MAX_CHUNK + TAG_LEN).contains(&len) {
+            return Err(invalid("invalid chunk length"));
+        }
+
+        chunk.resize(len, 0);
+        reader.read_exact(&mut chunk)?;
+        // a failed attempt may overwrite the chunk, so the last chunk is opened from a copy
+        copy.clone_from(&chunk);
+        let nonce = |counter| EncryptionKey::nonce(&segment, counter);
+        let mut last = false;
+        let plaintext = match key
+            .0
+            .open_in_place(nonce(counter), Aad::from(AAD_CHUNK), &mut chunk)
+        {
+            Ok(plaintext) => plaintext,
+            Err(_) => {
+                last = true;
+                key.0
+                    .open_in_place(nonce(counter), Aad::from(AAD_LAST), &mut copy)
+                    .map_err(|_| invalid("decryption failed, wrong key or modified data"))?
+            }
+        };
+        writer.write_all(plaintext)?;
+        counter = counter.wrapping_add(1);
+        if last {
+            prefix = None;
+        }
+    }
+    writer.flush()?;
+    if truncated || prefix.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "log truncated, a segment ends without its last chunk",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, SharedBuf};
+    use crate::{ConfigBuilder, Level, LevelFilter, WriteLogger};
+
+    #[test]
+    fn test_encrypting_writer() {
+        let key = EncryptionKey::from_hex(&"2a".repeat(32)).unwrap();
+        let buf = SharedBuf::default();
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        for i in 0..2 {
+            // every logger starts a new segment, like an application appending after a restart
+            let logger = WriteLogger::new(
+                LevelFilter::Info,
+                conf.clone(),
+                EncryptingWriter::new(buf.clone(), &key),
+            );
+            log(&logger, Level::Info, &format!("secret {}", i));
+        }
+
+        let encrypted = buf.0.lock().unwrap().clone();
+        assert!(!String::from_utf8_lossy(&encrypted).contains("secret"));
+        let mut decrypted = Vec::new();
+        decrypt(&key, &encrypted[..], &mut decrypted).unwrap();
+        assert_eq!(decrypted, b"[INFO] secret 0\n[INFO] secret 1\n");
+
+        let wrong = EncryptionKey::from_hex(&"2b".repeat(32)).unwrap();
+        let err = decrypt(&wrong, &encrypted[..], std::io::sink()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        let err = decrypt(&key, &encrypted[..encrypted.len() - 1], std::io::sink()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        // a segment is its header, the chunk of the record and the empty last chunk
+        let segment = 4 + 8 + (4 + 16 + 16) + (4 + 16);
+        assert_eq!(encrypted.len(), 2 * segment);
+        let mut cut = encrypted[..segment - 20].to_vec();
+        let mut decrypted = Vec::new();
+        let err = decrypt(&key, &cut[..], &mut decrypted).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(decrypted, b"[INFO] secret 0\n");
+        // the following segments are still decrypted
+        cut.extend_from_slice(&encrypted[segment..]);
+        let mut decrypted = Vec::new();
+        let err = decrypt(&key, &cut[..], &mut decrypted).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(decrypted, b"[INFO] secret 0\n[INFO] secret 1\n");
+        // removing whole segments is not detected
+        decrypt(&key, &encrypted[..segment], std::io::sink()).unwrap();
+        assert!(EncryptionKey::from_hex("2a").is_none());
+    }
+}
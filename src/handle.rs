@@ -0,0 +1,305 @@
+//! Module providing the handle returned by the `init` functions of all loggers
+
+use crate::stats::LossReporter;
+use crate::SharedLogger;
+use log::{set_boxed_logger, set_max_level, LevelFilter, SetLoggerError};
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::ops::Deref;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Error returned by the `init` functions of loggers, that need to acquire a resource like a file or a socket
+#[derive(Debug)]
+pub enum InitError {
+    /// The resource (e.g. the log file) could not be opened
+    Io(io::Error),
+    /// Another logger was already initialized
+    SetLogger(SetLoggerError),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::Io(err) => write!(f, "failed to initialize logger: {}", err),
+            InitError::SetLogger(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for InitError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InitError::Io(err) => Some(err),
+            InitError::SetLogger(err) => Some(err),
+        }
+    }
+}
+
+impl From<io::Error> for InitError {
+    fn from(err: io::Error) -> Self {
+        InitError::Io(err)
+    }
+}
+
+impl From<SetLoggerError> for InitError {
+    fn from(err: SetLoggerError) -> Self {
+        InitError::SetLogger(err)
+    }
+}
+
+/// Error returned when opening a log file fails
+#[derive(Debug)]
+pub enum FileError {
+    /// A missing parent directory of the file could not be created
+    CreateDir(PathBuf, io::Error),
+    /// The file itself could not be opened
+    Open(PathBuf, io::Error),
+}
+
+impl FileError {
+    /// The path of the directory or file, that could not be created or opened
+    pub fn path(&self) -> &PathBuf {
+        match self {
+            FileError::CreateDir(path, _) | FileError::Open(path, _) => path,
+        }
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileError::CreateDir(path, err) => {
+                write!(f, "failed to create directory {}: {}", path.display(), err)
+            }
+            FileError::Open(path, err) => {
+                write!(f, "failed to open log file {}: {}", path.display(), err)
+            }
+        }
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            FileError::CreateDir(_, err) | FileError::Open(_, err) => Some(err),
+        }
+    }
+}
+
+impl From<FileError> for io::Error {
+    fn from(err: FileError) -> Self {
+        let kind = match &err {
+            FileError::CreateDir(_, err) | FileError::Open(_, err) => err.kind(),
+        };
+        io::Error::new(kind, err)
+    }
+}
+
+impl From<FileError> for InitError {
+    fn from(err: FileError) -> Self {
+        InitError::Io(err.into())
+    }
+}
+
+/// Handle to a globally initialized logger, returned by the `init` functions.
+///
+/// The handle allows changing the level of the logger at runtime and dereferences
+/// to the logger itself for access to logger specific functionality.
+/// It can be cloned and sent to other threads freely, dropping it does not affect the logger.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let handle = SimpleLogger::init(LevelFilter::Info, Config::default()).unwrap();
+/// // e.g. after receiving a signal
+/// handle.set_level(LevelFilter::Debug);
+/// # }
+/// ```
+pub struct LoggerHandle<L: SharedLogger> {
+    logger: Arc<L>,
+}
+
+impl<L: SharedLogger> LoggerHandle<L> {
+    /// Returns the current level of the logger
+    pub fn level(&self) -> LevelFilter {
+        self.logger.level()
+    }
+
+    /// Changes the level of the logger, also updating the global max level of the `log` crate.
+    pub fn set_level(&self, level: LevelFilter) {
+        self.logger.set_level(level);
+        set_max_level(self.logger.level());
+    }
+
+    /// Returns a guard flushing and shutting down the logger, when it is dropped
+    ///
+    /// The global logger itself is never dropped, so keep the guard around until the end of `main`
+    /// to not lose buffered records, e.g. of a `WriteLogger` with a [`FlushPolicy`](crate::FlushPolicy)
+    /// or an `AsyncLogger`. This also works, if `main` panics.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new()
+    ///     .set_flush_policy(FlushPolicy::EveryRecords(100))
+    ///     .build();
+    /// let _guard = WriteLogger::init(LevelFilter::Info, config, File::create("my_rust_bin.log").unwrap())
+    ///     .unwrap()
+    ///     .flush_guard();
+    /// # }
+    /// ```
+    pub fn flush_guard(&self) -> FlushGuard<L> {
+        FlushGuard {
+            logger: self.logger.clone(),
+            flusher: None,
+        }
+    }
+
+    /// Returns a guard like [`LoggerHandle::flush_guard`], that additionally flushes the logger every `interval`
+    ///
+    /// This bounds the records lost on a crash for buffered loggers, e.g. a `WriteLogger` with a
+    /// [`FlushPolicy`](crate::FlushPolicy), without flushing after every record.
+    /// The flushing thread is stopped, before the guard flushes the logger a last time.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new()
+    ///     .set_flush_policy(FlushPolicy::EveryRecords(1000))
+    ///     .build();
+    /// let _guard = WriteLogger::init(LevelFilter::Info, config, File::create("my_rust_bin.log").unwrap())
+    ///     .unwrap()
+    ///     .periodic_flush_guard(Duration::from_millis(500));
+    /// # }
+    /// ```
+    pub fn periodic_flush_guard(&self, interval: Duration) -> FlushGuard<L>
+    where
+        L: 'static,
+    {
+        FlushGuard {
+            logger: self.logger.clone(),
+            flusher: Some(Periodic::spawn("simplelog-flush", interval, {
+                let logger = self.logger.clone();
+                move || logger.flush()
+            })),
+        }
+    }
+
+    /// Logs a summary of the records lost since the last one every `interval`, until the returned reporter is dropped
+    ///
+    /// The summary is a single `Warn` record with the target `simplelog`, that counts the records
+    /// dropped by a full queue, suppressed by a rate limit or lost due to write errors, see [`Stats`](crate::Stats).
+    /// Nothing is logged for an interval without lost records.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let handle = SimpleLogger::init(LevelFilter::Info, Config::default()).unwrap();
+    /// let _reporter = handle.report_lost_records(Duration::from_secs(60));
+    /// # }
+    /// ```
+    pub fn report_lost_records(&self, interval: Duration) -> LossReporter
+    where
+        L: 'static,
+    {
+        LossReporter::spawn(self.logger.clone(), interval)
+    }
+}
+
+/// Thread calling a function periodically, until it is dropped
+pub(crate) struct Periodic {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Periodic {
+    pub(crate) fn spawn<F>(name: &str, interval: Duration, mut f: F) -> Periodic
+    where
+        F: FnMut() + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name(String::from(name))
+            .spawn(move || loop {
+                match stopped.recv_timeout(interval) {
+                    Err(RecvTimeoutError::Timeout) => f(),
+                    _ => return,
+                }
+            })
+            .expect("failed to spawn thread");
+        Periodic {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Periodic {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Guard flushing a global logger when dropped, returned by [`LoggerHandle::flush_guard`]
+///
+/// After flushing, the global max level of the `log` crate is set to `Off`,
+/// so records logged afterwards are dropped instead of getting stuck in a buffer, that is never written.
+#[must_use = "the logger is flushed as soon as the guard is dropped"]
+pub struct FlushGuard<L: SharedLogger> {
+    logger: Arc<L>,
+    flusher: Option<Periodic>,
+}
+
+impl<L: SharedLogger> Drop for FlushGuard<L> {
+    fn drop(&mut self) {
+        drop(self.flusher.take());
+        self.logger.flush();
+        set_max_level(LevelFilter::Off);
+    }
+}
+
+impl<L: SharedLogger> Clone for LoggerHandle<L> {
+    fn clone(&self) -> Self {
+        LoggerHandle {
+            logger: self.logger.clone(),
+        }
+    }
+}
+
+impl<L: SharedLogger> Deref for LoggerHandle<L> {
+    type Target = L;
+
+    fn deref(&self) -> &L {
+        &self.logger
+    }
+}
+
+/// Globally initializes `logger` as the one and only used log facility
+pub(crate) fn init<L: SharedLogger + 'static>(
+    logger: Box<L>,
+) -> Result<LoggerHandle<L>, SetLoggerError> {
+    let logger: Arc<L> = Arc::from(logger);
+    set_boxed_logger(Box::new(logger.clone()))?;
+    set_max_level(logger.level());
+    Ok(LoggerHandle { logger })
+}
@@ -11,36 +11,125 @@
 //! - `SimpleLogger` (very basic logger that logs to stdout)
 //! - `TermLogger` (advanced terminal logger, that splits to stdout/err and has color support) (can be excluded on unsupported platforms)
 //! - `WriteLogger` (logs to a given struct implementing `Write`, e.g. a file)
+//! - `TeeLogger` (formats records once and writes them into two structs implementing `Write`, e.g. a file and a buffer)
+//! - `ContainerLogger` (writes records as single-line JSON with UTC times to stdout, as expected by the log drivers of Docker and Kubernetes)
+//! - `FileLogger` (logs to a file, that is rotated by size or at time boundaries)
+//! - `SplitFileLogger` (logs everything to one file and warnings and errors additionally to a second one)
+//! - `SyslogLogger` (sends records to the local syslog daemon, Unix-only, requires the `syslog` feature)
+//! - `RemoteSyslogLogger` (sends records to a syslog server in the RFC 5424 format over TCP, optionally using TLS, requires the `syslog` or `syslog-tls` feature)
+//! - `JournalLogger` (sends records with structured fields to systemd-journald, Linux-only, requires the `journald` feature)
+//! - `WinEventLogger` (writes records into the Windows Event Log, Windows-only, requires the `wineventlog` feature)
+//! - `EtwLogger` (emits records as ETW events of a provider, e.g. for WPA or PerfView, Windows-only, requires the `etw` feature)
+//! - `DebugViewLogger` (passes records to `OutputDebugStringW`, e.g. for DebugView or the output window of Visual Studio, Windows-only, requires the `debugview` feature)
+//! - `OsLogger` (writes records to Apple's unified logging system with the target as category, e.g. for Console.app, Apple-only, requires the `oslog` feature)
+//! - `TcpLogger` (sends records to a log collector over TCP, reconnecting and buffering records if the connection is lost)
+//! - `NamedPipeLogger` (writes records to a Windows named pipe, e.g. of a log collection agent, reopening it if it is broken, Windows-only)
+//! - `UnixSocketLogger` (writes records to a Unix domain socket, e.g. of a local collector like Vector or fluent-bit, Unix-only)
+//! - `HttpLogger` (sends records in batches as JSON to an HTTP endpoint, requires the `http` feature)
+//! - `WebhookLogger` (posts records as alerts to a webhook, e.g. of Slack, Discord or Teams, requires the `http` feature)
+//! - `GelfLogger` (sends records to Graylog using GELF over UDP, requires the `gelf` feature)
+//! - `KafkaLogger` (publishes records to a Kafka topic, requires the `kafka` feature)
+//! - `EmailLogger` (mails records, e.g. errors, at most once per interval, requires the `smtp` feature)
+//! - `SqliteLogger` (inserts records into a SQLite database to query them using SQL, requires the `sqlite` feature)
+//! - `MemoryLogger` (keeps the most recent records in memory, e.g. to show them in a GUI)
+//! - `NullLogger` (discards all records, e.g. as a placeholder or for benchmarking)
 //! - `CombinedLogger` (can be used to form combinations of the above loggers)
+//! - `AsyncLogger` (wraps another logger and writes its records from a background thread)
 //! - `TestLogger` (specialized logger for tests. Uses print!() / println!() for tests to be able to capture the output)
 //!
 //! Only one Logger should be initialized of the start of your program
 //! through the `Logger::init(...)` method. For the actual calling syntax
 //! take a look at the documentation of the specific implementation(s) you wanna use.
+//! To get started quickly, [`init`] picks a logger suitable for the terminal.
 //!
 
 #![deny(missing_docs, rust_2018_idioms)]
 
+mod ansi;
 mod config;
+mod context;
+#[cfg(feature = "serde")]
+mod description;
+#[cfg(feature = "encryption")]
+mod encrypt;
+mod handle;
 mod loggers;
+mod panic;
+mod reload;
+mod scope;
+mod stats;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "tracing")]
+mod tracing_bridge;
 
+pub use self::ansi::enable_ansi_support;
+#[cfg(feature = "termcolor")]
+pub use self::config::ColorTheme;
 pub use self::config::{
-    format_description, Config, ConfigBuilder, FormatItem, LevelPadding, TargetPadding,
-    ThreadLogMode, ThreadPadding,
+    format_description, Config, ConfigBuilder, FlushPolicy, FormatError, FormatItem, Formatter,
+    LevelPadding, LevelSymbols, LineEnding, LocationMode, OwnedRecord, RecordTransform,
+    TargetPadding, ThreadLogMode, ThreadPadding, TimePrecision,
+};
+pub use self::context::{context, scoped_context, Context, ContextGuard};
+#[cfg(feature = "serde")]
+pub use self::description::{LoggerDescription, LoggersDescription};
+#[cfg(feature = "encryption")]
+pub use self::encrypt::{decrypt, EncryptingWriter, EncryptionKey};
+pub use self::handle::{FileError, FlushGuard, InitError, LoggerHandle};
+#[cfg(all(windows, feature = "debugview"))]
+pub use self::loggers::DebugViewLogger;
+#[cfg(all(windows, feature = "etw"))]
+pub use self::loggers::EtwLogger;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub use self::loggers::JournalLogger;
+#[cfg(windows)]
+pub use self::loggers::NamedPipeLogger;
+#[cfg(all(target_vendor = "apple", feature = "oslog"))]
+pub use self::loggers::OsLogger;
+#[cfg(all(unix, feature = "syslog"))]
+pub use self::loggers::SyslogLogger;
+#[cfg(all(windows, feature = "wineventlog"))]
+pub use self::loggers::WinEventLogger;
+pub use self::loggers::{
+    AsyncLogger, CombinedLogger, ConnectionEvent, ContainerLogger, DefaultFormatter, FileLogger,
+    LoggerId, MemoryLogger, NetworkOptions, NullLogger, OverflowPolicy, Rotation, SimpleLogger,
+    SplitFileLogger, TcpLogger, TeeLogger, WriteLogger,
 };
 #[cfg(feature = "test")]
-pub use self::loggers::TestLogger;
-pub use self::loggers::{CombinedLogger, SimpleLogger, WriteLogger};
+pub use self::loggers::{CapturedRecord, TestLogger};
+#[cfg(feature = "smtp")]
+pub use self::loggers::{EmailLogger, EmailOptions};
+#[cfg(feature = "syslog")]
+pub use self::loggers::{Facility, RemoteSyslogLogger, SyslogFormat, SyslogOptions};
+#[cfg(feature = "gelf")]
+pub use self::loggers::{GelfLogger, GelfOptions};
+#[cfg(feature = "http")]
+pub use self::loggers::{HttpLogger, HttpOptions, WebhookLogger, WebhookOptions};
+#[cfg(feature = "kafka")]
+pub use self::loggers::{KafkaKey, KafkaLogger};
+#[cfg(feature = "sqlite")]
+pub use self::loggers::{SqliteLogger, SqliteOptions};
 #[cfg(feature = "termcolor")]
 pub use self::loggers::{TermLogger, TerminalMode};
+#[cfg(unix)]
+pub use self::loggers::{UnixSocketKind, UnixSocketLogger};
+pub use self::panic::install_panic_hook;
+#[cfg(feature = "notify")]
+pub use self::reload::LevelFileWatcher;
+pub use self::reload::RuntimeLevelFilters;
+pub use self::scope::LogScope;
+pub use self::stats::{stats, LossReporter, Stats};
+#[cfg(feature = "tracing")]
+pub use self::tracing_bridge::{init_tracing_bridge, TracingBridge};
 #[cfg(feature = "termcolor")]
-pub use termcolor::{Color, ColorChoice};
+pub use termcolor::{Color, ColorChoice, ColorSpec};
 
 pub use log::{Level, LevelFilter};
 
-use log::Log;
 #[cfg(test)]
 use log::*;
+use log::{Log, Record};
 
 #[cfg(feature = "paris")]
 #[doc(hidden)]
@@ -84,18 +173,129 @@ pub trait SharedLogger: Log {
     /// ```
     fn config(&self) -> Option<&Config>;
 
+    /// Changes the set Level of this Logger
+    ///
+    /// Loggers not supporting runtime changes of their level ignore this call,
+    /// which is the default implementation.
+    /// Use the [`LoggerHandle`] returned by `init` to change the level of a global logger.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let logger = SimpleLogger::new(LevelFilter::Info, Config::default());
+    /// logger.set_level(LevelFilter::Debug);
+    /// assert_eq!(logger.level(), LevelFilter::Debug);
+    /// # }
+    /// ```
+    fn set_level(&self, _level: LevelFilter) {}
+
+    /// Logs the record like `Log::log`, but returns the error, if writing it failed
+    ///
+    /// Used by the `CombinedLogger` to report failing loggers.
+    /// The default implementation calls `Log::log` and always succeeds.
+    fn try_log(&self, record: &Record<'_>) -> std::io::Result<()> {
+        self.log(record);
+        Ok(())
+    }
+
+    /// Returns the level, at and below which a backtrace is appended to the records
+    ///
+    /// Used by the `AsyncLogger` and the `CombinedLogger` to capture the backtrace once where
+    /// the record is logged. The default implementation returns the one set by
+    /// [`ConfigBuilder::set_backtrace_level`], `Off` for loggers without a config.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new().set_backtrace_level(LevelFilter::Error).build();
+    /// let logger = SimpleLogger::new(LevelFilter::Info, config);
+    /// assert_eq!(logger.backtrace_level(), LevelFilter::Error);
+    /// # }
+    /// ```
+    fn backtrace_level(&self) -> LevelFilter {
+        self.config()
+            .map_or(LevelFilter::Off, |config| config.backtrace)
+    }
+
     /// Returns the logger as a Log trait object
     fn as_log(self: Box<Self>) -> Box<dyn Log>;
 }
 
+/// Globally initializes a logger with sensible defaults, the quickest way to get started.
+///
+/// If standard error is a terminal, a colored `TermLogger` is used (with the `termcolor` feature),
+/// otherwise a `SimpleLogger`. Records are logged at the `Info` level and above, which is overridden
+/// by the `RUST_LOG` environment variable, e.g. `RUST_LOG=debug` or `RUST_LOG=info,my_app=trace`
+/// (see [`ConfigBuilder::add_level_filters_from_spec`]).
+/// The logger is wrapped in a `CombinedLogger`, so the returned handle can add further loggers later on.
+/// Fails if another Logger was already initialized.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use std::fs::File;
+/// # fn main() {
+/// let handle = simplelog::init().unwrap();
+/// log::info!("ready");
+/// handle.add_logger(WriteLogger::new(LevelFilter::Debug, Config::default(), File::create("my_rust_bin.log").unwrap()));
+/// # }
+/// ```
+pub fn init() -> Result<LoggerHandle<CombinedLogger>, log::SetLoggerError> {
+    use std::io::IsTerminal;
+
+    let logger = default_logger(Config::from_env(), std::io::stderr().is_terminal());
+    handle::init(CombinedLogger::new(vec![logger.into_shared()]))
+}
+
+/// The logger chosen by `init`
+enum DefaultLogger {
+    #[cfg(feature = "termcolor")]
+    Term(Box<TermLogger>),
+    Simple(Box<SimpleLogger>),
+}
+
+impl DefaultLogger {
+    fn into_shared(self) -> Box<dyn SharedLogger> {
+        match self {
+            #[cfg(feature = "termcolor")]
+            DefaultLogger::Term(logger) => logger,
+            DefaultLogger::Simple(logger) => logger,
+        }
+    }
+}
+
+/// Colors are only worth it on a terminal, so a `SimpleLogger` is used otherwise
+#[cfg_attr(not(feature = "termcolor"), allow(unused_variables))]
+fn default_logger(config: Config, is_terminal: bool) -> DefaultLogger {
+    #[cfg(feature = "termcolor")]
+    if is_terminal {
+        return DefaultLogger::Term(TermLogger::new(
+            LevelFilter::Info,
+            config,
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        ));
+    }
+    DefaultLogger::Simple(SimpleLogger::new(LevelFilter::Info, config))
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
     use std::io::Read;
 
     use super::*;
+    use crate::test_util::{log, log_to_string, SharedBuf};
 
     #[test]
+    #[allow(clippy::needless_borrows_for_generic_args, clippy::useless_vec)]
     fn test() {
         let mut i = 0;
 
@@ -305,4 +505,1659 @@ mod tests {
             assert!(trace.contains("Test Trace"));
         }
     }
+
+    #[test]
+    fn test_single_write_per_record() {
+        use crate::loggers::logging::try_log;
+
+        #[derive(Default)]
+        struct CountingWriter {
+            writes: usize,
+            data: Vec<u8>,
+        }
+
+        impl std::io::Write for CountingWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.writes += 1;
+                self.data.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_location_level(LevelFilter::Error)
+            .build();
+        let mut writer = CountingWriter::default();
+        try_log(
+            &conf,
+            &Record::builder()
+                .args(format_args!("{} {}", "one", 1))
+                .level(Level::Info)
+                .target("app")
+                .file(Some("main.rs"))
+                .line(Some(1))
+                .build(),
+            &mut writer,
+        )
+        .unwrap();
+        assert_eq!(writer.writes, 1);
+        assert_eq!(writer.data, b"[INFO] [main.rs:1] one 1\n");
+    }
+
+    #[test]
+    fn test_nested_formatting() {
+        use crate::loggers::logging::with_formatted;
+
+        // a message logging another record on the same thread while being formatted
+        struct Nested(Config);
+
+        impl std::fmt::Display for Nested {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let inner = log_to_string(
+                    self.0.clone(),
+                    &Record::builder()
+                        .args(format_args!("inner"))
+                        .level(Level::Info)
+                        .build(),
+                );
+                write!(f, "outer after {}", inner.trim_end())
+            }
+        }
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let mut lines = Vec::new();
+        with_formatted(
+            &conf,
+            &Record::builder()
+                .args(format_args!("{}", Nested(conf.clone())))
+                .level(Level::Warn)
+                .build(),
+            |line| {
+                lines.push(String::from_utf8_lossy(line).into_owned());
+                Ok(())
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            lines,
+            vec![String::from("[WARN] outer after [INFO] inner\n")]
+        );
+    }
+
+    #[test]
+    fn test_periodic_flush() {
+        use crate::handle::Periodic;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        struct Flushes(Arc<AtomicUsize>);
+
+        impl std::io::Write for Flushes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        let flushes = Arc::new(AtomicUsize::new(0));
+        let logger: Arc<WriteLogger<Flushes>> = Arc::from(WriteLogger::new(
+            LevelFilter::Info,
+            ConfigBuilder::new()
+                .set_flush_policy(FlushPolicy::EveryRecords(1000))
+                .build(),
+            Flushes(flushes.clone()),
+        ));
+        let flusher = Periodic::spawn("test-flush", Duration::from_millis(10), move || {
+            logger.flush()
+        });
+        std::thread::sleep(Duration::from_millis(200));
+        drop(flusher);
+        let flushed = flushes.load(Ordering::SeqCst);
+        assert!(flushed > 0);
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(flushes.load(Ordering::SeqCst), flushed);
+    }
+
+    #[cfg(feature = "termcolor")]
+    #[test]
+    fn test_term_logger_format() {
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_location_level(LevelFilter::Error)
+            .build();
+        let record = Record::builder()
+            .args(format_args!("disk full"))
+            .level(Level::Error)
+            .target("app")
+            .file(Some("main.rs"))
+            .line(Some(7))
+            .build();
+        let logger = TermLogger::new(
+            LevelFilter::Info,
+            conf.clone(),
+            TerminalMode::Stdout,
+            ColorChoice::Never,
+        );
+        // plain output is the same as the one of the other loggers, colors only wrap the level
+        let mut buf = termcolor::Buffer::no_color();
+        logger.write_record(&record, &mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(buf.as_slice()),
+            log_to_string(conf, &record)
+        );
+        // with ansi_term the colors are only written, if `set_write_log_enable_colors` is set
+        #[cfg(not(feature = "ansi_term"))]
+        {
+            let mut buf = termcolor::Buffer::ansi();
+            logger.write_record(&record, &mut buf).unwrap();
+            let out = String::from_utf8_lossy(buf.as_slice());
+            assert!(out.contains("\x1b[0m"), "{:?}", out);
+            assert!(
+                out.ends_with("[ERROR] \x1b[0m[main.rs:7] disk full\n"),
+                "{:?}",
+                out
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_template() {
+        let conf = ConfigBuilder::new()
+            .set_format("{level:<5}|{target:>6}|{{{line}}} {message}")
+            .unwrap()
+            .build();
+        let out = log_to_string(
+            conf,
+            &Record::builder()
+                .args(format_args!("hello"))
+                .level(Level::Info)
+                .target("app")
+                .line(Some(42))
+                .build(),
+        );
+        assert_eq!(out, "INFO |   app|{42} hello\n");
+
+        assert_eq!(
+            ConfigBuilder::new().set_format("{nope}").unwrap_err(),
+            FormatError::UnknownField("nope".into())
+        );
+        assert_eq!(
+            ConfigBuilder::new().set_format("{level").unwrap_err(),
+            FormatError::Unclosed
+        );
+        assert_eq!(
+            ConfigBuilder::new().set_format("level}").unwrap_err(),
+            FormatError::Unmatched
+        );
+        assert!(ConfigBuilder::new().set_format("{level:<x}").is_err());
+    }
+
+    #[test]
+    fn test_location_mode() {
+        let log = |mode, file| {
+            log_to_string(
+                ConfigBuilder::new()
+                    .set_time_level(LevelFilter::Off)
+                    .set_max_level(LevelFilter::Off)
+                    .set_location_level(LevelFilter::Error)
+                    .set_location_mode(mode)
+                    .build(),
+                &Record::builder()
+                    .args(format_args!("hello"))
+                    .level(Level::Info)
+                    .file(Some(file))
+                    .line(Some(3))
+                    .build(),
+            )
+        };
+        let registry = "/home/ci/.cargo/registry/src/index/serde-1.0.0/src/de/mod.rs";
+        assert_eq!(
+            log(LocationMode::Full, registry),
+            format!("[{}:3] hello\n", registry)
+        );
+        assert_eq!(log(LocationMode::FileName, registry), "[mod.rs:3] hello\n");
+        assert_eq!(
+            log(LocationMode::Crate, registry),
+            "[serde-1.0.0/src/de/mod.rs:3] hello\n"
+        );
+        assert_eq!(
+            log(LocationMode::Crate, "src/main.rs"),
+            "[src/main.rs:3] hello\n"
+        );
+        assert_eq!(
+            log(LocationMode::FileName, "src\\main.rs"),
+            "[main.rs:3] hello\n"
+        );
+    }
+
+    #[test]
+    fn test_column_layout() {
+        let log = |conf, target| {
+            log_to_string(
+                conf,
+                &Record::builder()
+                    .args(format_args!("hello"))
+                    .level(Level::Warn)
+                    .target(target)
+                    .file(Some("src/db/pool.rs"))
+                    .line(Some(42))
+                    .build(),
+            )
+        };
+        let conf = ConfigBuilder::new()
+            .set_format("{level:^6.1}|{target:<9.9}|{message}")
+            .unwrap()
+            .build();
+        assert_eq!(
+            log(conf.clone(), "my_app::db::pool"),
+            "  …   |…db::pool|hello\n"
+        );
+        assert_eq!(log(conf, "db::pool"), "  …   |db::pool |hello\n");
+        assert!(ConfigBuilder::new().set_format("{target:<5.x}").is_err());
+
+        let conf = ConfigBuilder::new().set_column_layout(10, 16).build();
+        let out = log(conf, "my_app::db::pool");
+        assert!(out.ends_with("[WARN ] …:db::pool …c/db/pool.rs:42 hello\n"));
+        let conf = ConfigBuilder::new().set_column_layout(10, 0).build();
+        assert!(log(conf, "db").ends_with("[WARN ] db         hello\n"));
+    }
+
+    #[test]
+    fn test_custom_formatter() {
+        struct Upper;
+
+        impl Formatter for Upper {
+            fn format(
+                &self,
+                record: &Record<'_>,
+                buf: &mut dyn std::io::Write,
+            ) -> std::io::Result<()> {
+                write!(
+                    buf,
+                    "{}|{}",
+                    record.level(),
+                    record.args().to_string().to_uppercase()
+                )
+            }
+        }
+
+        let record = Record::builder()
+            .args(format_args!("hello"))
+            .level(Level::Info)
+            .target("app")
+            .build();
+        let conf = ConfigBuilder::new()
+            .set_format("{message}")
+            .unwrap()
+            .set_formatter(Upper)
+            .set_line_ending(LineEnding::Crlf)
+            .build();
+        assert_eq!(log_to_string(conf, &record), "INFO|HELLO\r\n");
+
+        let conf = ConfigBuilder::new()
+            .set_formatter(|record: &Record<'_>, buf: &mut dyn std::io::Write| {
+                write!(buf, "{} says {}", record.target(), record.args())
+            })
+            .add_filter_ignore_str("app")
+            .build();
+        assert_eq!(log_to_string(conf, &record), "");
+        let mut builder = ConfigBuilder::new();
+        builder
+            .set_time_level(LevelFilter::Off)
+            .set_formatter(Upper)
+            .clear_formatter();
+        assert_eq!(log_to_string(builder.build(), &record), "[INFO] hello\n");
+
+        // the default layout is a formatter as well, that custom ones may wrap
+        let layout = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let mut buf = Vec::new();
+        DefaultFormatter::new(&layout)
+            .format(&record, &mut buf)
+            .unwrap();
+        assert_eq!(buf, b"[INFO] hello");
+        let conf = ConfigBuilder::new()
+            .set_formatter(move |record: &Record<'_>, buf: &mut dyn std::io::Write| {
+                buf.write_all(b"app | ")?;
+                DefaultFormatter::new(&layout).format(record, buf)
+            })
+            .build();
+        assert_eq!(log_to_string(conf, &record), "app | [INFO] hello\n");
+    }
+
+    #[test]
+    fn test_level_format() {
+        let conf = ConfigBuilder::new()
+            .set_format("[{level}] {message}")
+            .unwrap()
+            .set_level_format(Level::Error, "[{level}] {location}: {message}")
+            .unwrap()
+            .build();
+        let log = |level| {
+            log_to_string(
+                conf.clone(),
+                &Record::builder()
+                    .args(format_args!("hello"))
+                    .level(level)
+                    .file(Some("main.rs"))
+                    .line(Some(7))
+                    .build(),
+            )
+        };
+        assert_eq!(log(Level::Info), "[INFO] hello\n");
+        assert_eq!(log(Level::Error), "[ERROR] main.rs:7: hello\n");
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_level_format(Level::Warn, "{level}! {message}")
+            .unwrap()
+            .build();
+        let log = |level| {
+            log_to_string(
+                conf.clone(),
+                &Record::builder()
+                    .args(format_args!("hello"))
+                    .level(level)
+                    .build(),
+            )
+        };
+        assert_eq!(log(Level::Warn), "WARN! hello\n");
+        assert_eq!(log(Level::Info), "[INFO] hello\n");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_config() {
+        use crate::test_util::test_dir;
+        use serde::de::value::{Error, MapDeserializer};
+        use serde::Deserialize;
+
+        let settings = |pairs: Vec<(&'static str, &'static str)>| {
+            MapDeserializer::<_, Error>::new(pairs.into_iter())
+        };
+        let conf = Config::deserialize(settings(vec![
+            ("time", "off"),
+            ("format", "{level}: {message}"),
+            ("filters", "warn"),
+        ]))
+        .unwrap();
+        let format = |level| {
+            log_to_string(
+                conf.clone(),
+                &Record::builder()
+                    .args(format_args!("hello"))
+                    .level(level)
+                    .build(),
+            )
+        };
+        assert_eq!(format(Level::Warn), "WARN: hello\n");
+        assert_eq!(format(Level::Info), "");
+
+        assert!(Config::deserialize(settings(vec![("format", "{nope}")])).is_err());
+        assert!(Config::deserialize(settings(vec![("colour", "red")])).is_err());
+
+        let dir = test_dir("test_deserialize_config");
+        let path = dir.join("app.log");
+        let description = LoggerDescription::deserialize(MapDeserializer::<_, Error>::new(
+            vec![
+                ("type", "file"),
+                ("level", "info"),
+                ("path", path.to_str().unwrap()),
+            ]
+            .into_iter(),
+        ))
+        .unwrap();
+        let logger = description.build().unwrap();
+        log(&logger, Level::Info, "from a file");
+        logger.flush();
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains("from a file"));
+    }
+
+    #[test]
+    fn test_runtime_level_filters() {
+        let filters = RuntimeLevelFilters::new();
+        let buf = SharedBuf::default();
+        let errors = SharedBuf::default();
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let following = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_runtime_level_filters(&filters)
+            .build();
+        let logger = WriteLogger::new(LevelFilter::Error, following.clone(), buf.clone());
+        // a logger created with a stricter level, that does not opt in, is never raised
+        let combined = CombinedLogger::new(vec![
+            WriteLogger::new(LevelFilter::Info, following.clone(), std::io::sink()),
+            WriteLogger::new(LevelFilter::Error, conf.clone(), errors.clone()),
+        ]);
+        let strict = CombinedLogger::new(vec![WriteLogger::new(
+            LevelFilter::Error,
+            conf,
+            std::io::sink(),
+        )]);
+        let metadata = Metadata::builder()
+            .level(Level::Debug)
+            .target("simplelog_reload::db")
+            .build();
+        let record = Record::builder()
+            .metadata(metadata.clone())
+            .args(format_args!("slow query"))
+            .build();
+        // nested loggers are updated before the ones containing them, even if added later on
+        let nested = CombinedLogger::new(vec![]);
+        nested.add_logger(CombinedLogger::new(vec![WriteLogger::new(
+            LevelFilter::Info,
+            following,
+            std::io::sink(),
+        )]));
+        logger.log(&record);
+        assert_eq!(buf.contents(), "");
+        assert!(!combined.enabled(&metadata));
+        assert!(!nested.enabled(&metadata));
+
+        filters.set("simplelog_reload::db=debug");
+        logger.log(&record);
+        combined.log(&record);
+        assert!(buf.contents().starts_with("[DEBUG]"), "{}", buf.contents());
+        assert!(
+            buf.contents().ends_with("slow query\n"),
+            "{}",
+            buf.contents()
+        );
+        assert_eq!(errors.contents(), "");
+        assert!(combined.enabled(&metadata));
+        assert!(nested.enabled(&metadata));
+        assert!(!strict.enabled(&metadata));
+        // other filters are not followed
+        RuntimeLevelFilters::new().set("simplelog_reload=off");
+        assert!(logger.enabled(&metadata));
+
+        #[cfg(feature = "notify")]
+        {
+            let dir = crate::test_util::test_dir("test_runtime_level_filters");
+            let path = dir.join("levels");
+            std::fs::write(&path, "# quiet down\nsimplelog_reload=off\n").unwrap();
+            let watcher = filters.watch(path, std::time::Duration::from_millis(10));
+            for _ in 0..200 {
+                if !logger.enabled(&metadata) {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            assert!(!logger.enabled(&metadata));
+            assert!(!combined.enabled(&metadata));
+            drop(watcher);
+        }
+    }
+
+    #[test]
+    fn test_config_presets() {
+        let record = Record::builder()
+            .args(format_args!("hello"))
+            .level(Level::Info)
+            .target("app")
+            .file(Some("main.rs"))
+            .line(Some(7))
+            .build();
+        assert_eq!(log_to_string(Config::minimal(), &record), "hello\n");
+
+        let out = log_to_string(Config::production(), &record);
+        assert!(out.ends_with("Z [INFO] app: hello\n"), "{}", out);
+        assert_eq!(out.find('.'), Some(19), "{}", out);
+
+        let out = log_to_string(Config::verbose(), &record);
+        assert!(
+            out.contains(&format!("[INFO] [{}] (", std::process::id())),
+            "{}",
+            out
+        );
+        assert!(
+            out.ends_with("app: [main.rs:7] [<unknown>] hello\n"),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn test_line_ending() {
+        let record = Record::builder()
+            .args(format_args!("hello"))
+            .level(Level::Info)
+            .build();
+        let mut builder = ConfigBuilder::new();
+        builder.set_time_level(LevelFilter::Off);
+        builder.set_line_ending(LineEnding::Crlf);
+        assert_eq!(log_to_string(builder.build(), &record), "[INFO] hello\r\n");
+        builder.set_line_ending_custom(" ;\n");
+        assert_eq!(log_to_string(builder.build(), &record), "[INFO] hello ;\n");
+    }
+
+    #[test]
+    fn test_continuation_prefix() {
+        let record = Record::builder()
+            .args(format_args!("Point {{\n    x: 1,\n}}"))
+            .level(Level::Info)
+            .build();
+        let mut builder = ConfigBuilder::new();
+        builder.set_time_level(LevelFilter::Off);
+        builder.set_continuation_prefix("  | ");
+        assert_eq!(
+            log_to_string(builder.build(), &record),
+            "[INFO] Point {\n  |     x: 1,\n  | }\n"
+        );
+        builder.set_format("{level}: {message}").unwrap();
+        assert_eq!(
+            log_to_string(builder.build(), &record),
+            "INFO: Point {\n  |     x: 1,\n  | }\n"
+        );
+        builder.clear_continuation_prefix();
+        assert_eq!(
+            log_to_string(builder.build(), &record),
+            "INFO: Point {\n    x: 1,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_max_message_len() {
+        let mut builder = ConfigBuilder::new();
+        builder.set_time_level(LevelFilter::Off);
+        builder.set_max_message_len(Some(6));
+        let log = |config, message: &str| {
+            log_to_string(
+                config,
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(Level::Info)
+                    .build(),
+            )
+        };
+        assert_eq!(log(builder.build(), "short"), "[INFO] short\n");
+        assert_eq!(
+            log(builder.build(), "exactly"),
+            "[INFO] exactl… (7 bytes)\n"
+        );
+        // never splits a character
+        assert_eq!(log(builder.build(), "ümlauts"), "[INFO] ümlau… (8 bytes)\n");
+        assert_eq!(log(builder.build(), "aaaaaü"), "[INFO] aaaaa… (7 bytes)\n");
+    }
+
+    #[test]
+    fn test_default_logger() {
+        #[cfg(feature = "termcolor")]
+        assert!(matches!(
+            default_logger(Config::default(), true),
+            DefaultLogger::Term(_)
+        ));
+        #[cfg(not(feature = "termcolor"))]
+        assert!(matches!(
+            default_logger(Config::default(), true),
+            DefaultLogger::Simple(_)
+        ));
+        assert!(matches!(
+            default_logger(Config::default(), false),
+            DefaultLogger::Simple(_)
+        ));
+        assert_eq!(
+            default_logger(Config::default(), false)
+                .into_shared()
+                .level(),
+            LevelFilter::Info
+        );
+    }
+
+    #[test]
+    fn test_cef_format() {
+        let conf = ConfigBuilder::new()
+            .set_cef_format("Acme", "Gate|way", "1.0")
+            .set_hostname("web-1")
+            .build();
+        let out = log_to_string(
+            conf.clone(),
+            &Record::builder()
+                .args(format_args!("login failed for a=b\\c"))
+                .level(Level::Warn)
+                .target("auth")
+                .build(),
+        );
+        assert!(
+            out.starts_with("CEF:0|Acme|Gate\\|way|1.0|auth|login failed for a=b\\\\c|6|rt="),
+            "{}",
+            out
+        );
+        assert!(
+            out.ends_with(&format!(" dvchost=web-1 dvcpid={}\n", std::process::id())),
+            "{}",
+            out
+        );
+
+        // the loggers formatting records on their own write the same events
+        #[cfg(any(feature = "termcolor", feature = "test"))]
+        let record = Record::builder()
+            .args(format_args!("login failed"))
+            .level(Level::Warn)
+            .target("auth")
+            .build();
+        #[cfg(any(feature = "termcolor", feature = "test"))]
+        let prefix = "CEF:0|Acme|Gate\\|way|1.0|auth|login failed|6|rt=";
+        #[cfg(feature = "termcolor")]
+        {
+            let logger = TermLogger::new(
+                LevelFilter::Info,
+                conf.clone(),
+                TerminalMode::Stdout,
+                ColorChoice::Never,
+            );
+            let mut buf = termcolor::Buffer::no_color();
+            logger.write_record(&record, &mut buf).unwrap();
+            let out = String::from_utf8_lossy(buf.as_slice());
+            assert!(out.starts_with(prefix), "{}", out);
+        }
+        #[cfg(feature = "test")]
+        {
+            let out = crate::loggers::testlog::format(&conf, &record);
+            assert!(out.starts_with(prefix), "{}", out);
+        }
+    }
+
+    #[test]
+    fn test_time_format_str() {
+        let time = time::macros::datetime!(2024-05-03 07:04:05.123456789 +1:00);
+        let format = |format| {
+            time.format(&crate::config::parse_time_format(format).unwrap())
+                .unwrap()
+        };
+        assert_eq!(format("%Y-%m-%d %H:%M:%S%.3f"), "2024-05-03 07:04:05.123");
+        assert_eq!(
+            format("%-d.%-m.%y %l%P %:z [%f]"),
+            "3.5.24  7am +01:00 [123456789]"
+        );
+        assert_eq!(format("%e %b %T %%"), " 3 May 07:04:05 %");
+
+        assert_eq!(
+            ConfigBuilder::new().set_time_format_str("%Q").unwrap_err(),
+            FormatError::UnknownSpecifier("%Q".into())
+        );
+        assert_eq!(
+            ConfigBuilder::new()
+                .set_time_format_str("%.4f")
+                .unwrap_err(),
+            FormatError::UnknownSpecifier("%.4".into())
+        );
+    }
+
+    #[test]
+    fn test_rfc3339_precision() {
+        let format = |time, precision| {
+            let mut buf = Vec::new();
+            crate::loggers::logging::format_rfc3339(&mut buf, time, precision).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let time = time::macros::datetime!(2024-05-03 12:34:56.1234567 UTC);
+        assert_eq!(format(time, TimePrecision::Seconds), "2024-05-03T12:34:56Z");
+        assert_eq!(
+            format(time, TimePrecision::Millis),
+            "2024-05-03T12:34:56.123Z"
+        );
+        assert_eq!(
+            format(time, TimePrecision::Micros),
+            "2024-05-03T12:34:56.123456Z"
+        );
+        assert_eq!(
+            format(
+                time.to_offset(time::macros::offset!(+2)),
+                TimePrecision::Nanos
+            ),
+            "2024-05-03T14:34:56.123456700+02:00"
+        );
+    }
+
+    #[cfg(feature = "termcolor")]
+    #[test]
+    fn test_color_theme() {
+        let mut theme = ColorTheme::default();
+        assert_eq!(theme.level(Level::Error).fg(), Some(&Color::Red));
+        theme.set_level(Level::Trace, ColorSpec::new().set_dimmed(true).clone());
+        let config = ConfigBuilder::new()
+            .set_color_theme(theme)
+            .set_level_color(Level::Info, Some(Color::Green))
+            .build();
+        assert!(config.color_theme.level(Level::Trace).dimmed());
+        assert_eq!(
+            config.color_theme.level(Level::Info).fg(),
+            Some(&Color::Green)
+        );
+        assert!(ColorTheme::plain().level(Level::Warn).is_none());
+    }
+
+    #[cfg(all(feature = "termcolor", feature = "ansi_term"))]
+    #[test]
+    fn test_color_theme_styles() {
+        let mut theme = ColorTheme::plain();
+        theme
+            .set_level(
+                Level::Error,
+                ColorSpec::new()
+                    .set_fg(Some(Color::White))
+                    .set_bg(Some(Color::Red))
+                    .set_bold(true)
+                    .clone(),
+            )
+            .set_level(Level::Trace, ColorSpec::new().set_dimmed(true).clone());
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_write_log_enable_colors(true)
+            .set_color_theme(theme)
+            .build();
+        let record = |level| {
+            log_to_string(
+                config.clone(),
+                &Record::builder()
+                    .args(format_args!("styled"))
+                    .level(level)
+                    .build(),
+            )
+        };
+
+        assert_eq!(
+            record(Level::Error),
+            "\u{1b}[1;41;37m[ERROR]\u{1b}[0m styled\n"
+        );
+        assert!(record(Level::Trace).starts_with("\u{1b}[2m[TRACE]\u{1b}[0m "));
+        assert!(record(Level::Info).starts_with("[INFO] styled"));
+    }
+
+    #[cfg(all(feature = "termcolor", feature = "ansi_term"))]
+    #[test]
+    fn test_color_callback() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_write_log_enable_colors(true)
+            .set_color_theme(ColorTheme::plain())
+            .set_color_callback(|record| {
+                record
+                    .target()
+                    .starts_with("payments::")
+                    .then(|| ColorSpec::new().set_fg(Some(Color::Magenta)).clone())
+            })
+            .build();
+        let record = |target| {
+            log_to_string(
+                config.clone(),
+                &Record::builder()
+                    .args(format_args!("charged"))
+                    .level(Level::Info)
+                    .target(target)
+                    .build(),
+            )
+        };
+
+        assert_eq!(
+            record("payments::stripe"),
+            "\u{1b}[35m[INFO]\u{1b}[0m charged\n"
+        );
+        assert_eq!(record("shipping"), "[INFO] charged\n");
+    }
+
+    #[cfg(feature = "termcolor")]
+    #[test]
+    fn test_color_depth() {
+        use crate::loggers::logging::{color_depth_from_env, degrade_color, ColorDepth};
+
+        let depth = |vars: &'static [(&'static str, &'static str)]| {
+            color_depth_from_env(|name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.into())
+            })
+        };
+        assert_eq!(depth(&[]), ColorDepth::Basic);
+        assert_eq!(depth(&[("TERM", "xterm")]), ColorDepth::Basic);
+        assert_eq!(depth(&[("TERM", "xterm-256color")]), ColorDepth::Ansi256);
+        assert_eq!(
+            depth(&[("COLORTERM", "truecolor"), ("TERM", "xterm-256color")]),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(depth(&[("COLORTERM", "24bit")]), ColorDepth::TrueColor);
+
+        let orange = Color::Rgb(255, 135, 0);
+        assert_eq!(degrade_color(orange, ColorDepth::TrueColor), orange);
+        assert_eq!(
+            degrade_color(orange, ColorDepth::Ansi256),
+            Color::Ansi256(208)
+        );
+        assert_eq!(degrade_color(orange, ColorDepth::Basic), Color::Yellow);
+        assert_eq!(
+            degrade_color(Color::Rgb(128, 128, 128), ColorDepth::Ansi256),
+            Color::Ansi256(244)
+        );
+        assert_eq!(
+            degrade_color(Color::Ansi256(201), ColorDepth::Basic),
+            Color::Magenta
+        );
+        assert_eq!(
+            degrade_color(Color::Ansi256(9), ColorDepth::Basic),
+            Color::Red
+        );
+        assert_eq!(
+            degrade_color(Color::Ansi256(235), ColorDepth::Basic),
+            Color::Black
+        );
+        assert_eq!(degrade_color(Color::Blue, ColorDepth::Basic), Color::Blue);
+
+        let mut theme = ColorTheme::plain();
+        theme.set_level(
+            Level::Info,
+            ColorSpec::new()
+                .set_fg(Some(orange))
+                .set_bg(Some(Color::Ansi256(17)))
+                .set_bold(true)
+                .clone(),
+        );
+        let theme = theme.degraded(ColorDepth::Basic);
+        assert_eq!(theme.level(Level::Info).fg(), Some(&Color::Yellow));
+        assert_eq!(theme.level(Level::Info).bg(), Some(&Color::Black));
+        assert!(theme.level(Level::Info).bold());
+    }
+
+    #[cfg(feature = "termcolor")]
+    #[test]
+    fn test_color_choice_from_env() {
+        use crate::loggers::logging::color_choice_from_env;
+
+        let resolve = |vars: &'static [(&'static str, &'static str)]| {
+            color_choice_from_env(ColorChoice::Auto, |name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.into())
+            })
+        };
+        assert_eq!(resolve(&[]), ColorChoice::Auto);
+        assert_eq!(resolve(&[("NO_COLOR", "1")]), ColorChoice::Never);
+        assert_eq!(resolve(&[("NO_COLOR", "")]), ColorChoice::Auto);
+        assert_eq!(resolve(&[("CLICOLOR_FORCE", "1")]), ColorChoice::Always);
+        assert_eq!(resolve(&[("CLICOLOR_FORCE", "0")]), ColorChoice::Auto);
+        assert_eq!(
+            resolve(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")]),
+            ColorChoice::Never
+        );
+        assert_eq!(resolve(&[("CLICOLOR", "0")]), ColorChoice::Never);
+        assert_eq!(resolve(&[("TERM", "dumb")]), ColorChoice::Never);
+        assert_eq!(resolve(&[("TERM", "xterm-256color")]), ColorChoice::Auto);
+        assert_eq!(
+            resolve(&[("CI", "true"), ("GITHUB_ACTIONS", "true")]),
+            ColorChoice::AlwaysAnsi
+        );
+        assert_eq!(resolve(&[("CI", "true")]), ColorChoice::Never);
+        assert_eq!(resolve(&[("CI", "false")]), ColorChoice::Auto);
+        assert_eq!(
+            resolve(&[("GITHUB_ACTIONS", "true"), ("NO_COLOR", "1")]),
+            ColorChoice::Never
+        );
+        assert_eq!(
+            resolve(&[("CI", "true"), ("CLICOLOR_FORCE", "1")]),
+            ColorChoice::Always
+        );
+        assert_eq!(
+            color_choice_from_env(ColorChoice::Always, |_| Some("1".into())),
+            ColorChoice::Always
+        );
+    }
+
+    #[cfg(all(unix, feature = "termcolor"))]
+    #[test]
+    fn test_location_url() {
+        use crate::loggers::termlog::location_url;
+
+        assert_eq!(
+            location_url("vscode://file{path}:{line}", "/src/my app/main.rs", Some(7)),
+            "vscode://file/src/my%20app/main.rs:7"
+        );
+        assert_eq!(
+            location_url("file://{path}", "src/main.rs", None),
+            format!(
+                "file://{}/src/main.rs",
+                std::env::current_dir().unwrap().display()
+            )
+        );
+    }
+
+    #[cfg(feature = "termcolor")]
+    #[test]
+    fn test_forced_colors() {
+        use crate::loggers::logging::color_choice_from_env;
+        use crate::loggers::termlog::stream_choice;
+
+        let no_color = |name: &str| match name {
+            "NO_COLOR" => Some("1".into()),
+            _ => None,
+        };
+        let piped = |choice| stream_choice(color_choice_from_env(choice, no_color), false);
+        assert_eq!(piped(ColorChoice::Always), ColorChoice::Always);
+        assert_eq!(piped(ColorChoice::AlwaysAnsi), ColorChoice::AlwaysAnsi);
+        assert_eq!(piped(ColorChoice::Auto), ColorChoice::Never);
+        assert_eq!(stream_choice(ColorChoice::Auto, false), ColorChoice::Never);
+        assert_eq!(stream_choice(ColorChoice::Auto, true), ColorChoice::Auto);
+        assert_eq!(
+            color_choice_from_env(ColorChoice::Never, |name| match name {
+                "CLICOLOR_FORCE" => Some("1".into()),
+                _ => None,
+            }),
+            ColorChoice::Never
+        );
+    }
+
+    #[test]
+    fn test_stderr_level() {
+        use crate::loggers::logging::use_stderr;
+
+        let record = |level| Record::builder().level(level).build();
+        let config = Config::default();
+        assert!(use_stderr(&config, &record(Level::Error)));
+        assert!(!use_stderr(&config, &record(Level::Warn)));
+
+        let config = ConfigBuilder::new()
+            .set_stderr_level(LevelFilter::Warn)
+            .build();
+        assert!(use_stderr(&config, &record(Level::Warn)));
+        assert!(!use_stderr(&config, &record(Level::Info)));
+
+        let config = ConfigBuilder::new()
+            .set_stderr_level(LevelFilter::Off)
+            .build();
+        assert!(!use_stderr(&config, &record(Level::Error)));
+    }
+
+    #[test]
+    fn test_print_gate() {
+        use crate::loggers::logging::print_gated;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let calls = std::sync::Arc::new(AtomicUsize::new(0));
+        let gate_calls = calls.clone();
+        let config = ConfigBuilder::new()
+            .set_print_gate(move |print| {
+                gate_calls.fetch_add(1, Ordering::SeqCst);
+                print()
+            })
+            .build();
+
+        let mut printed = false;
+        print_gated(&config, || printed = true);
+        assert!(printed);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_level_labels() {
+        let record = |config: &Config, level| {
+            log_to_string(
+                config.clone(),
+                &Record::builder()
+                    .args(format_args!("disk full"))
+                    .level(level)
+                    .build(),
+            )
+        };
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_level_padding(LevelPadding::Right)
+            .set_level_label(Level::Error, "ERR")
+            .set_level_label(Level::Warn, "WARNING")
+            .build();
+        assert_eq!(record(&config, Level::Error), "[ERR    ] disk full\n");
+        assert_eq!(record(&config, Level::Warn), "[WARNING] disk full\n");
+        assert_eq!(record(&config, Level::Info), "[INFO   ] disk full\n");
+
+        let config = ConfigBuilder::new()
+            .set_format("{level} {message}")
+            .unwrap()
+            .set_level_label(Level::Warn, "WRN")
+            .build();
+        assert_eq!(record(&config, Level::Warn), "WRN disk full\n");
+
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_level_symbols(LevelSymbols::Before)
+            .build();
+        assert_eq!(record(&config, Level::Warn), "[⚠ WARN] disk full\n");
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_level_symbols(LevelSymbols::Instead)
+            .build();
+        assert_eq!(record(&config, Level::Error), "[✖] disk full\n");
+    }
+
+    #[test]
+    fn test_target_filters() {
+        let config = ConfigBuilder::new()
+            .add_filter_allow_str("my_app")
+            .add_filter_ignore_str("my_app::noisy")
+            .build();
+        let logger = NullLogger::new(LevelFilter::Info, config);
+        let enabled = |target| {
+            logger.enabled(
+                &Metadata::builder()
+                    .level(Level::Info)
+                    .target(target)
+                    .build(),
+            )
+        };
+        assert!(enabled("my_app::db"));
+        assert!(!enabled("my_app::noisy::poll"));
+        assert!(!enabled("hyper"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_filters() {
+        let config = ConfigBuilder::new()
+            .add_filter_allow_regex("^(my_app|hyper)(::|$)")
+            .unwrap()
+            .add_filter_ignore_regex("::noisy$")
+            .unwrap()
+            .build();
+        let logger = NullLogger::new(LevelFilter::Info, config);
+        let enabled = |target| {
+            logger.enabled(
+                &Metadata::builder()
+                    .level(Level::Info)
+                    .target(target)
+                    .build(),
+            )
+        };
+        assert!(enabled("my_app::db"));
+        assert!(enabled("hyper"));
+        assert!(!enabled("my_app::noisy"));
+        assert!(!enabled("my_application"));
+
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .add_message_filter_allow_regex("^request")
+            .unwrap()
+            .add_message_filter_ignore_regex("/health$")
+            .unwrap()
+            .build();
+        let request = |path: &str| {
+            log_to_string(
+                config.clone(),
+                &Record::builder()
+                    .args(format_args!("request {}", path))
+                    .level(Level::Info)
+                    .build(),
+            )
+        };
+        assert_eq!(request("/users"), "[INFO] request /users\n");
+        assert_eq!(request("/health"), "");
+        let record = Record::builder()
+            .args(format_args!("started"))
+            .level(Level::Info)
+            .build();
+        assert_eq!(log_to_string(config, &record), "");
+
+        assert!(ConfigBuilder::new().add_filter_allow_regex("(").is_err());
+    }
+
+    #[test]
+    fn test_redactor() {
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_redactor(|message| match message.contains("hunter2") {
+                true => std::borrow::Cow::Owned(message.replace("hunter2", "***")),
+                false => std::borrow::Cow::Borrowed(message),
+            })
+            .build();
+        let log = |password: &str| {
+            log_to_string(
+                conf.clone(),
+                &Record::builder()
+                    .args(format_args!("login with {}", password))
+                    .level(Level::Info)
+                    .build(),
+            )
+        };
+        assert_eq!(log("hunter2"), "[INFO] login with ***\n");
+        assert_eq!(log("swordfish"), "[INFO] login with swordfish\n");
+    }
+
+    #[test]
+    fn test_transforms() {
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Error)
+            .add_transform(|record: &mut OwnedRecord| record.target != "noisy")
+            .add_transform(|record: &mut OwnedRecord| {
+                if record.message.starts_with("fatal") {
+                    record.level = Level::Error;
+                }
+                record.target = record.target.to_uppercase();
+                true
+            })
+            .build();
+        let log = |target: &str, message: &str| {
+            log_to_string(
+                conf.clone(),
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(Level::Info)
+                    .target(target)
+                    .build(),
+            )
+        };
+        assert_eq!(log("noisy", "hello"), "");
+        assert_eq!(log("app", "hello"), "[INFO] APP: hello\n");
+        assert_eq!(
+            log("app", "fatal: disk full"),
+            "[ERROR] APP: fatal: disk full\n"
+        );
+    }
+
+    #[test]
+    fn test_record_filters() {
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .add_record_filter(|record| !record.args().to_string().contains("customer="))
+            .add_record_filter(|record| record.line() != Some(0))
+            .build();
+        let log = |message: &str, line| {
+            log_to_string(
+                conf.clone(),
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(Level::Info)
+                    .line(Some(line))
+                    .build(),
+            )
+        };
+        assert_eq!(log("order placed", 1), "[INFO] order placed\n");
+        assert_eq!(log("order placed by customer=42", 1), "");
+        assert_eq!(log("order placed", 0), "");
+
+        let mut builder = ConfigBuilder::new();
+        builder
+            .set_time_level(LevelFilter::Off)
+            .add_record_filter(|_| false)
+            .clear_record_filters();
+        assert_eq!(
+            log_to_string(
+                builder.build(),
+                &Record::builder()
+                    .args(format_args!("kept"))
+                    .level(Level::Info)
+                    .build()
+            ),
+            "[INFO] kept\n"
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let conf = ConfigBuilder::new()
+            .add_transform(|record: &mut OwnedRecord| record.message != "drop me")
+            .set_rate_limit(1, std::time::Duration::from_secs(60))
+            .build();
+        let logger = WriteLogger::new(LevelFilter::Info, conf, std::io::sink());
+        let log = |message: &str| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{}", message))
+                    .level(Level::Warn)
+                    .target("test_stats")
+                    .build(),
+            )
+        };
+
+        // other tests log concurrently, so only lower bounds can be checked
+        let before = stats();
+        log("drop me");
+        log("first");
+        log("second");
+        let after = stats();
+        assert!(after.emitted(Level::Warn) > before.emitted(Level::Warn));
+        assert!(after.total() > before.total());
+        assert!(after.dropped > before.dropped);
+        assert!(after.suppressed > before.suppressed);
+        assert!(after.rate_limited > before.rate_limited);
+    }
+
+    #[test]
+    fn test_lost_records() {
+        use crate::stats::loss_summary;
+        use std::time::Duration;
+
+        struct Failing;
+
+        impl std::io::Write for Failing {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let logger = WriteLogger::new(LevelFilter::Info, Config::default(), Failing);
+        let before = stats();
+        log(&logger, Level::Info, "lost");
+        let after = stats();
+        assert!(after.write_errors > before.write_errors);
+        assert!(after.lost() > before.lost());
+
+        let interval = Duration::from_secs(60);
+        assert_eq!(loss_summary(&before, &before, interval), None);
+        let mut after = before;
+        after.queue_full += 2;
+        after.write_errors += 1;
+        assert_eq!(
+            loss_summary(&before, &after, interval).unwrap(),
+            "lost 3 log records in the last 60s: 2 dropped by a full queue, 1 not written due to errors"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_rate_limit(2, std::time::Duration::from_millis(200))
+            .build();
+        let buf = SharedBuf::default();
+        let logger = WriteLogger::new(LevelFilter::Trace, config, buf.clone());
+        let log = |target| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("hot"))
+                    .level(Level::Warn)
+                    .target(target)
+                    .build(),
+            )
+        };
+        for _ in 0..5 {
+            log("hot_loop");
+        }
+        log("other");
+        assert_eq!(buf.contents(), "[WARN] hot\n[WARN] hot\n[WARN] hot\n");
+
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        log("hot_loop");
+        assert_eq!(
+            buf.contents(),
+            "[WARN] hot\n[WARN] hot\n[WARN] hot\n[WARN] suppressed 3 messages\n[WARN] hot\n"
+        );
+    }
+
+    #[test]
+    fn test_fold_repeats() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_fold_repeats(std::time::Duration::from_millis(200))
+            .build();
+        let buf = SharedBuf::default();
+        let logger = WriteLogger::new(LevelFilter::Trace, config, buf.clone());
+        let log = |message| log(&logger, Level::Warn, message);
+        for _ in 0..4 {
+            log("disk full");
+        }
+        log("disk ok");
+        log("disk ok");
+        assert_eq!(
+            buf.contents(),
+            "[WARN] disk full\n[WARN] last message repeated 3 times\n[WARN] disk ok\n"
+        );
+
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        log("disk ok");
+        assert_eq!(
+            buf.contents(),
+            "[WARN] disk full\n[WARN] last message repeated 3 times\n[WARN] disk ok\n[WARN] last message repeated 1 times\n"
+        );
+    }
+
+    #[test]
+    fn test_sampling() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_sampling(LevelFilter::Warn, 3)
+            .build();
+        let buf = SharedBuf::default();
+        let logger = WriteLogger::new(LevelFilter::Trace, config, buf.clone());
+        for i in 0..7 {
+            log(&logger, Level::Info, &i.to_string());
+            log(&logger, Level::Error, &i.to_string());
+        }
+        let contents = buf.contents();
+        let info: Vec<_> = contents
+            .lines()
+            .filter(|line| line.starts_with("[INFO]"))
+            .collect();
+        assert_eq!(info, ["[INFO] 0", "[INFO] 3", "[INFO] 6"]);
+        assert_eq!(
+            contents
+                .lines()
+                .filter(|line| line.starts_with("[ERROR]"))
+                .count(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        let record = |level| {
+            Record::builder()
+                .args(format_args!("buffered"))
+                .level(level)
+                .build()
+        };
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_flush_policy(FlushPolicy::EveryRecords(2))
+            .build();
+        let buf = SharedBuf::default();
+        let logger = WriteLogger::new(LevelFilter::Info, config, buf.clone());
+        logger.log(&record(Level::Info));
+        assert_eq!(buf.contents(), "");
+        logger.log(&record(Level::Info));
+        assert_eq!(buf.contents(), "[INFO] buffered\n[INFO] buffered\n");
+
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_flush_policy(FlushPolicy::AtLevel(Level::Warn))
+            .build();
+        let buf = SharedBuf::default();
+        let logger = WriteLogger::new(LevelFilter::Info, config, buf.clone());
+        logger.log(&record(Level::Info));
+        assert_eq!(buf.contents(), "");
+        logger.log(&record(Level::Warn));
+        assert_eq!(buf.contents(), "[INFO] buffered\n[WARN] buffered\n");
+        logger.log(&record(Level::Info));
+        logger.flush();
+        assert_eq!(
+            buf.contents(),
+            "[INFO] buffered\n[WARN] buffered\n[INFO] buffered\n"
+        );
+
+        /// Writer counting how often it is flushed
+        struct Flushes(SharedBuf, std::sync::Arc<std::sync::atomic::AtomicUsize>);
+        impl std::io::Write for Flushes {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.1.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                Ok(())
+            }
+        }
+
+        // by default records are written right away, but the writer is only flushed with the logger
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let buf = SharedBuf::default();
+        let flushes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let logger = WriteLogger::new(
+            LevelFilter::Info,
+            config,
+            Flushes(buf.clone(), flushes.clone()),
+        );
+        logger.log(&record(Level::Info));
+        logger.log(&record(Level::Info));
+        assert_eq!(buf.contents(), "[INFO] buffered\n[INFO] buffered\n");
+        assert_eq!(flushes.load(std::sync::atomic::Ordering::Relaxed), 0);
+        logger.flush();
+        assert_eq!(flushes.load(std::sync::atomic::Ordering::Relaxed), 1);
+
+        // dropping the logger writes the buffered records and flushes the writer, like a `BufWriter`
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_flush_policy(FlushPolicy::EveryRecords(10))
+            .build();
+        let buf = SharedBuf::default();
+        let flushes = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let logger = WriteLogger::new(
+            LevelFilter::Info,
+            config,
+            Flushes(buf.clone(), flushes.clone()),
+        );
+        logger.log(&record(Level::Info));
+        assert_eq!(buf.contents(), "");
+        drop(CombinedLogger::new(vec![logger]));
+        assert_eq!(buf.contents(), "[INFO] buffered\n");
+        assert_eq!(flushes.load(std::sync::atomic::Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_pid() {
+        let record = Record::builder()
+            .args(format_args!("started"))
+            .level(Level::Info)
+            .build();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_pid_level(LevelFilter::Info)
+            .build();
+        assert_eq!(
+            log_to_string(config, &record),
+            format!("[INFO] [{}] started\n", std::process::id())
+        );
+
+        let config = ConfigBuilder::new()
+            .set_format("{pid} {message}")
+            .unwrap()
+            .build();
+        assert_eq!(
+            log_to_string(config, &record),
+            format!("{} started\n", std::process::id())
+        );
+    }
+
+    #[test]
+    fn test_hostname() {
+        let record = Record::builder()
+            .args(format_args!("started"))
+            .level(Level::Info)
+            .build();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_hostname_level(LevelFilter::Info)
+            .set_hostname("web-1")
+            .build();
+        assert_eq!(log_to_string(config, &record), "web-1 [INFO] started\n");
+
+        let config = ConfigBuilder::new()
+            .set_format("{hostname}: {message}")
+            .unwrap()
+            .build();
+        assert!(!log_to_string(config, &record).starts_with(": "));
+    }
+
+    #[test]
+    fn test_backtrace() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_backtrace_level(LevelFilter::Warn)
+            .build();
+        let record = |level| {
+            log_to_string(
+                config.clone(),
+                &Record::builder()
+                    .args(format_args!("failure"))
+                    .level(level)
+                    .build(),
+            )
+        };
+
+        let error = record(Level::Error);
+        assert!(
+            error.starts_with("[ERROR] failure\nstack backtrace:\n"),
+            "{}",
+            error
+        );
+        assert!(error.ends_with('\n') && !error.ends_with("\n\n"));
+        assert_eq!(record(Level::Info), "[INFO] failure\n");
+
+        // the backtrace is the one of the call site, not the one of the background thread
+        let buf = SharedBuf::default();
+        let logger = AsyncLogger::new(WriteLogger::new(LevelFilter::Info, config, buf.clone()));
+        assert_eq!(logger.backtrace_level(), LevelFilter::Warn);
+        log(&logger, Level::Error, "failure");
+        logger.flush();
+        let error = buf.contents();
+        assert!(error.contains("test_backtrace"), "{}", error);
+        assert!(!error.contains("run_worker"), "{}", error);
+    }
+
+    #[cfg(feature = "kv")]
+    #[test]
+    fn test_key_values() {
+        let kvs = [("user", "alice"), ("note", "two words"), ("empty", "")];
+        let record = || {
+            Record::builder()
+                .args(format_args!("login"))
+                .level(Level::Info)
+                .key_values(&kvs)
+                .build()
+        };
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        assert_eq!(
+            log_to_string(config, &record()),
+            "[INFO] login user=alice note=\"two words\" empty=\"\"\n"
+        );
+
+        let config = ConfigBuilder::new()
+            .set_format("{message} ({kv})")
+            .unwrap()
+            .build();
+        assert_eq!(
+            log_to_string(config, &record()),
+            "login (user=alice note=\"two words\" empty=\"\")\n"
+        );
+    }
+
+    #[test]
+    fn test_level_filters() {
+        let conf = ConfigBuilder::new()
+            .add_level_filter_str("hyper", LevelFilter::Warn)
+            .add_level_filter_str("my_app::db", LevelFilter::Trace)
+            .add_level_filter("my_app::db::pool".to_string(), LevelFilter::Off)
+            .build();
+        let logger = SimpleLogger::new(LevelFilter::Info, conf);
+        let enabled = |level: Level, target: &str| {
+            logger.enabled(&Metadata::builder().level(level).target(target).build())
+        };
+
+        assert_eq!(logger.level(), LevelFilter::Trace);
+        assert!(enabled(Level::Info, "my_app"));
+        assert!(!enabled(Level::Debug, "my_app"));
+        assert!(!enabled(Level::Info, "hyper::client"));
+        assert!(enabled(Level::Warn, "hyper::client"));
+        assert!(enabled(Level::Trace, "my_app::db"));
+        assert!(!enabled(Level::Error, "my_app::db::pool"));
+    }
+
+    #[test]
+    fn test_level_filters_from_spec() {
+        let conf = ConfigBuilder::new()
+            .add_level_filters_from_spec("warn, my_app=info,my_app::db,hyper=bogus/regex")
+            .build();
+        let logger = SimpleLogger::new(LevelFilter::Off, conf);
+        let enabled = |level: Level, target: &str| {
+            logger.enabled(&Metadata::builder().level(level).target(target).build())
+        };
+
+        assert!(enabled(Level::Warn, "hyper"));
+        assert!(!enabled(Level::Info, "hyper"));
+        assert!(enabled(Level::Info, "my_app"));
+        assert!(!enabled(Level::Debug, "my_app"));
+        assert!(enabled(Level::Trace, "my_app::db"));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_bridge() {
+        let buf = SharedBuf::default();
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Error)
+            .build();
+        let bridge =
+            TracingBridge::with_logger(WriteLogger::new(LevelFilter::Info, conf, buf.clone()));
+        tracing_core::dispatcher::with_default(&tracing_core::Dispatch::new(bridge), || {
+            tracing::warn!(target: "db", attempt = 3, "connection {} lost", "primary");
+            tracing::debug!("not logged");
+        });
+        assert_eq!(
+            buf.contents(),
+            "[WARN] db: connection primary lost attempt=3\n"
+        );
+    }
+
+    #[test]
+    fn test_context() {
+        let record = Record::builder()
+            .args(format_args!("hello"))
+            .level(Level::Info)
+            .build();
+        let mut builder = ConfigBuilder::new();
+        builder.set_time_level(LevelFilter::Off);
+
+        context().insert("request_id", 42).insert("user", "a b");
+        context().insert("request_id", 43);
+        assert_eq!(context().get("request_id").as_deref(), Some("43"));
+        assert_eq!(
+            log_to_string(builder.build(), &record),
+            "[INFO] hello request_id=43 user=\"a b\"\n"
+        );
+
+        // records logged by a background thread keep the context of the logging thread
+        let buf = SharedBuf::default();
+        let logger = AsyncLogger::new(WriteLogger::new(
+            LevelFilter::Info,
+            builder.build(),
+            buf.clone(),
+        ));
+        logger.log(&record);
+        logger.flush();
+        assert_eq!(buf.contents(), "[INFO] hello request_id=43 user=\"a b\"\n");
+
+        assert_eq!(context().remove("user").as_deref(), Some("a b"));
+        assert_eq!(
+            log_to_string(builder.build(), &record),
+            "[INFO] hello request_id=43\n"
+        );
+        context().clear();
+        assert_eq!(log_to_string(builder.build(), &record), "[INFO] hello\n");
+    }
+
+    #[test]
+    fn test_scoped_context() {
+        {
+            let _job = scoped_context("job", 1);
+            assert_eq!(context().get("job").as_deref(), Some("1"));
+            {
+                let _job = scoped_context("job", 2);
+                let _step = scoped_context("step", "load");
+                assert_eq!(context().get("job").as_deref(), Some("2"));
+            }
+            assert_eq!(context().get("job").as_deref(), Some("1"));
+            assert_eq!(context().get("step"), None);
+        }
+        assert_eq!(context().get("job"), None);
+    }
 }
@@ -0,0 +1,598 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the AsyncLogger Implementation
+
+use super::logging::{record_key_values, with_origin, Origin};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record, RecordBuilder, SetLoggerError};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A record, that was captured to be written on the background thread
+struct OwnedRecord {
+    level: Level,
+    target: String,
+    message: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+    key_values: Vec<(String, String)>,
+    origin: Origin,
+}
+
+impl OwnedRecord {
+    fn capture(record: &Record<'_>, backtrace: LevelFilter) -> OwnedRecord {
+        OwnedRecord {
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+            module_path: record.module_path().map(str::to_string),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+            key_values: record_key_values(record),
+            origin: Origin::capture(record, backtrace),
+        }
+    }
+
+    fn log(self, logger: &dyn SharedLogger) {
+        let OwnedRecord {
+            level,
+            target,
+            message,
+            module_path,
+            file,
+            line,
+            key_values,
+            origin,
+        } = self;
+        with_origin(origin, || {
+            logger.log(
+                &with_key_values(
+                    Record::builder()
+                        .args(format_args!("{}", message))
+                        .level(level)
+                        .target(&target)
+                        .module_path(module_path.as_deref())
+                        .file(file.as_deref())
+                        .line(line),
+                    &key_values,
+                )
+                .build(),
+            )
+        });
+    }
+}
+
+#[cfg(feature = "kv")]
+fn with_key_values<'a, 'b>(
+    builder: &'b mut RecordBuilder<'a>,
+    key_values: &'a dyn log::kv::Source,
+) -> &'b mut RecordBuilder<'a> {
+    builder.key_values(key_values)
+}
+
+#[cfg(not(feature = "kv"))]
+fn with_key_values<'a, 'b>(
+    builder: &'b mut RecordBuilder<'a>,
+    _key_values: &'a [(String, String)],
+) -> &'b mut RecordBuilder<'a> {
+    builder
+}
+
+enum Message {
+    Record(Box<OwnedRecord>),
+    Flush(Sender<()>),
+}
+
+/// What an [`AsyncLogger`] with a bounded queue does with records, that do not fit into the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Block the logging thread until there is space in the queue
+    Block,
+    /// Drop the record, that is about to be logged
+    DropNewest,
+    /// Drop the oldest record in the queue to make space for the new one
+    DropOldest,
+}
+
+/// How often the number of dropped records is reported
+const DROP_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+struct QueueState {
+    messages: VecDeque<Message>,
+    records: usize,
+    closed: bool,
+}
+
+/// Queue shared between the logging threads and the background thread
+struct Queue {
+    state: Mutex<QueueState>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: Option<(usize, OverflowPolicy)>,
+    dropped: AtomicU64,
+}
+
+impl Queue {
+    /// Returns `false` if the queue was closed
+    fn push_record(&self, record: OwnedRecord) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if let Some((capacity, policy)) = self.capacity {
+            while !state.closed && state.records >= capacity {
+                match policy {
+                    OverflowPolicy::Block => state = self.not_full.wait(state).unwrap(),
+                    OverflowPolicy::DropNewest => {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        stats::count_queue_full();
+                        return true;
+                    }
+                    OverflowPolicy::DropOldest => {
+                        // never drop flush requests, somebody is waiting for those
+                        if let Some(pos) = state
+                            .messages
+                            .iter()
+                            .position(|m| matches!(m, Message::Record(_)))
+                        {
+                            state.messages.remove(pos);
+                            state.records -= 1;
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                            stats::count_queue_full();
+                        }
+                    }
+                }
+            }
+        }
+        if state.closed {
+            return false;
+        }
+        state.messages.push_back(Message::Record(Box::new(record)));
+        state.records += 1;
+        self.not_empty.notify_one();
+        true
+    }
+
+    /// Flush requests are not limited by the capacity. Returns `false` if the queue was closed
+    fn push_flush(&self, done: Sender<()>) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return false;
+        }
+        state.messages.push_back(Message::Flush(done));
+        self.not_empty.notify_one();
+        true
+    }
+
+    /// Waits for the next message. Fails with `true` if the queue is closed and empty
+    /// and with `false` if the timeout elapsed.
+    fn pop(&self, timeout: Duration) -> Result<Message, bool> {
+        let mut state = self.state.lock().unwrap();
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(message) = state.messages.pop_front() {
+                if let Message::Record(_) = message {
+                    state.records -= 1;
+                    self.not_full.notify_one();
+                }
+                return Ok(message);
+            }
+            if state.closed {
+                return Err(true);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(false);
+            }
+            state = self
+                .not_empty
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+}
+
+/// Calls `f`, counting a panic of the wrapped logger as a write error instead of unwinding,
+/// which would end the worker and leave the queue without consumer
+fn guarded<F: FnOnce()>(f: F) {
+    if panic::catch_unwind(AssertUnwindSafe(f)).is_err() {
+        stats::count_write_error();
+    }
+}
+
+fn run_worker(queue: &Queue, logger: &dyn SharedLogger) {
+    let mut reported = 0;
+    let mut next_report = Instant::now() + DROP_REPORT_INTERVAL;
+    loop {
+        let closed = match queue.pop(DROP_REPORT_INTERVAL) {
+            Ok(Message::Record(record)) => {
+                guarded(|| (*record).log(logger));
+                false
+            }
+            Ok(Message::Flush(done)) => {
+                guarded(|| logger.flush());
+                let _ = done.send(());
+                false
+            }
+            Err(closed) => closed,
+        };
+
+        if closed || Instant::now() >= next_report {
+            let dropped = queue.dropped.load(Ordering::Relaxed);
+            if dropped > reported {
+                guarded(|| {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!(
+                                "dropped {} log records, because the queue was full",
+                                dropped - reported
+                            ))
+                            .level(Level::Warn)
+                            .target("simplelog")
+                            .build(),
+                    )
+                });
+                reported = dropped;
+            }
+            next_report = Instant::now() + DROP_REPORT_INTERVAL;
+        }
+
+        if closed {
+            guarded(|| logger.flush());
+            return;
+        }
+    }
+}
+
+/// The AsyncLogger struct. Provides a Logger implementation, that wraps another logger
+/// and hands records to a dedicated background thread, which writes them using the wrapped logger.
+///
+/// Logging only captures the record (formatting its message) and pushes it onto a queue,
+/// so slow sinks, like files or network connections, do not block the logging thread.
+/// The time and thread written by the loggers of this crate are the ones of the logging thread.
+///
+/// The queue is unbounded by default, use [`AsyncLogger::bounded`] to limit its size.
+/// The number of records dropped due to a full queue is logged by the background thread
+/// every ten seconds.
+///
+/// Records still queued when the program exits are lost, unless `flush()` or `shutdown()` are called.
+pub struct AsyncLogger {
+    logger: Arc<dyn SharedLogger>,
+    queue: Arc<Queue>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl AsyncLogger {
+    /// init function. Globally initializes the AsyncLogger as the one and only used log facility.
+    ///
+    /// Takes the logger to wrap as an argument. It should not already be set globally.
+    /// Fails if another logger is already set globally.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let handle = AsyncLogger::init(
+    ///     WriteLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap())
+    /// ).unwrap();
+    /// // ...
+    /// handle.shutdown();
+    /// # }
+    /// ```
+    pub fn init(
+        logger: Box<dyn SharedLogger>,
+    ) -> Result<LoggerHandle<AsyncLogger>, SetLoggerError> {
+        handle::init(AsyncLogger::new(logger))
+    }
+
+    /// Globally initializes an AsyncLogger with a bounded queue as the one and only used log facility.
+    ///
+    /// See [`AsyncLogger::bounded`] for details.
+    /// Fails if another logger is already set globally.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let _ = AsyncLogger::init_bounded(
+    ///     WriteLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap()),
+    ///     1024,
+    ///     OverflowPolicy::DropOldest,
+    /// );
+    /// # }
+    /// ```
+    pub fn init_bounded(
+        logger: Box<dyn SharedLogger>,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Result<LoggerHandle<AsyncLogger>, SetLoggerError> {
+        handle::init(AsyncLogger::bounded(logger, capacity, policy))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the logger to wrap as an argument and spawns the background thread.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let async_logger = AsyncLogger::new(
+    ///     WriteLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap())
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(logger: Box<dyn SharedLogger>) -> Box<AsyncLogger> {
+        AsyncLogger::with_queue(logger, None)
+    }
+
+    /// Create a new AsyncLogger, whose queue holds at most `capacity` records.
+    ///
+    /// `policy` decides what happens, when a record is logged while the queue is full.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let async_logger = AsyncLogger::bounded(
+    ///     WriteLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap()),
+    ///     1024,
+    ///     OverflowPolicy::Block,
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn bounded(
+        logger: Box<dyn SharedLogger>,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Box<AsyncLogger> {
+        AsyncLogger::with_queue(logger, Some((capacity.max(1), policy)))
+    }
+
+    fn with_queue(
+        logger: Box<dyn SharedLogger>,
+        capacity: Option<(usize, OverflowPolicy)>,
+    ) -> Box<AsyncLogger> {
+        let logger: Arc<dyn SharedLogger> = Arc::from(logger);
+        let queue = Arc::new(Queue {
+            state: Mutex::new(QueueState {
+                messages: VecDeque::new(),
+                records: 0,
+                closed: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+            dropped: AtomicU64::new(0),
+        });
+
+        let (worker_logger, worker_queue) = (logger.clone(), queue.clone());
+        let worker = thread::Builder::new()
+            .name("simplelog-async".into())
+            .spawn(move || run_worker(&worker_queue, &*worker_logger))
+            .expect("failed to spawn logging thread");
+
+        Box::new(AsyncLogger {
+            logger,
+            queue,
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+
+    /// Returns the total number of records dropped, because the queue was full
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Writes all queued records and stops the background thread.
+    ///
+    /// Records logged afterwards are written synchronously by the wrapped logger.
+    pub fn shutdown(&self) {
+        // the worker drains the queue before exiting
+        self.queue.close();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Log for AsyncLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.logger.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let owned = OwnedRecord::capture(record, self.logger.backtrace_level());
+            if !self.queue.push_record(owned) {
+                self.logger.log(record);
+            }
+        }
+    }
+
+    /// Blocks until all records queued so far are written, then flushes the wrapped logger.
+    fn flush(&self) {
+        let (done, wait) = channel();
+        if self.queue.push_flush(done) {
+            let _ = wait.recv();
+        } else {
+            self.logger.flush();
+        }
+    }
+}
+
+impl Drop for AsyncLogger {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl SharedLogger for AsyncLogger {
+    fn level(&self) -> LevelFilter {
+        self.logger.level()
+    }
+
+    fn config(&self) -> Option<&Config> {
+        self.logger.config()
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.logger.set_level(level);
+    }
+
+    fn backtrace_level(&self) -> LevelFilter {
+        self.logger.backtrace_level()
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, SharedBuf};
+    use crate::{ConfigBuilder, ThreadLogMode, WriteLogger};
+
+    #[test]
+    fn test_async_logger() {
+        let buf = SharedBuf::default();
+        let conf = ConfigBuilder::new()
+            .set_format("{thread} {message}")
+            .unwrap()
+            .set_thread_mode(ThreadLogMode::Names)
+            .build();
+        let logger = AsyncLogger::new(WriteLogger::new(LevelFilter::Info, conf, buf.clone()));
+
+        std::thread::Builder::new()
+            .name("producer".into())
+            .spawn(move || {
+                for i in 0..3 {
+                    log(&logger, Level::Info, &format!("record {}", i));
+                }
+                logger.flush();
+                assert_eq!(
+                    buf.contents(),
+                    "producer record 0\nproducer record 1\nproducer record 2\n"
+                );
+                logger.shutdown();
+                log(&logger, Level::Info, "sync");
+                assert!(buf.contents().ends_with("producer sync\n"));
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_async_logger_panic() {
+        /// Logger panicking for records saying "boom"
+        struct Panicking(SharedBuf);
+        impl Log for Panicking {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn log(&self, record: &Record<'_>) {
+                let message = record.args().to_string();
+                assert_ne!(message, "boom");
+                std::io::Write::write_all(&mut self.0.clone(), format!("{}\n", message).as_bytes())
+                    .unwrap();
+            }
+            fn flush(&self) {}
+        }
+        impl SharedLogger for Panicking {
+            fn level(&self) -> LevelFilter {
+                LevelFilter::Info
+            }
+            fn config(&self) -> Option<&Config> {
+                None
+            }
+            fn as_log(self: Box<Self>) -> Box<dyn Log> {
+                Box::new(*self)
+            }
+        }
+
+        let buf = SharedBuf::default();
+        let logger = AsyncLogger::new(Box::new(Panicking(buf.clone())));
+        for message in &["before", "boom", "after"] {
+            log(&logger, Level::Info, message);
+        }
+        // the worker survived the panic, so the flush returns
+        logger.flush();
+        assert_eq!(buf.contents(), "before\nafter\n");
+        logger.shutdown();
+    }
+
+    #[test]
+    fn test_async_logger_drop_oldest() {
+        /// Writer blocking while the test holds the lock
+        struct Gate(std::sync::Arc<std::sync::Mutex<()>>, SharedBuf);
+        impl std::io::Write for Gate {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let _lock = self.0.lock().unwrap();
+                self.1.write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buf = SharedBuf::default();
+        let gate = std::sync::Arc::new(std::sync::Mutex::new(()));
+        let conf = ConfigBuilder::new()
+            .set_format("{message}")
+            .unwrap()
+            .build();
+        let logger = AsyncLogger::bounded(
+            WriteLogger::new(LevelFilter::Info, conf, Gate(gate.clone(), buf.clone())),
+            2,
+            OverflowPolicy::DropOldest,
+        );
+        let log = |i: u32| log(&logger, Level::Info, &format!("record {}", i));
+
+        let closed = gate.lock().unwrap();
+        log(0);
+        // let the worker pick up the first record and block on the gate
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        for i in 1..4 {
+            log(i);
+        }
+        drop(closed);
+        logger.shutdown();
+
+        assert_eq!(logger.dropped(), 1);
+        assert_eq!(
+            buf.contents(),
+            "record 0\nrecord 2\nrecord 3\ndropped 1 log records, because the queue was full\n"
+        );
+    }
+}
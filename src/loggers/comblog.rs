@@ -7,15 +7,98 @@
 
 //! Module providing the CombinedLogger Implementation
 
-use crate::{Config, SharedLogger};
-use log::{set_boxed_logger, set_max_level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use super::logging::{with_backtrace, AtomicLevel};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+use log::{set_max_level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+
+/// Identifies a logger contained in a `CombinedLogger`, e.g. to remove it again
+///
+/// The loggers passed to [`CombinedLogger::new`] are given the ids `0`, `1`, ... in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LoggerId(pub u64);
+
+struct Child {
+    id: LoggerId,
+    logger: Box<dyn SharedLogger>,
+    /// Target prefixes routed to this logger
+    routes: Vec<String>,
+    failures: AtomicU64,
+}
+
+impl Child {
+    fn new(id: LoggerId, logger: Box<dyn SharedLogger>) -> Child {
+        Child {
+            id,
+            logger,
+            routes: Vec::new(),
+            failures: AtomicU64::new(0),
+        }
+    }
+}
+
+type ErrorHandler = Arc<dyn Fn(LoggerId, &io::Error) + Send + Sync>;
 
 /// The CombinedLogger struct. Provides a Logger implementation that proxies multiple Loggers as one.
 ///
-/// The purpose is to allow multiple Loggers to be set globally
+/// The purpose is to allow multiple Loggers to be set globally.
+/// Loggers can be added and removed at runtime, e.g. to start logging into a file
+/// only after the user picked a directory for it.
+///
+/// By default every record is passed to all contained loggers. Records can be routed to specific
+/// loggers by their target using [`CombinedLogger::add_route`].
+///
+/// A failing logger (e.g. because the disk is full) does not keep the record from the others,
+/// even if it panics. Failures are counted per logger (see [`CombinedLogger::failures`])
+/// and reported to the handler set by [`CombinedLogger::set_error_handler`].
 pub struct CombinedLogger {
-    level: LevelFilter,
-    logger: Vec<Box<dyn SharedLogger>>,
+    loggers: Arc<Loggers>,
+    next_id: AtomicU64,
+    error_handler: RwLock<Option<ErrorHandler>>,
+}
+
+/// The loggers of a `CombinedLogger` along with the lowest level used by them
+struct Loggers {
+    level: AtomicLevel,
+    children: RwLock<Vec<Child>>,
+}
+
+impl Loggers {
+    fn update_level(&self) {
+        self.level.set(max_level(&self.children.read().unwrap()));
+    }
+}
+
+/// The loggers of the `CombinedLogger`s, whose level may change along with runtime level filters.
+/// A `CombinedLogger` is moved to the end, whenever a logger is added to it,
+/// so nested ones are always updated before the ones containing them.
+static FOLLOWERS: Mutex<Vec<Weak<Loggers>>> = Mutex::new(Vec::new());
+
+/// Registers `loggers` to follow runtime level filters, if one of them may change along with them
+fn follow(loggers: &Arc<Loggers>) {
+    if !follow_runtime_filters(&loggers.children.read().unwrap()) {
+        return;
+    }
+    let mut followers = FOLLOWERS.lock().unwrap();
+    let weak = Arc::downgrade(loggers);
+    followers.retain(|follower| follower.strong_count() > 0 && !follower.ptr_eq(&weak));
+    followers.push(weak);
+}
+
+/// Updates the level of all `CombinedLogger`s, that follow runtime level filters,
+/// after the filters changed
+pub(crate) fn update_runtime_levels() {
+    let mut followers = FOLLOWERS.lock().unwrap();
+    followers.retain(|follower| match follower.upgrade() {
+        Some(loggers) => {
+            loggers.update_level();
+            true
+        }
+        None => false,
+    });
 }
 
 impl CombinedLogger {
@@ -28,6 +111,8 @@ impl CombinedLogger {
     /// for that purpose.
     /// Fails if another logger is already set globally.
     ///
+    /// Changing the level through the returned handle changes the level of all contained loggers.
+    ///
     /// # Examples
     /// ```
     /// # extern crate simplelog;
@@ -43,10 +128,10 @@ impl CombinedLogger {
     ///         );
     /// # }
     /// ```
-    pub fn init(logger: Vec<Box<dyn SharedLogger>>) -> Result<(), SetLoggerError> {
-        let comblog = CombinedLogger::new(logger);
-        set_max_level(comblog.level());
-        set_boxed_logger(comblog)
+    pub fn init(
+        logger: Vec<Box<dyn SharedLogger>>,
+    ) -> Result<LoggerHandle<CombinedLogger>, SetLoggerError> {
+        handle::init(CombinedLogger::new(logger))
     }
 
     /// allows to create a new logger, that can be independently used, no matter whats globally set.
@@ -76,50 +161,395 @@ impl CombinedLogger {
     /// ```
     #[must_use]
     pub fn new(logger: Vec<Box<dyn SharedLogger>>) -> Box<CombinedLogger> {
-        let mut log_level = LevelFilter::Off;
-        for log in &logger {
-            if log_level < log.level() {
-                log_level = log.level();
+        let logger: Vec<_> = logger
+            .into_iter()
+            .enumerate()
+            .map(|(i, logger)| Child::new(LoggerId(i as u64), logger))
+            .collect();
+        let next_id = AtomicU64::new(logger.len() as u64);
+        let loggers = Arc::new(Loggers {
+            level: AtomicLevel::new(max_level(&logger)),
+            children: RwLock::new(logger),
+        });
+        follow(&loggers);
+        Box::new(CombinedLogger {
+            loggers,
+            next_id,
+            error_handler: RwLock::new(None),
+        })
+    }
+
+    /// Adds a logger, returning the id to remove it again later on
+    ///
+    /// If the CombinedLogger is set globally, use [`LoggerHandle::add_logger`] instead,
+    /// which also raises the global max level of the `log` crate, if necessary.
+    pub fn add_logger(&self, logger: Box<dyn SharedLogger>) -> LoggerId {
+        let id = LoggerId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut children = self.loggers.children.write().unwrap();
+        children.push(Child::new(id, logger));
+        self.loggers.level.set(max_level(&children));
+        drop(children);
+        follow(&self.loggers);
+        id
+    }
+
+    /// Removes the logger with the given id, returning it.
+    /// It is flushed before, returns `None` if there is no logger with that id.
+    ///
+    /// If the CombinedLogger is set globally, use [`LoggerHandle::remove_logger`] instead,
+    /// which also lowers the global max level of the `log` crate, if possible.
+    pub fn remove_logger(&self, id: LoggerId) -> Option<Box<dyn SharedLogger>> {
+        let mut children = self.loggers.children.write().unwrap();
+        let index = children.iter().position(|child| child.id == id)?;
+        let child = children.remove(index);
+        self.loggers.level.set(max_level(&children));
+        drop(children);
+
+        child.logger.flush();
+        Some(child.logger)
+    }
+
+    /// Routes records with a target starting with `prefix` to the logger with the given id.
+    /// Returns `false` if there is no logger with that id.
+    ///
+    /// Routed records are only passed to the loggers of the longest matching prefix,
+    /// all other records only to the loggers without any routes.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let combined_logger = CombinedLogger::new(vec![
+    ///     SimpleLogger::new(LevelFilter::Info, Config::default()),
+    ///     WriteLogger::new(LevelFilter::Info, Config::default(), File::create("audit.log").unwrap()),
+    /// ]);
+    /// // `audit` records only go into the file, everything else to the terminal
+    /// combined_logger.add_route("audit", LoggerId(1));
+    /// # }
+    /// ```
+    pub fn add_route(&self, prefix: &str, id: LoggerId) -> bool {
+        let mut children = self.loggers.children.write().unwrap();
+        match children.iter_mut().find(|child| child.id == id) {
+            Some(child) => {
+                child.routes.push(String::from(prefix));
+                true
             }
+            None => false,
         }
+    }
 
-        Box::new(CombinedLogger {
-            level: log_level,
-            logger,
-        })
+    /// Removes all routes of the logger with the given id, so it receives all records not routed elsewhere again
+    pub fn clear_routes(&self, id: LoggerId) {
+        let mut children = self.loggers.children.write().unwrap();
+        if let Some(child) = children.iter_mut().find(|child| child.id == id) {
+            child.routes.clear();
+        }
+    }
+
+    /// Set a callback, that is invoked when one of the contained loggers fails to log a record
+    /// (default is to ignore these failures)
+    ///
+    /// The callback is given the id of the logger and the error. A panic of the logger is reported
+    /// as an error of the kind `Other`. Records logged by the callback itself are passed to the
+    /// CombinedLogger again, so take care not to fail over and over again.
+    pub fn set_error_handler<F>(&self, handler: F)
+    where
+        F: Fn(LoggerId, &io::Error) + Send + Sync + 'static,
+    {
+        *self.error_handler.write().unwrap() = Some(Arc::new(handler));
+    }
+
+    /// Returns how often the logger with the given id failed to log a record,
+    /// or `None` if there is no logger with that id
+    pub fn failures(&self, id: LoggerId) -> Option<u64> {
+        self.loggers
+            .children
+            .read()
+            .unwrap()
+            .iter()
+            .find(|child| child.id == id)
+            .map(|child| child.failures.load(Ordering::Relaxed))
+    }
+
+    /// Returns the ids of all contained loggers, in the order records are passed to them
+    pub fn logger_ids(&self) -> Vec<LoggerId> {
+        self.loggers
+            .children
+            .read()
+            .unwrap()
+            .iter()
+            .map(|child| child.id)
+            .collect()
+    }
+}
+
+impl LoggerHandle<CombinedLogger> {
+    /// Adds a logger to the global CombinedLogger, returning the id to remove it again later on
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let handle = CombinedLogger::init(vec![]).unwrap();
+    /// // once the user picked a directory
+    /// let id = handle.add_logger(WriteLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap()));
+    /// // later on
+    /// handle.remove_logger(id);
+    /// # }
+    /// ```
+    pub fn add_logger(&self, logger: Box<dyn SharedLogger>) -> LoggerId {
+        let id = (**self).add_logger(logger);
+        set_max_level(self.level());
+        id
+    }
+
+    /// Removes the logger with the given id from the global CombinedLogger, returning it.
+    /// It is flushed before, returns `None` if there is no logger with that id.
+    pub fn remove_logger(&self, id: LoggerId) -> Option<Box<dyn SharedLogger>> {
+        let logger = (**self).remove_logger(id);
+        set_max_level(self.level());
+        logger
     }
 }
 
 impl Log for CombinedLogger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= self.level
+        // checked before locking the loggers, so filtered records do not take any lock
+        metadata.level() <= self.level()
     }
 
     fn log(&self, record: &Record<'_>) {
         if self.enabled(record.metadata()) {
-            for log in &self.logger {
-                log.log(record);
+            let mut errors = Vec::new();
+            let children = self.loggers.children.read().unwrap();
+            let route = children
+                .iter()
+                .flat_map(|child| &child.routes)
+                .filter(|prefix| record.target().starts_with(prefix.as_str()))
+                .max_by_key(|prefix| prefix.len());
+            // capture the backtrace once for all loggers
+            with_backtrace(record, backtrace_level(&children), || {
+                for child in children.iter() {
+                    let routed = match route {
+                        Some(route) => child.routes.iter().any(|prefix| prefix == route),
+                        None => child.routes.is_empty(),
+                    };
+                    if routed && record.level() <= child.logger.level() {
+                        let res =
+                            panic::catch_unwind(AssertUnwindSafe(|| child.logger.try_log(record)))
+                                .unwrap_or_else(|_| Err(io::Error::other("logger panicked")));
+                        if let Err(err) = res {
+                            stats::count_write_error();
+                            child.failures.fetch_add(1, Ordering::Relaxed);
+                            errors.push((child.id, err));
+                        }
+                    }
+                }
+            });
+            drop(children);
+
+            // the handler is called without holding the lock, so it may log or change the loggers
+            if !errors.is_empty() {
+                let handler = self.error_handler.read().unwrap().clone();
+                if let Some(handler) = handler {
+                    for (id, err) in &errors {
+                        handler(*id, err);
+                    }
+                }
             }
         }
     }
 
     fn flush(&self) {
-        for log in &self.logger {
-            log.flush();
+        for child in self.loggers.children.read().unwrap().iter() {
+            child.logger.flush();
         }
     }
 }
 
 impl SharedLogger for CombinedLogger {
     fn level(&self) -> LevelFilter {
-        self.level
+        self.loggers.level.get()
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        let children = self.loggers.children.read().unwrap();
+        for child in children.iter() {
+            child.logger.set_level(level);
+        }
+        self.loggers.level.set(max_level(&children));
     }
 
     fn config(&self) -> Option<&Config> {
         None
     }
 
+    fn backtrace_level(&self) -> LevelFilter {
+        backtrace_level(&self.loggers.children.read().unwrap())
+    }
+
     fn as_log(self: Box<Self>) -> Box<dyn Log> {
         Box::new(*self)
     }
 }
+
+/// Whether the level of one of the given loggers may change along with runtime level filters,
+/// which is assumed for loggers without a config of their own, e.g. a nested `CombinedLogger`
+fn follow_runtime_filters(logger: &[Child]) -> bool {
+    logger.iter().any(|child| {
+        child
+            .logger
+            .config()
+            .is_none_or(|config| config.runtime_level_filters.is_some())
+    })
+}
+
+/// The lowest level, at which one of the given loggers appends a backtrace
+fn backtrace_level(logger: &[Child]) -> LevelFilter {
+    logger
+        .iter()
+        .map(|child| child.logger.backtrace_level())
+        .max()
+        .unwrap_or(LevelFilter::Off)
+}
+
+/// The lowest level used by the given loggers
+fn max_level(logger: &[Child]) -> LevelFilter {
+    let mut log_level = LevelFilter::Off;
+    for child in logger {
+        if log_level < child.logger.level() {
+            log_level = child.logger.level();
+        }
+    }
+    log_level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, SharedBuf};
+    use crate::{ConfigBuilder, Level, SimpleLogger, WriteLogger};
+
+    #[test]
+    fn test_set_level() {
+        let logger = CombinedLogger::new(vec![
+            SimpleLogger::new(LevelFilter::Warn, Config::default()),
+            WriteLogger::new(LevelFilter::Error, Config::default(), std::io::sink()),
+        ]);
+        let debug = Metadata::builder().level(Level::Debug).build();
+
+        assert_eq!(logger.level(), LevelFilter::Warn);
+        assert!(!logger.enabled(&debug));
+        logger.set_level(LevelFilter::Debug);
+        assert_eq!(logger.level(), LevelFilter::Debug);
+        assert!(logger.enabled(&debug));
+    }
+
+    #[test]
+    fn test_add_remove_logger() {
+        let logger = CombinedLogger::new(vec![WriteLogger::new(
+            LevelFilter::Warn,
+            Config::default(),
+            std::io::sink(),
+        )]);
+        let debug = Metadata::builder().level(Level::Debug).build();
+        assert!(!logger.enabled(&debug));
+
+        let buf = SharedBuf::default();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let id = logger.add_logger(WriteLogger::new(LevelFilter::Debug, config, buf.clone()));
+        assert_eq!(logger.logger_ids(), [LoggerId(0), id]);
+        assert!(logger.enabled(&debug));
+        log(&logger, Level::Info, "added");
+        assert_eq!(buf.contents(), "[INFO] added\n");
+
+        assert!(logger.remove_logger(id).is_some());
+        assert!(logger.remove_logger(id).is_none());
+        assert!(!logger.enabled(&debug));
+        log(&logger, Level::Warn, "removed");
+        assert_eq!(buf.contents(), "[INFO] added\n");
+    }
+
+    #[test]
+    fn test_routes() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let term = SharedBuf::default();
+        let audit = SharedBuf::default();
+        let logger = CombinedLogger::new(vec![
+            WriteLogger::new(LevelFilter::Info, config.clone(), term.clone()),
+            WriteLogger::new(LevelFilter::Info, config, audit.clone()),
+        ]);
+        assert!(logger.add_route("audit", LoggerId(1)));
+        assert!(!logger.add_route("audit", LoggerId(2)));
+
+        let log = |target| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{}", target))
+                    .level(Level::Info)
+                    .target(target)
+                    .build(),
+            )
+        };
+        log("audit::login");
+        log("my_app");
+        assert_eq!(term.contents(), "[INFO] my_app\n");
+        assert_eq!(audit.contents(), "[INFO] audit::login\n");
+
+        logger.clear_routes(LoggerId(1));
+        log("audit::logout");
+        assert_eq!(term.contents(), "[INFO] my_app\n[INFO] audit::logout\n");
+    }
+
+    #[test]
+    fn test_failing_logger() {
+        struct Full;
+
+        impl std::io::Write for Full {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let buf = SharedBuf::default();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let logger = CombinedLogger::new(vec![
+            WriteLogger::new(LevelFilter::Info, config.clone(), Full),
+            WriteLogger::new(LevelFilter::Info, config, buf.clone()),
+        ]);
+        let reported = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_reported = reported.clone();
+        logger.set_error_handler(move |id, err| {
+            handler_reported.lock().unwrap().push((id, err.to_string()))
+        });
+
+        for _ in 0..2 {
+            log(&logger, Level::Info, "saved");
+        }
+        assert_eq!(buf.contents(), "[INFO] saved\n[INFO] saved\n");
+        assert_eq!(logger.failures(LoggerId(0)), Some(2));
+        assert_eq!(logger.failures(LoggerId(1)), Some(0));
+        assert_eq!(logger.failures(LoggerId(2)), None);
+        assert_eq!(
+            *reported.lock().unwrap(),
+            [
+                (LoggerId(0), String::from("disk full")),
+                (LoggerId(0), String::from("disk full"))
+            ]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the ContainerLogger Implementation
+
+use super::logging::{
+    enabled, max_level, now, print_gated, should_skip, throttled, write_json, AtomicLevel,
+};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::{self, stdout, Write};
+use time::UtcOffset;
+
+/// The ContainerLogger struct. Provides a Logger implementation writing records as JSON lines to stdout.
+///
+/// Every record is written as a JSON object on a single line, with the time in UTC and without any colors,
+/// which is what log drivers of Docker and Kubernetes expect to collect and parse.
+/// The object contains the same fields as the ones shipped by the `HttpLogger`,
+/// the time format and line ending of the `Config` are ignored.
+pub struct ContainerLogger {
+    level: AtomicLevel,
+    config: Config,
+}
+
+impl ContainerLogger {
+    /// init function. Globally initializes the ContainerLogger as the one and only used log facility.
+    ///
+    /// Logs records of level `Info` and above, the level and filters can be adjusted using the environment
+    /// just like for `simplelog::init()`. The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = ContainerLogger::init();
+    /// # }
+    /// ```
+    pub fn init() -> Result<LoggerHandle<ContainerLogger>, SetLoggerError> {
+        handle::init(ContainerLogger::new(LevelFilter::Info, Config::from_env()))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let container_logger = ContainerLogger::new(LevelFilter::Info, Config::default());
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(log_level: LevelFilter, config: Config) -> Box<ContainerLogger> {
+        Box::new(ContainerLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+        })
+    }
+}
+
+impl Log for ContainerLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {
+        let _ = stdout().flush();
+    }
+}
+
+impl SharedLogger for ContainerLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        let mut res = Ok(());
+        if self.enabled(record.metadata()) && !should_skip(&self.config, record) {
+            throttled(&self.config, record, |record| {
+                let mut line = Vec::new();
+                let written = write_json(
+                    record,
+                    &mut line,
+                    &self.config,
+                    now().to_offset(UtcOffset::UTC),
+                );
+                if let Err(err) = written {
+                    res = Err(err);
+                    return;
+                }
+                line.push(b'\n');
+                print_gated(&self.config, || {
+                    // a single write, so lines of concurrent records are not interleaved
+                    if let Err(err) = stdout().lock().write_all(&line) {
+                        res = Err(err);
+                    }
+                });
+            });
+        }
+        res
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Level;
+
+    #[test]
+    fn test_container_logger() {
+        let record = Record::builder()
+            .args(format_args!("multi\nline \"message\""))
+            .level(Level::Warn)
+            .target("simplelog::tests")
+            .build();
+        let mut json = Vec::new();
+        crate::loggers::logging::write_json(
+            &record,
+            &mut json,
+            &Config::default(),
+            time::macros::datetime!(2024-01-02 03:04:05 UTC),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(json).unwrap(),
+            r#"{"time":"2024-01-02T03:04:05Z","level":"WARN","target":"simplelog::tests","message":"multi\nline \"message\""}"#
+        );
+
+        let logger = ContainerLogger::new(LevelFilter::Info, Config::default());
+        assert_eq!(logger.level(), LevelFilter::Info);
+        logger.log(&record);
+        logger.flush();
+    }
+}
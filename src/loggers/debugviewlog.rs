@@ -0,0 +1,139 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the DebugViewLogger Implementation
+
+use super::logging::{enabled, max_level, with_formatted, AtomicLevel};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io;
+use windows_sys::Win32::System::Diagnostics::Debug::OutputDebugStringW;
+
+/// The DebugViewLogger struct. Provides a Logger implementation passing records to `OutputDebugStringW`.
+///
+/// The records show up in the output window of an attached debugger like Visual Studio or in DebugView,
+/// which makes them visible for GUI processes without a console.
+/// They are sent including the line ending, as debuggers do not separate the strings they receive.
+pub struct DebugViewLogger {
+    level: AtomicLevel,
+    config: Config,
+}
+
+impl DebugViewLogger {
+    /// init function. Globally initializes the DebugViewLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments. The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = DebugViewLogger::init(LevelFilter::Debug, Config::default());
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+    ) -> Result<LoggerHandle<DebugViewLogger>, SetLoggerError> {
+        handle::init(DebugViewLogger::new(log_level, config))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let debug_logger = DebugViewLogger::new(LevelFilter::Debug, Config::default());
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(log_level: LevelFilter, config: Config) -> Box<DebugViewLogger> {
+        Box::new(DebugViewLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+        })
+    }
+}
+
+impl Log for DebugViewLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for DebugViewLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            with_formatted(&self.config, record, |msg| {
+                let msg: Vec<u16> = String::from_utf8_lossy(msg)
+                    .encode_utf16()
+                    // a nul character would end the string early
+                    .map(|c| if c == 0 { 0xfffd } else { c })
+                    .chain(Some(0))
+                    .collect();
+                unsafe { OutputDebugStringW(msg.as_ptr()) };
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Level;
+
+    #[test]
+    fn test_debug_view_logger() {
+        let logger = DebugViewLogger::new(LevelFilter::Info, Config::default());
+        for level in &[Level::Error, Level::Warn, Level::Info, Level::Debug] {
+            logger
+                .try_log(
+                    &Record::builder()
+                        .args(format_args!("test message\0with nul"))
+                        .level(*level)
+                        .build(),
+                )
+                .unwrap();
+        }
+        logger.flush();
+    }
+}
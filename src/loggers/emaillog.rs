@@ -0,0 +1,609 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the EmailLogger Implementation
+
+use super::logging::{
+    enabled, hostname, max_level, now, to_config_offset, with_formatted, AtomicLevel,
+};
+use super::tcplog::{ConnectionEvent, NetworkOptions};
+use crate::{handle, Config, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use time::format_description::well_known::Rfc2822;
+
+/// Timeout for connecting and every reply of the server, so a stalled server cannot block the worker forever
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings of the mails sent by the [`EmailLogger`]
+///
+/// # Examples
+/// ```no_run
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use std::time::Duration;
+/// # fn main() {
+/// let mut options = EmailOptions::new();
+/// options
+///     .set_interval(Duration::from_secs(15 * 60))
+///     .set_subject("errors of the billing service");
+/// let _ = EmailLogger::init(
+///     LevelFilter::Error,
+///     Config::default(),
+///     "localhost:25",
+///     "app@example.com",
+///     &["oncall@example.com"],
+///     options,
+///     NetworkOptions::default(),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EmailOptions {
+    interval: Duration,
+    subject: Option<String>,
+}
+
+impl EmailOptions {
+    /// Creates the default settings
+    pub fn new() -> EmailOptions {
+        EmailOptions {
+            interval: Duration::from_secs(5 * 60),
+            subject: None,
+        }
+    }
+
+    /// set the minimum time between two mails (default is 5 minutes)
+    ///
+    /// Records logged in the meantime are collected and sent together with the next mail,
+    /// flushing the logger does not send them any earlier.
+    pub fn set_interval(&mut self, interval: Duration) -> &mut EmailOptions {
+        self.interval = interval;
+        self
+    }
+
+    /// set the subject of the mails
+    /// (default is the amount of records and the hostname, e.g. `3 records logged on web-1`)
+    pub fn set_subject(&mut self, subject: &str) -> &mut EmailOptions {
+        self.subject = Some(String::from(subject));
+        self
+    }
+}
+
+impl Default for EmailOptions {
+    fn default() -> EmailOptions {
+        EmailOptions::new()
+    }
+}
+
+/// Records waiting to be mailed
+struct Outbox {
+    records: Vec<String>,
+    /// When the last mail was sent
+    sent_at: Option<Instant>,
+    /// When sending is retried after a failure
+    retry_at: Option<Instant>,
+    backoff: Duration,
+    dropped: u64,
+    /// Whether a mail is being sent right now
+    sending: bool,
+    closed: bool,
+}
+
+/// State shared between the logger and its worker thread
+struct Mailer {
+    config: Config,
+    options: EmailOptions,
+    network: NetworkOptions,
+    addrs: Vec<SocketAddr>,
+    from: String,
+    to: Vec<String>,
+    outbox: Mutex<Outbox>,
+    changed: Condvar,
+}
+
+/// Removes line breaks, so values cannot inject headers or commands
+fn single_line(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// Reads a (possibly multi-line) reply and checks that its code is of the expected class, e.g. `2` for `250`
+fn expect(reader: &mut impl BufRead, class: u8) -> io::Result<()> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "connection closed by the SMTP server",
+            ));
+        }
+        // continuation lines of a multi-line reply have a `-` after the code
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        return match line.as_bytes().first() {
+            Some(&code) if code == class && line.len() >= 3 => Ok(()),
+            _ => Err(io::Error::other(format!(
+                "unexpected reply of the SMTP server: {}",
+                line.trim_end()
+            ))),
+        };
+    }
+}
+
+fn command(
+    stream: &mut TcpStream,
+    reader: &mut impl BufRead,
+    command: &str,
+    class: u8,
+) -> io::Result<()> {
+    stream.write_all(command.as_bytes())?;
+    stream.write_all(b"\r\n")?;
+    expect(reader, class)
+}
+
+impl Mailer {
+    fn connect(&self) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in &self.addrs {
+            match TcpStream::connect_timeout(addr, TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(TIMEOUT))?;
+                    stream.set_write_timeout(Some(TIMEOUT))?;
+                    return Ok(stream);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::AddrNotAvailable, "no address to connect to")
+        }))
+    }
+
+    /// Builds the mail holding the records, including its headers
+    fn message(&self, records: &[String], dropped: u64) -> String {
+        let host = hostname(&self.config);
+        let subject = match &self.options.subject {
+            Some(subject) => single_line(subject),
+            None => format!("{} records logged on {}", records.len(), single_line(host)),
+        };
+        let date = to_config_offset(now(), &self.config)
+            .format(&Rfc2822)
+            .unwrap_or_default();
+
+        let mut message = format!(
+            "From: <{}>\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+             Content-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+            self.from,
+            self.to
+                .iter()
+                .map(|to| format!("<{}>", to))
+                .collect::<Vec<_>>()
+                .join(", "),
+            subject,
+            date,
+        );
+        let mut body = records.concat();
+        if dropped > 0 {
+            body.push_str(&format!(
+                "\n{} further records were dropped, because too many were pending\n",
+                dropped
+            ));
+        }
+        for line in body.lines() {
+            // lines starting with a dot are escaped by doubling it, a single dot ends the mail
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+        message
+    }
+
+    fn send_mail(&self, records: &[String], dropped: u64) -> io::Result<()> {
+        let mut stream = self.connect()?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let host = single_line(hostname(&self.config));
+
+        expect(&mut reader, b'2')?;
+        command(&mut stream, &mut reader, &format!("EHLO {}", host), b'2')?;
+        command(
+            &mut stream,
+            &mut reader,
+            &format!("MAIL FROM:<{}>", self.from),
+            b'2',
+        )?;
+        for to in &self.to {
+            command(&mut stream, &mut reader, &format!("RCPT TO:<{}>", to), b'2')?;
+        }
+        command(&mut stream, &mut reader, "DATA", b'3')?;
+        stream.write_all(self.message(records, dropped).as_bytes())?;
+        command(&mut stream, &mut reader, ".", b'2')?;
+        // the mail is accepted at this point, so a failing QUIT is no reason to send it again
+        let _ = command(&mut stream, &mut reader, "QUIT", b'2');
+        Ok(())
+    }
+
+    /// Mails all pending records, keeping them if sending fails
+    fn send<'a>(&'a self, mut outbox: MutexGuard<'a, Outbox>) -> MutexGuard<'a, Outbox> {
+        let records = std::mem::take(&mut outbox.records);
+        let dropped = outbox.dropped;
+        outbox.sending = true;
+        drop(outbox);
+
+        let result = self.send_mail(&records, dropped);
+
+        let mut outbox = self.outbox.lock().unwrap();
+        outbox.sending = false;
+        match result {
+            Ok(()) => {
+                outbox.sent_at = Some(Instant::now());
+                outbox.retry_at = None;
+                outbox.backoff = self.network.reconnect_backoff.0;
+                outbox.dropped -= dropped;
+            }
+            Err(err) => {
+                let backoff = outbox.backoff;
+                self.network
+                    .report(ConnectionEvent::SendFailed(&err, backoff));
+                outbox.retry_at = Some(Instant::now() + backoff);
+                outbox.backoff = (backoff * 2).min(self.network.reconnect_backoff.1);
+
+                // put the records back in front of the ones logged in the meantime
+                let logged = std::mem::replace(&mut outbox.records, records);
+                outbox.records.extend(logged);
+                let excess = outbox
+                    .records
+                    .len()
+                    .saturating_sub(self.network.buffer_limit.max(1));
+                outbox.records.drain(..excess);
+                outbox.dropped += excess as u64;
+            }
+        }
+        outbox
+    }
+
+    /// When the pending records may be sent, respecting the interval and the backoff after a failure
+    fn deadline(&self, outbox: &Outbox, now: Instant) -> Instant {
+        let mut deadline = outbox
+            .sent_at
+            .map_or(now, |sent_at| sent_at + self.options.interval);
+        if let Some(retry_at) = outbox.retry_at {
+            deadline = deadline.max(retry_at);
+        }
+        deadline
+    }
+
+    fn run(&self) {
+        let mut outbox = self.outbox.lock().unwrap();
+        loop {
+            if outbox.closed {
+                if !outbox.records.is_empty() {
+                    drop(self.send(outbox));
+                }
+                return;
+            }
+            if outbox.records.is_empty() {
+                outbox = self.changed.wait(outbox).unwrap();
+                continue;
+            }
+
+            let now = Instant::now();
+            let deadline = self.deadline(&outbox, now);
+            outbox = match deadline <= now {
+                true => self.send(outbox),
+                false => self.changed.wait_timeout(outbox, deadline - now).unwrap().0,
+            };
+        }
+    }
+}
+
+/// The EmailLogger struct. Provides a Logger implementation sending records by email, e.g. as alerts for errors.
+///
+/// Records are formatted according to the `Config` and collected. The first record is mailed right away,
+/// further records are collected and sent together, at most one mail per interval
+/// (see [`EmailOptions::set_interval`]). The subject names the amount of records and the host,
+/// unless it is set by [`EmailOptions::set_subject`].
+/// Usually this logger is created with the `Error` level and combined with other loggers using a `CombinedLogger`.
+///
+/// Mails are sent from a background thread using plain SMTP without authentication or encryption,
+/// so the server is expected to be a local relay, like the mail transfer agent of the host.
+/// If sending fails, the records are kept (up to [`NetworkOptions::set_buffer_limit`]) and sent again
+/// with the backoff set by [`NetworkOptions::set_reconnect_backoff`]. Failures are reported to the handler
+/// set by [`NetworkOptions::set_connection_handler`]. Pending records are sent when the logger is dropped,
+/// and on `flush` if the interval and the backoff passed.
+///
+pub struct EmailLogger {
+    level: AtomicLevel,
+    mailer: Arc<Mailer>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl EmailLogger {
+    /// init function. Globally initializes the EmailLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the address of the SMTP server, the sender, the recipients,
+    /// the settings of the mails and of the connection as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the address cannot be resolved or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = EmailLogger::init(
+    ///     LevelFilter::Error,
+    ///     Config::default(),
+    ///     "localhost:25",
+    ///     "app@example.com",
+    ///     &["oncall@example.com"],
+    ///     EmailOptions::default(),
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        server: A,
+        from: &str,
+        to: &[&str],
+        options: EmailOptions,
+        network: NetworkOptions,
+    ) -> Result<LoggerHandle<EmailLogger>, InitError> {
+        let logger = EmailLogger::new(log_level, config, server, from, to, options, network)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the address of the SMTP server, the sender, the recipients,
+    /// the settings of the mails and of the connection as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// The address is resolved once, connections are only made to send a mail.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let mut options = EmailOptions::new();
+    /// options.set_interval(Duration::from_secs(15 * 60));
+    /// let _ = CombinedLogger::init(vec![
+    ///     SimpleLogger::new(LevelFilter::Info, Config::default()),
+    ///     EmailLogger::new(
+    ///         LevelFilter::Error,
+    ///         Config::default(),
+    ///         "localhost:25",
+    ///         "app@example.com",
+    ///         &["oncall@example.com"],
+    ///         options,
+    ///         NetworkOptions::default(),
+    ///     )
+    ///     .unwrap(),
+    /// ]);
+    /// # }
+    /// ```
+    pub fn new<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        server: A,
+        from: &str,
+        to: &[&str],
+        options: EmailOptions,
+        network: NetworkOptions,
+    ) -> io::Result<Box<EmailLogger>> {
+        let backoff = network.reconnect_backoff.0;
+        let mailer = Arc::new(Mailer {
+            config,
+            options,
+            network,
+            addrs: server.to_socket_addrs()?.collect(),
+            from: single_line(from),
+            to: to.iter().map(|to| single_line(to)).collect(),
+            outbox: Mutex::new(Outbox {
+                records: Vec::new(),
+                sent_at: None,
+                retry_at: None,
+                backoff,
+                dropped: 0,
+                sending: false,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let worker_mailer = mailer.clone();
+        let worker = thread::Builder::new()
+            .name(String::from("simplelog-email"))
+            .spawn(move || worker_mailer.run())
+            .expect("failed to spawn thread");
+
+        Ok(Box::new(EmailLogger {
+            level: AtomicLevel::new(log_level),
+            mailer,
+            worker: Mutex::new(Some(worker)),
+        }))
+    }
+}
+
+impl Log for EmailLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.mailer.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let config = &self.mailer.config;
+            let _ = with_formatted(config, record, |buf| {
+                let mut outbox = self.mailer.outbox.lock().unwrap();
+                if outbox.records.len() >= self.mailer.network.buffer_limit.max(1) {
+                    outbox.records.remove(0);
+                    outbox.dropped += 1;
+                }
+                outbox
+                    .records
+                    .push(String::from_utf8_lossy(buf).into_owned());
+                self.mailer.changed.notify_one();
+                Ok(())
+            });
+        }
+    }
+
+    fn flush(&self) {
+        let outbox = self.mailer.outbox.lock().unwrap();
+        // within the interval the worker sends the records once it passed
+        let now = Instant::now();
+        if !outbox.records.is_empty()
+            && !outbox.sending
+            && self.mailer.deadline(&outbox, now) <= now
+        {
+            drop(self.mailer.send(outbox));
+        }
+    }
+}
+
+impl Drop for EmailLogger {
+    fn drop(&mut self) {
+        self.mailer.outbox.lock().unwrap().closed = true;
+        self.mailer.changed.notify_one();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl SharedLogger for EmailLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.mailer.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.mailer.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::{ConfigBuilder, Level};
+
+    #[test]
+    fn test_email_logger() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_hostname("web-1")
+            .build();
+        let mut options = EmailOptions::new();
+        options
+            .set_subject("alert")
+            .set_interval(std::time::Duration::from_secs(3600));
+        let logger = EmailLogger::new(
+            LevelFilter::Error,
+            config,
+            listener.local_addr().unwrap(),
+            "app@example.com",
+            &["oncall@example.com"],
+            options,
+            NetworkOptions::default(),
+        )
+        .unwrap();
+        let log = |message: &str| log(&logger, Level::Error, message);
+
+        // a minimal SMTP server, returning the commands and the mail it received
+        let receive = || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            (&stream).write_all(b"220 localhost\r\n").unwrap();
+            let (mut commands, mut mail) = (Vec::new(), String::new());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_string();
+                let reply: &[u8] = match line.as_str() {
+                    "DATA" => b"354 go ahead\r\n",
+                    "QUIT" => b"221 bye\r\n",
+                    _ => b"250-localhost\r\n250 OK\r\n",
+                };
+                (&stream).write_all(reply).unwrap();
+                if line == "QUIT" {
+                    return (commands, mail);
+                }
+                if line != "DATA" {
+                    commands.push(line);
+                    continue;
+                }
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line == ".\r\n" {
+                        break;
+                    }
+                    mail.push_str(&line);
+                }
+                (&stream).write_all(b"250 queued\r\n").unwrap();
+            }
+        };
+
+        log("disk full");
+        let (commands, mail) = receive();
+        assert_eq!(
+            commands,
+            [
+                "EHLO web-1",
+                "MAIL FROM:<app@example.com>",
+                "RCPT TO:<oncall@example.com>"
+            ]
+        );
+        assert!(mail.contains("\r\nSubject: alert\r\n"), "{}", mail);
+        assert!(mail.ends_with("\r\n\r\n[ERROR] disk full\r\n"), "{}", mail);
+
+        // the interval did not pass yet, so these are sent together when the logger is dropped
+        log("still full");
+        log("caused by\n.env missing");
+        logger.flush();
+        listener.set_nonblocking(true).unwrap();
+        assert_eq!(
+            listener.accept().unwrap_err().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+        listener.set_nonblocking(false).unwrap();
+        let (_, mail) = std::thread::scope(|scope| {
+            let receiver = scope.spawn(receive);
+            drop(logger);
+            receiver.join().unwrap()
+        });
+        assert!(
+            mail.ends_with("\r\n\r\n[ERROR] still full\r\n[ERROR] caused by\r\n..env missing\r\n"),
+            "{}",
+            mail
+        );
+    }
+}
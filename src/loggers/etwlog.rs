@@ -0,0 +1,207 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the EtwLogger Implementation
+
+use super::logging::{enabled, max_level, try_log, AtomicLevel};
+use crate::{handle, Config, InitError, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::ffi::OsStr;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use windows_sys::core::GUID;
+use windows_sys::Win32::System::Diagnostics::Etw::{
+    EventProviderEnabled, EventRegister, EventUnregister, EventWriteString, REGHANDLE,
+};
+
+/// Handle of a registered provider
+struct Provider(REGHANDLE);
+
+// Provider handles may be used from any thread
+unsafe impl Send for Provider {}
+unsafe impl Sync for Provider {}
+
+impl Drop for Provider {
+    fn drop(&mut self) {
+        unsafe {
+            EventUnregister(self.0);
+        }
+    }
+}
+
+/// Maps a `Level` to the matching ETW level, there is no level below verbose
+fn etw_level(level: Level) -> u8 {
+    match level {
+        Level::Error => 2,
+        Level::Warn => 3,
+        Level::Info => 4,
+        Level::Debug | Level::Trace => 5,
+    }
+}
+
+/// Parses a GUID written like `5eb5a2d4-3f0e-4c3a-9b1e-2f6f4c2b8a11`, optionally in braces
+fn parse_guid(guid: &str) -> Option<u128> {
+    let guid = guid.trim();
+    let guid = guid
+        .strip_prefix('{')
+        .and_then(|guid| guid.strip_suffix('}'))
+        .unwrap_or(guid);
+    let groups: Vec<&str> = guid.split('-').collect();
+    let lengths = groups.iter().map(|group| group.len()).collect::<Vec<_>>();
+    if lengths != [8, 4, 4, 4, 12] || !guid.chars().all(|c| c == '-' || c.is_ascii_hexdigit()) {
+        return None;
+    }
+    u128::from_str_radix(&groups.concat(), 16).ok()
+}
+
+/// The EtwLogger struct. Provides a Logger implementation emitting records as Event Tracing for Windows (ETW) events.
+///
+/// Records are written as string events of the provider with the given GUID, so they show up
+/// in traces recorded with e.g. WPR, PerfView or `logman` next to the performance data of the system.
+/// The levels are mapped to the ETW levels error, warning, information and verbose (`Debug` and `Trace`).
+///
+/// Records are only formatted, if a trace session enabled the provider for their level.
+pub struct EtwLogger {
+    level: AtomicLevel,
+    config: Config,
+    provider: Provider,
+}
+
+impl EtwLogger {
+    /// init function. Globally initializes the EtwLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config` and the GUID of the provider as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the GUID is invalid, the provider cannot be registered or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = EtwLogger::init(LevelFilter::Info, Config::default(), "5eb5a2d4-3f0e-4c3a-9b1e-2f6f4c2b8a11");
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        provider: &str,
+    ) -> Result<LoggerHandle<EtwLogger>, InitError> {
+        let logger = EtwLogger::new(log_level, config, provider)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config` and the GUID of the provider as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let etw_logger = EtwLogger::new(LevelFilter::Info, Config::default(), "5eb5a2d4-3f0e-4c3a-9b1e-2f6f4c2b8a11").unwrap();
+    /// # }
+    /// ```
+    pub fn new(
+        log_level: LevelFilter,
+        config: Config,
+        provider: &str,
+    ) -> io::Result<Box<EtwLogger>> {
+        let guid = parse_guid(provider)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "invalid provider GUID"))?;
+        let guid = GUID::from_u128(guid);
+        let mut handle = 0u64;
+        let rc = unsafe { EventRegister(&guid, None, ptr::null(), &mut handle) };
+        if rc != 0 {
+            return Err(io::Error::from_raw_os_error(rc as i32));
+        }
+
+        Ok(Box::new(EtwLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            provider: Provider(handle as REGHANDLE),
+        }))
+    }
+}
+
+impl Log for EtwLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let level = etw_level(record.level());
+        if self.enabled(record.metadata())
+            && unsafe { EventProviderEnabled(self.provider.0, level, 0) } != 0
+        {
+            let mut msg = Vec::new();
+            if try_log(&self.config, record, &mut msg).is_err() || msg.is_empty() {
+                return;
+            }
+            while matches!(msg.last(), Some(b'\n') | Some(b'\r')) {
+                msg.pop();
+            }
+            let msg: Vec<u16> = OsStr::new(&*String::from_utf8_lossy(&msg))
+                .encode_wide()
+                .chain(Some(0))
+                .collect();
+
+            unsafe {
+                EventWriteString(self.provider.0, level, 0, msg.as_ptr());
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for EtwLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::Level;
+
+    #[test]
+    fn test_etw_logger() {
+        assert!(EtwLogger::new(LevelFilter::Info, Config::default(), "not-a-guid").is_err());
+
+        let logger = EtwLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            "{5eb5a2d4-3f0e-4c3a-9b1e-2f6f4c2b8a11}",
+        )
+        .unwrap();
+        for level in &[Level::Error, Level::Warn, Level::Info] {
+            log(&logger, *level, "test event");
+        }
+        logger.flush();
+    }
+}
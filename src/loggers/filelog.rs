@@ -0,0 +1,924 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the FileLogger Implementation
+
+use super::logging::{
+    enabled, file_options, max_level, open_file, to_config_offset, with_formatted, AtomicLevel,
+};
+use crate::config::NamePart;
+use crate::{handle, stats, Config, FileError, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::cmp::Reverse;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::SystemTime;
+use time::{macros::format_description, Duration, OffsetDateTime, Time};
+
+/// When a [`FileLogger`] should start writing to a new file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Rotation {
+    /// Never rotate
+    Never,
+    /// Rotate once the file has grown to the given amount of bytes.
+    ///
+    /// Rotated files are named `app.log.1`, `app.log.2`, ... with `.1` being the most recent.
+    Size(u64),
+    /// Rotate at the start of every hour, rotated files are named `app.log.2024-05-03-14`
+    Hourly,
+    /// Rotate at midnight, rotated files are named `app.log.2024-05-03`
+    Daily,
+    /// Rotate at midnight from Sunday to Monday,
+    /// rotated files are named after the Monday starting the week, e.g. `app.log.2024-04-29`
+    Weekly,
+}
+
+impl Rotation {
+    fn is_timed(self) -> bool {
+        matches!(self, Rotation::Hourly | Rotation::Daily | Rotation::Weekly)
+    }
+
+    /// Start of the period `time` falls into
+    fn period_start(self, time: OffsetDateTime) -> OffsetDateTime {
+        match self {
+            Rotation::Hourly => time.replace_time(Time::from_hms(time.hour(), 0, 0).unwrap()),
+            Rotation::Daily => time.replace_time(Time::MIDNIGHT),
+            Rotation::Weekly => {
+                let days = time.weekday().number_days_from_monday();
+                time.replace_time(Time::MIDNIGHT) - Duration::days(days.into())
+            }
+            Rotation::Never | Rotation::Size(_) => time,
+        }
+    }
+
+    fn period_length(self) -> Duration {
+        match self {
+            Rotation::Hourly => Duration::HOUR,
+            Rotation::Daily => Duration::DAY,
+            Rotation::Weekly => Duration::WEEK,
+            Rotation::Never | Rotation::Size(_) => Duration::MAX,
+        }
+    }
+
+    fn period_suffix(self, start: OffsetDateTime) -> String {
+        let res = match self {
+            Rotation::Hourly => start.format(format_description!("[year]-[month]-[day]-[hour]")),
+            _ => start.format(format_description!("[year]-[month]-[day]")),
+        };
+        res.expect("formatting a date into a String cannot fail")
+    }
+}
+
+/// The currently opened log file and the bookkeeping needed to rotate it
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    rotation: Rotation,
+    /// Start of the period the current file belongs to (timed rotation only)
+    period_start: OffsetDateTime,
+    /// Background compression of the previously rotated file and removal of old ones
+    cleanup: Option<JoinHandle<()>>,
+}
+
+impl RotatingFile {
+    /// Closes the file and opens `path` again, in case it was moved away
+    fn reopen(&mut self, config: &Config) -> io::Result<()> {
+        self.file.flush()?;
+        self.file = file_options(config).open(&self.path)?;
+        self.size = self.file.metadata()?.len();
+        Ok(())
+    }
+
+    fn open(path: PathBuf, rotation: Rotation, config: &Config) -> Result<RotatingFile, FileError> {
+        let file = open_file(&path, config)?;
+        let metadata = match file.metadata() {
+            Ok(metadata) => metadata,
+            Err(err) => return Err(FileError::Open(path, err)),
+        };
+        let size = metadata.len();
+
+        // an existing, non-empty file belongs to the period it was last written in
+        let last_write = match metadata.modified() {
+            Ok(modified) if size > 0 => OffsetDateTime::from(modified),
+            _ => OffsetDateTime::now_utc(),
+        };
+
+        Ok(RotatingFile {
+            path,
+            file,
+            size,
+            rotation,
+            period_start: rotation.period_start(to_config_offset(last_write, config)),
+            cleanup: None,
+        })
+    }
+
+    fn should_rotate(&self, now: OffsetDateTime) -> bool {
+        match self.rotation {
+            Rotation::Never => false,
+            Rotation::Size(max_size) => max_size > 0 && self.size >= max_size,
+            rotation => now >= self.period_start + rotation.period_length(),
+        }
+    }
+
+    fn rotate(&mut self, now: OffsetDateTime, config: &Config) -> io::Result<()> {
+        self.file.flush()?;
+
+        // rotated files are renamed below, so the previous cleanup has to be finished
+        if let Some(cleanup) = self.cleanup.take() {
+            let _ = cleanup.join();
+        }
+
+        let rotated = match &config.rotated_name {
+            Some(pattern) => {
+                let date = match self.rotation.is_timed() {
+                    true => self.rotation.period_suffix(self.period_start),
+                    false => Rotation::Daily.period_suffix(to_config_offset(now, config)),
+                };
+                self.rotate_named(pattern, &date)?
+            }
+            None if self.rotation.is_timed() => self.rotate_timed()?,
+            None => self.rotate_indexed()?,
+        };
+        if self.rotation.is_timed() {
+            self.period_start = self.rotation.period_start(to_config_offset(now, config));
+        }
+
+        self.file = file_options(config).open(&self.path)?;
+        self.size = 0;
+
+        #[cfg(feature = "flate2")]
+        let compression = config
+            .compress_rotated
+            .then(|| config.compression_error_handler.clone());
+        #[cfg(not(feature = "flate2"))]
+        let compression: Option<()> = None;
+        let (max_files, max_bytes) = (config.max_rotated_files, config.max_rotated_bytes);
+        let retention = max_files.is_some() || max_bytes.is_some();
+        if compression.is_none() && !retention {
+            return Ok(());
+        }
+
+        let path = self.path.clone();
+        let pattern = config.rotated_name.clone();
+        self.cleanup = Some(thread::spawn(move || {
+            #[cfg(feature = "flate2")]
+            if let Some(handler) = compression {
+                if let Err(err) = compress(&rotated) {
+                    if let Some(handler) = handler {
+                        (handler.0)(&rotated, &err);
+                    }
+                }
+            }
+            #[cfg(not(feature = "flate2"))]
+            let _ = (compression, rotated);
+
+            if retention {
+                // best effort, files failing to be removed now are retried after the next rotation
+                let _ = remove_old_rotated(&path, pattern.as_deref(), max_files, max_bytes);
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Moves `app.log` to `app.log.1`, `app.log.1` to `app.log.2` and so forth.
+    fn rotate_indexed(&self) -> io::Result<PathBuf> {
+        let mut last = 0;
+        while rotated_exists(&suffixed_path(&self.path, &(last + 1).to_string())) {
+            last += 1;
+        }
+        for index in (1..=last).rev() {
+            let from = suffixed_path(&self.path, &index.to_string());
+            let to = suffixed_path(&self.path, &(index + 1).to_string());
+            if from.exists() {
+                fs::rename(&from, &to)?;
+            }
+            let (from, to) = (suffixed_path(&from, "gz"), suffixed_path(&to, "gz"));
+            if from.exists() {
+                fs::rename(&from, &to)?;
+            }
+        }
+        let rotated = suffixed_path(&self.path, "1");
+        fs::rename(&self.path, &rotated)?;
+        Ok(rotated)
+    }
+
+    /// Moves `app.log` to `app.log.<period>`, appending a counter
+    /// in case a file for that period already exists.
+    fn rotate_timed(&self) -> io::Result<PathBuf> {
+        let suffix = self.rotation.period_suffix(self.period_start);
+        let mut rotated = suffixed_path(&self.path, &suffix);
+        let mut index = 0;
+        while rotated_exists(&rotated) {
+            index += 1;
+            rotated = suffixed_path(&self.path, &format!("{}.{}", suffix, index));
+        }
+        fs::rename(&self.path, &rotated)?;
+        Ok(rotated)
+    }
+
+    /// Moves `app.log` to the first name given by the naming pattern, that does not exist yet
+    fn rotate_named(&self, pattern: &[NamePart], date: &str) -> io::Result<PathBuf> {
+        let has_index = pattern.contains(&NamePart::Index);
+        let mut index = 1;
+        let mut rotated = named_path(&self.path, pattern, date, index);
+        while rotated_exists(&rotated) {
+            index += 1;
+            rotated = match has_index {
+                true => named_path(&self.path, pattern, date, index),
+                false => suffixed_path(
+                    &named_path(&self.path, pattern, date, 0),
+                    &(index - 1).to_string(),
+                ),
+            };
+        }
+        fs::rename(&self.path, &rotated)?;
+        Ok(rotated)
+    }
+}
+
+impl Drop for RotatingFile {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.cleanup.take() {
+            let _ = cleanup.join();
+        }
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Reports a failure to rotate or reopen the log file
+fn file_error(config: &Config, path: &Path, err: &io::Error) {
+    stats::count_write_error();
+    if let Some(handler) = &config.file_error_handler {
+        (handler.0)(path, err);
+    }
+}
+
+/// Path of a file rotated from `path` according to the naming pattern
+fn named_path(path: &Path, pattern: &[NamePart], date: &str, index: u32) -> PathBuf {
+    let mut name = String::new();
+    for part in pattern {
+        match part {
+            NamePart::Literal(literal) => name.push_str(literal),
+            NamePart::Date => name.push_str(date),
+            NamePart::Index => name.push_str(&index.to_string()),
+            part => name.push_str(&name_field(path, part)),
+        }
+    }
+    path.with_file_name(name)
+}
+
+/// Value of the `{name}`, `{stem}` or `{ext}` field of a naming pattern
+fn name_field(path: &Path, part: &NamePart) -> String {
+    let value = match part {
+        NamePart::Name => path.file_name(),
+        NamePart::Stem => path.file_stem(),
+        NamePart::Ext => path.extension(),
+        _ => None,
+    };
+    value.unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// Checks if `file_name` is the name of a file rotated from `path`, either as is or compressed
+///
+/// Only names this crate produces match, so other files next to the log file,
+/// like `app.log.bak`, are never taken for rotated ones.
+fn is_rotated(path: &Path, pattern: Option<&[NamePart]>, file_name: &str) -> bool {
+    let dot = || NamePart::Literal(String::from("."));
+    let defaults;
+    let patterns: &[&[NamePart]] = match pattern {
+        Some(pattern) => &[pattern],
+        None => {
+            // `app.log.1` for rotation by size and `app.log.2024-05-03` for timed rotation
+            defaults = [
+                [NamePart::Name, dot(), NamePart::Index],
+                [NamePart::Name, dot(), NamePart::Date],
+            ];
+            &[&defaults[0], &defaults[1]]
+        }
+    };
+    let matches = |name: &str| {
+        patterns
+            .iter()
+            .any(|pattern| matches_name(path, pattern, name))
+    };
+    // names already existing get a counter appended, unless the pattern has an index
+    let base = file_name.strip_suffix(".gz").unwrap_or(file_name);
+    matches(base)
+        || base.rsplit_once('.').is_some_and(|(name, counter)| {
+            !counter.is_empty() && counter.bytes().all(|c| c.is_ascii_digit()) && matches(name)
+        })
+}
+
+/// Checks if `name` is formed according to the naming pattern `parts`
+fn matches_name(path: &Path, parts: &[NamePart], name: &str) -> bool {
+    match parts.split_first() {
+        None => name.is_empty(),
+        // `2024-05-03` for daily and `2024-05-03-17` for hourly rotation
+        Some((NamePart::Date, rest)) => ["dddd-dd-dd-dd", "dddd-dd-dd"].iter().any(|format| {
+            name.get(..format.len()).is_some_and(|date| {
+                date.bytes().zip(format.bytes()).all(|(c, f)| match f {
+                    b'd' => c.is_ascii_digit(),
+                    f => c == f,
+                })
+            }) && matches_name(path, rest, &name[format.len()..])
+        }),
+        Some((NamePart::Index, rest)) => {
+            let digits = name.bytes().take_while(u8::is_ascii_digit).count();
+            digits > 0 && matches_name(path, rest, &name[digits..])
+        }
+        Some((part, rest)) => {
+            let literal = match part {
+                NamePart::Literal(literal) => literal.clone(),
+                part => name_field(path, part),
+            };
+            name.strip_prefix(literal.as_str())
+                .is_some_and(|name| matches_name(path, rest, name))
+        }
+    }
+}
+
+/// Checks if a rotated file exists, either as is or compressed
+fn rotated_exists(path: &Path) -> bool {
+    path.exists() || suffixed_path(path, "gz").exists()
+}
+
+/// Removes the oldest rotated files of `path`, so that at most `max_files` rotated files
+/// with at most `max_bytes` in total are kept
+fn remove_old_rotated(
+    path: &Path,
+    pattern: Option<&[NamePart]>,
+    max_files: Option<usize>,
+    max_bytes: Option<u64>,
+) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut rotated = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.path() == path || !is_rotated(path, pattern, &entry.file_name().to_string_lossy())
+        {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            rotated.push((modified, metadata.len(), entry.path()));
+        }
+    }
+    // newest first
+    rotated.sort_by_key(|(modified, _, _)| Reverse(*modified));
+
+    let mut total = 0;
+    for (i, (_, len, path)) in rotated.iter().enumerate() {
+        total += len;
+        if max_files.is_some_and(|max| i >= max) || max_bytes.is_some_and(|max| total > max) {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Compresses `path` into `path.gz` and removes the uncompressed file
+#[cfg(feature = "flate2")]
+fn compress(path: &Path) -> io::Result<()> {
+    use flate2::{write::GzEncoder, Compression};
+
+    let mut input = File::open(path)?;
+    let mut encoder = GzEncoder::new(
+        File::create(suffixed_path(path, "gz"))?,
+        Compression::default(),
+    );
+    io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?.sync_all()?;
+    fs::remove_file(path)
+}
+
+fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Incremented by the SIGHUP handler installed by [`FileLogger::reopen_on_sighup`]
+#[cfg(unix)]
+static SIGHUP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+extern "C" fn on_sighup(_signum: libc::c_int) {
+    // only async-signal-safe operations are allowed here, the files are reopened by the next record
+    SIGHUP_COUNT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// The FileLogger struct. Provides a Logger implementation writing to a file,
+/// that is rotated according to a [`Rotation`] policy.
+///
+/// When the file at `path` needs to be rotated it is renamed (see [`Rotation`] for the
+/// resulting names) and a new file is opened at `path`. Rotation is checked before
+/// writing each record, so a single record is never split across two files.
+/// Time boundaries are evaluated using the time offset of the `Config`.
+///
+/// To cooperate with external tools like logrotate, the file can be reopened using [`FileLogger::reopen`]
+/// or, on Unix, whenever the process receives a SIGHUP (see [`FileLogger::reopen_on_sighup`]).
+pub struct FileLogger {
+    level: AtomicLevel,
+    config: Config,
+    file: Mutex<RotatingFile>,
+    /// Value of `SIGHUP_COUNT` the file was last opened at
+    #[cfg(unix)]
+    sighups: AtomicUsize,
+}
+
+impl FileLogger {
+    /// init function. Globally initializes the FileLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the path of the log file and the `Rotation` policy
+    /// as arguments. The level can be changed later on using the returned handle.
+    /// Fails if the file cannot be opened or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = FileLogger::init(LevelFilter::Info, Config::default(), "my_rust_bin.log", Rotation::Size(10 * 1024 * 1024));
+    /// # }
+    /// ```
+    pub fn init<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        rotation: Rotation,
+    ) -> Result<LoggerHandle<FileLogger>, InitError> {
+        let logger = FileLogger::new(log_level, config, path, rotation)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the path of the log file and the `Rotation` policy
+    /// as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// The file is created if it does not exist and appended to otherwise,
+    /// fails with a [`FileError`] naming the path, that could not be opened.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let file_logger = FileLogger::new(LevelFilter::Info, Config::default(), "my_rust_bin.log", Rotation::Size(10 * 1024 * 1024)).unwrap();
+    /// # }
+    /// ```
+    pub fn new<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        rotation: Rotation,
+    ) -> Result<Box<FileLogger>, FileError> {
+        let file = RotatingFile::open(path.as_ref().to_path_buf(), rotation, &config)?;
+        Ok(Box::new(FileLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            file: Mutex::new(file),
+            #[cfg(unix)]
+            sighups: AtomicUsize::new(SIGHUP_COUNT.load(Ordering::Relaxed)),
+        }))
+    }
+
+    /// Closes the log file and opens the file at its path again, creating it if necessary
+    ///
+    /// Call this after the file was moved away, e.g. by logrotate, to continue writing
+    /// to a new file at the original path instead of the moved one.
+    pub fn reopen(&self) -> io::Result<()> {
+        self.file.lock().unwrap().reopen(&self.config)
+    }
+
+    /// Installs a handler for SIGHUP, that makes all FileLoggers reopen their files
+    /// before writing the next record
+    ///
+    /// This matches the convention of logrotate setups sending SIGHUP after moving the files away.
+    /// Replaces any handler for SIGHUP installed before, system calls interrupted by the signal are restarted.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = FileLogger::init(LevelFilter::Info, Config::default(), "my_rust_bin.log", Rotation::Never);
+    /// FileLogger::reopen_on_sighup().unwrap();
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn reopen_on_sighup() -> io::Result<()> {
+        // SAFETY: the handler only touches an atomic, which is async-signal-safe,
+        // and an all-zero `sigaction` is valid before its fields are set
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = on_sighup as extern "C" fn(libc::c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(libc::SIGHUP, &action, std::ptr::null_mut()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap().flush();
+    }
+}
+
+impl SharedLogger for FileLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            with_formatted(&self.config, record, |line| {
+                let now = OffsetDateTime::now_utc();
+                let mut file = self.file.lock().unwrap();
+                #[cfg(unix)]
+                {
+                    let sighups = SIGHUP_COUNT.load(Ordering::Relaxed);
+                    if self.sighups.swap(sighups, Ordering::Relaxed) != sighups {
+                        // on failure keep writing to the current file rather than losing records
+                        if let Err(err) = file.reopen(&self.config) {
+                            file_error(&self.config, &file.path, &err);
+                        }
+                    }
+                }
+                if file.should_rotate(now) {
+                    // on failure keep writing to the current file rather than losing records
+                    if let Err(err) = file.rotate(now, &self.config) {
+                        file_error(&self.config, &file.path, &err);
+                    }
+                }
+                if self.config.file_locking {
+                    file.file.lock()?;
+                    // the lock has to be released in any case, also if writing panics
+                    let res = panic::catch_unwind(AssertUnwindSafe(|| file.write_all(line)));
+                    let unlocked = file.file.unlock();
+                    match res {
+                        Ok(res) => res.and(unlocked),
+                        Err(panic) => panic::resume_unwind(panic),
+                    }
+                } else {
+                    file.write_all(line)
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, test_dir};
+    use crate::{ConfigBuilder, Level, WriteLogger};
+
+    #[test]
+    fn test_file_rotation() {
+        let dir = test_dir("test_file_rotation");
+        let path = dir.join("app.log");
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Off)
+            .build();
+        let logger = FileLogger::new(LevelFilter::Info, conf, &path, Rotation::Size(16)).unwrap();
+        for msg in &["first record", "second record", "third record"] {
+            log(&logger, Level::Info, msg);
+        }
+        logger.flush();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert!(read("app.log").contains("third record"));
+        assert!(read("app.log.1").contains("second record"));
+        assert!(read("app.log.2").contains("first record"));
+        assert!(!dir.join("app.log.3").exists());
+    }
+
+    #[test]
+    fn test_file_rotation_error() {
+        let dir = test_dir("test_file_rotation_error");
+        let path = dir.join("app.log");
+
+        let errors = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_errors = errors.clone();
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            // rotated files cannot be moved into a missing directory
+            .set_rotated_name_pattern("missing/{name}.{index}")
+            .unwrap()
+            .set_file_error_handler(move |path, err| {
+                handler_errors
+                    .lock()
+                    .unwrap()
+                    .push((path.to_path_buf(), err.kind()))
+            })
+            .build();
+        let logger = FileLogger::new(LevelFilter::Info, conf, &path, Rotation::Size(1)).unwrap();
+        let before = stats().write_errors;
+        for msg in &["first record", "second record"] {
+            log(&logger, Level::Info, msg);
+        }
+        logger.flush();
+
+        // the records are still written into the current file
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("first record"));
+        assert!(contents.contains("second record"));
+        assert_eq!(
+            *errors.lock().unwrap(),
+            [(path.clone(), std::io::ErrorKind::NotFound)]
+        );
+        assert!(stats().write_errors > before);
+    }
+
+    #[test]
+    fn test_file_reopen() {
+        let dir = test_dir("test_file_reopen");
+        let path = dir.join("app.log");
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let logger = FileLogger::new(LevelFilter::Info, conf, &path, Rotation::Never).unwrap();
+        let log = |msg| log(&logger, Level::Info, msg);
+        log("before");
+        std::fs::rename(&path, dir.join("app.log.old")).unwrap();
+        logger.reopen().unwrap();
+        log("after");
+        logger.flush();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("app.log.old"), "[INFO] before\n");
+        assert_eq!(read("app.log"), "[INFO] after\n");
+
+        #[cfg(unix)]
+        {
+            FileLogger::reopen_on_sighup().unwrap();
+            std::fs::rename(&path, dir.join("app.log.older")).unwrap();
+            // SAFETY: the installed handler only increments an atomic
+            assert_eq!(unsafe { libc::raise(libc::SIGHUP) }, 0);
+            log("hangup");
+            logger.flush();
+            assert_eq!(read("app.log.older"), "[INFO] after\n");
+            assert_eq!(read("app.log"), "[INFO] hangup\n");
+        }
+    }
+
+    #[test]
+    fn test_file_locking() {
+        let dir = test_dir("test_file_locking");
+        let path = dir.join("app.log");
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_file_locking(true)
+            .build();
+        let loggers = [
+            FileLogger::new(LevelFilter::Info, conf.clone(), &path, Rotation::Never).unwrap(),
+            FileLogger::new(LevelFilter::Info, conf, &path, Rotation::Never).unwrap(),
+        ];
+        for logger in &loggers {
+            log(&logger, Level::Info, "locked");
+        }
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[INFO] locked\n[INFO] locked\n"
+        );
+    }
+
+    #[test]
+    fn test_file_create_options() {
+        let dir = test_dir("test_file_create_options");
+        let path = dir.join("nested").join("app.log");
+
+        let log = |config: Config| {
+            let logger = WriteLogger::create(LevelFilter::Info, config, &path)?;
+            log(&logger, Level::Info, "created");
+            Ok::<_, FileError>(())
+        };
+        let config = |truncate| {
+            ConfigBuilder::new()
+                .set_time_level(LevelFilter::Off)
+                .set_file_truncate(truncate)
+                .set_create_dirs(true)
+                .build()
+        };
+
+        match log(Config::default()) {
+            Err(FileError::Open(err_path, _)) => assert_eq!(err_path, path),
+            res => panic!("unexpected result: {:?}", res),
+        }
+        match FileLogger::new(LevelFilter::Info, Config::default(), &path, Rotation::Never) {
+            Err(FileError::Open(err_path, _)) => assert_eq!(err_path, path),
+            res => panic!("unexpected result: {:?}", res.map(|_| ())),
+        }
+        log(config(false)).unwrap();
+        log(config(false)).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[INFO] created\n[INFO] created\n"
+        );
+        log(config(true)).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[INFO] created\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("test_file_mode");
+        let path = dir.join("app.log");
+
+        let conf = ConfigBuilder::new().set_file_mode(0o600).build();
+        let logger = FileLogger::new(LevelFilter::Info, conf, &path, Rotation::Size(1)).unwrap();
+        for _ in 0..2 {
+            log(&logger, Level::Info, "secret");
+        }
+        for name in &["app.log", "app.log.1"] {
+            let mode = std::fs::metadata(dir.join(name))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_file_retention() {
+        let dir = test_dir("test_file_retention");
+        // files of others next to the log file, older than all rotated files
+        for name in &[
+            "app.log.bak",
+            "app.log.lock",
+            "app.log.old.gz",
+            "app.log.1x",
+        ] {
+            std::fs::write(dir.join(name), "foreign").unwrap();
+        }
+        std::thread::sleep(std::time::Duration::from_millis(20));
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_max_rotated_files(2)
+            .build();
+        let logger = FileLogger::new(
+            LevelFilter::Info,
+            conf,
+            dir.join("app.log"),
+            Rotation::Size(1),
+        )
+        .unwrap();
+        for i in 0..5 {
+            log(&logger, Level::Info, &format!("record {}", i));
+            // keep the modification times of the rotated files apart
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        drop(logger);
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("app.log"), "[INFO] record 4\n");
+        assert_eq!(read("app.log.1"), "[INFO] record 3\n");
+        assert_eq!(read("app.log.2"), "[INFO] record 2\n");
+        assert!(!dir.join("app.log.3").exists());
+        assert!(!dir.join("app.log.4").exists());
+        for name in &[
+            "app.log.bak",
+            "app.log.lock",
+            "app.log.old.gz",
+            "app.log.1x",
+        ] {
+            assert_eq!(read(name), "foreign");
+        }
+    }
+
+    #[test]
+    fn test_rotated_name_pattern() {
+        let dir = test_dir("test_rotated_name_pattern");
+
+        assert!(ConfigBuilder::new()
+            .set_rotated_name_pattern("{stem}-{nope}.{ext}")
+            .is_err());
+        assert!(ConfigBuilder::new()
+            .set_rotated_name_pattern("{stem}-{index")
+            .is_err());
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_rotated_name_pattern("{stem}-{index}.{ext}")
+            .unwrap()
+            .set_max_rotated_files(2)
+            .build();
+        let logger = FileLogger::new(
+            LevelFilter::Info,
+            conf,
+            dir.join("app.log"),
+            Rotation::Size(1),
+        )
+        .unwrap();
+        for i in 0..4 {
+            log(&logger, Level::Info, &format!("record {}", i));
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        drop(logger);
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("app.log"), "[INFO] record 3\n");
+        assert_eq!(read("app-3.log"), "[INFO] record 2\n");
+        assert_eq!(read("app-2.log"), "[INFO] record 1\n");
+        assert!(!dir.join("app-1.log").exists());
+    }
+
+    #[cfg(feature = "flate2")]
+    #[test]
+    fn test_file_rotation_compressed() {
+        let dir = test_dir("test_file_rotation_compressed");
+
+        let conf = ConfigBuilder::new()
+            .set_compress_rotated(true)
+            .set_compression_error_handler(|path, err| panic!("{:?}: {}", path, err))
+            .build();
+        let logger = FileLogger::new(
+            LevelFilter::Info,
+            conf,
+            dir.join("app.log"),
+            Rotation::Size(1),
+        )
+        .unwrap();
+        for _ in 0..3 {
+            log(&logger, Level::Info, "compressed record");
+        }
+        drop(logger);
+
+        assert!(dir.join("app.log.1.gz").exists());
+        assert!(dir.join("app.log.2.gz").exists());
+        assert!(!dir.join("app.log.1").exists());
+        assert!(!dir.join("app.log.2").exists());
+    }
+}
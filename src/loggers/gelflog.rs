@@ -0,0 +1,355 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the GelfLogger Implementation
+
+use super::logging::{
+    enabled, hostname, key_values, max_level, now, should_skip, throttled, write_json_str,
+    AtomicLevel,
+};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io::{self, Write};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Magic bytes starting every chunk of a chunked message
+const CHUNK_MAGIC: [u8; 2] = [0x1e, 0x0f];
+/// Size of the header preceding the payload of every chunk
+const CHUNK_HEADER: usize = 12;
+/// Maximum amount of chunks a message may be split into
+const MAX_CHUNKS: usize = 128;
+
+/// Settings of the messages sent by the [`GelfLogger`]
+#[derive(Debug, Clone)]
+pub struct GelfOptions {
+    fields: Vec<(String, String)>,
+    chunk_size: usize,
+}
+
+impl GelfOptions {
+    /// Creates the default settings
+    pub fn new() -> GelfOptions {
+        GelfOptions {
+            fields: Vec::new(),
+            chunk_size: 8192,
+        }
+    }
+
+    /// Add an additional field, that is sent with every record
+    ///
+    /// The name is sent with a leading underscore, e.g. `add_field("env", "production")`
+    /// results in the field `_env`.
+    pub fn add_field(&mut self, name: &str, value: &str) -> &mut GelfOptions {
+        self.fields.push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// set the maximum size of a datagram, bigger messages are chunked (default is 8192)
+    ///
+    /// Use smaller values like 1420, if the messages have to pass networks with a small MTU.
+    pub fn set_chunk_size(&mut self, size: usize) -> &mut GelfOptions {
+        self.chunk_size = size.max(64);
+        self
+    }
+}
+
+impl Default for GelfOptions {
+    fn default() -> GelfOptions {
+        GelfOptions::new()
+    }
+}
+
+/// Maps a `Level` to the matching syslog severity used by GELF
+fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// The GelfLogger struct. Provides a Logger implementation sending records to Graylog
+/// using the GELF format over UDP.
+///
+/// The first line of the message is sent as `short_message`, multi-line messages are also
+/// sent as a whole as `full_message`. The target, module, file and line of the record are sent
+/// as the additional fields `_target`, `_module`, `_file` and `_line`, the key-values of the record
+/// as additional fields named after their key. Further fields
+/// can be added using [`GelfOptions::add_field`].
+///
+/// Messages exceeding the chunk size (see [`GelfOptions::set_chunk_size`])
+/// are split into GELF chunks, messages too big for 128 chunks are dropped.
+pub struct GelfLogger {
+    level: AtomicLevel,
+    config: Config,
+    options: GelfOptions,
+    host: String,
+    socket: UdpSocket,
+    message_id: AtomicU64,
+}
+
+impl GelfLogger {
+    /// init function. Globally initializes the GelfLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the address of the Graylog GELF UDP input
+    /// and the settings of the messages as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the address cannot be resolved or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = GelfLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     "graylog.example.com:12201",
+    ///     GelfOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        addr: A,
+        options: GelfOptions,
+    ) -> Result<LoggerHandle<GelfLogger>, InitError> {
+        let logger = GelfLogger::new(log_level, config, addr, options)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the address of the Graylog GELF UDP input
+    /// and the settings of the messages as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let mut options = GelfOptions::new();
+    /// options.add_field("env", "production").set_chunk_size(1420);
+    /// let gelf_logger =
+    ///     GelfLogger::new(LevelFilter::Info, Config::default(), "127.0.0.1:12201", options).unwrap();
+    /// # }
+    /// ```
+    pub fn new<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        addr: A,
+        options: GelfOptions,
+    ) -> io::Result<Box<GelfLogger>> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::AddrNotAvailable, "no address to send to")
+        })?;
+        let socket = if addr.is_ipv4() {
+            UdpSocket::bind("0.0.0.0:0")?
+        } else {
+            UdpSocket::bind("[::]:0")?
+        };
+        socket.connect(addr)?;
+
+        // message ids only have to be unique for a short time, so seed them with the current time
+        let seed = now().unix_timestamp_nanos() as u64;
+
+        Ok(Box::new(GelfLogger {
+            level: AtomicLevel::new(log_level),
+            host: hostname(&config).to_string(),
+            config,
+            options,
+            socket,
+            message_id: AtomicU64::new(seed),
+        }))
+    }
+
+    fn encode(&self, record: &Record<'_>) -> io::Result<Vec<u8>> {
+        let message = record.args().to_string();
+        let short_message = message.lines().next().unwrap_or_default();
+        let timestamp = now().unix_timestamp_nanos() / 1_000_000;
+
+        let mut json = Vec::new();
+        json.write_all(b"{\"version\":\"1.1\",\"host\":")?;
+        write_json_str(&mut json, &self.host)?;
+        json.write_all(b",\"short_message\":")?;
+        write_json_str(&mut json, short_message)?;
+        if short_message.len() != message.len() {
+            json.write_all(b",\"full_message\":")?;
+            write_json_str(&mut json, &message)?;
+        }
+        write!(
+            json,
+            ",\"timestamp\":{}.{:03},\"level\":{}",
+            timestamp / 1000,
+            timestamp % 1000,
+            severity(record.level())
+        )?;
+        json.write_all(b",\"_target\":")?;
+        write_json_str(&mut json, record.target())?;
+        if let Some(module) = record.module_path() {
+            json.write_all(b",\"_module\":")?;
+            write_json_str(&mut json, module)?;
+        }
+        if let Some(file) = record.file() {
+            json.write_all(b",\"_file\":")?;
+            write_json_str(&mut json, file)?;
+        }
+        if let Some(line) = record.line() {
+            write!(json, ",\"_line\":{}", line)?;
+        }
+        let key_values = key_values(record);
+        for (name, value) in key_values.iter().chain(&self.options.fields) {
+            json.write_all(b",")?;
+            write_json_str(&mut json, &format!("_{}", name))?;
+            json.write_all(b":")?;
+            write_json_str(&mut json, value)?;
+        }
+        json.write_all(b"}")?;
+        Ok(json)
+    }
+
+    fn send(&self, message: &[u8]) -> io::Result<()> {
+        let chunk_size = self.options.chunk_size;
+        if message.len() <= chunk_size {
+            self.socket.send(message)?;
+            return Ok(());
+        }
+
+        let payload_size = chunk_size - CHUNK_HEADER;
+        let count = message.len().div_ceil(payload_size);
+        if count > MAX_CHUNKS {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "message too big for GELF chunking",
+            ));
+        }
+
+        let id = self
+            .message_id
+            .fetch_add(1, Ordering::Relaxed)
+            .to_be_bytes();
+        let mut chunk = Vec::with_capacity(chunk_size);
+        for (seq, payload) in message.chunks(payload_size).enumerate() {
+            chunk.clear();
+            chunk.extend_from_slice(&CHUNK_MAGIC);
+            chunk.extend_from_slice(&id);
+            chunk.push(seq as u8);
+            chunk.push(count as u8);
+            chunk.extend_from_slice(payload);
+            self.socket.send(&chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Log for GelfLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for GelfLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        let mut res = Ok(());
+        if self.enabled(record.metadata()) && !should_skip(&self.config, record) {
+            throttled(&self.config, record, |record| {
+                if res.is_ok() {
+                    res = self.encode(record).and_then(|message| self.send(&message));
+                }
+            });
+        }
+        res
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gelf_logger() {
+        use std::net::UdpSocket;
+
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let mut options = GelfOptions::new();
+        options.add_field("env", "test").set_chunk_size(200);
+        let logger = GelfLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            server.local_addr().unwrap(),
+            options,
+        )
+        .unwrap();
+        let log = |msg: &str| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("{}", msg))
+                    .level(Level::Warn)
+                    .target("gelf_test")
+                    .build(),
+            )
+        };
+        let mut buf = [0; 1024];
+
+        log("short");
+        let len = server.recv(&mut buf).unwrap();
+        let msg = String::from_utf8_lossy(&buf[..len]).into_owned();
+        assert!(msg.starts_with("{\"version\":\"1.1\",\"host\":"), "{}", msg);
+        assert!(msg.contains(",\"short_message\":\"short\",\"timestamp\":"));
+        assert!(msg.ends_with(",\"level\":4,\"_target\":\"gelf_test\",\"_env\":\"test\"}"));
+
+        let long = format!("first line\n{}", "x".repeat(400));
+        log(&long);
+        let mut chunks = Vec::new();
+        for expected_seq in 0.. {
+            let len = server.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..2], &[0x1e, 0x0f]);
+            let (seq, count) = (buf[10], buf[11]);
+            assert_eq!(seq, expected_seq);
+            chunks.extend_from_slice(&buf[12..len]);
+            if seq + 1 == count {
+                break;
+            }
+        }
+        let msg = String::from_utf8(chunks).unwrap();
+        assert!(
+            msg.contains("\"short_message\":\"first line\",\"full_message\":\"first line\\nxxx")
+        );
+        assert!(msg.ends_with("}"));
+    }
+}
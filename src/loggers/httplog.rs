@@ -0,0 +1,518 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the HttpLogger Implementation
+
+use super::logging::{
+    enabled, max_level, now, sending, should_skip, throttled, to_config_offset, while_sending,
+    write_json, AtomicLevel,
+};
+use super::tcplog::{ConnectionEvent, NetworkOptions};
+use crate::{handle, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Timeout of a single request, so a stalled collector cannot block the worker forever
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Settings of the requests made by the [`HttpLogger`]
+///
+/// # Examples
+/// ```no_run
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use std::time::Duration;
+/// # fn main() {
+/// let mut options = HttpOptions::new();
+/// options
+///     .add_header("Authorization", "Bearer secret")
+///     .set_batch_interval(Duration::from_secs(1));
+/// let _ = HttpLogger::init(
+///     LevelFilter::Info,
+///     Config::default(),
+///     "https://logs.example.com/ingest",
+///     options,
+///     NetworkOptions::default(),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpOptions {
+    headers: Vec<(String, String)>,
+    batch_size: usize,
+    batch_interval: Duration,
+}
+
+impl HttpOptions {
+    /// Creates the default settings
+    pub fn new() -> HttpOptions {
+        HttpOptions {
+            headers: Vec::new(),
+            batch_size: 100,
+            batch_interval: Duration::from_secs(5),
+        }
+    }
+
+    /// Add a header to the requests, e.g. for authentication
+    ///
+    /// For example `add_header("Authorization", "Bearer <token>")`.
+    pub fn add_header(&mut self, name: &str, value: &str) -> &mut HttpOptions {
+        self.headers.push((String::from(name), String::from(value)));
+        self
+    }
+
+    /// set the amount of records collected, before sending them as one batch (default is 100)
+    pub fn set_batch_size(&mut self, size: usize) -> &mut HttpOptions {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// set the maximum time a record waits to be sent, before an incomplete batch is sent (default is 5s)
+    pub fn set_batch_interval(&mut self, interval: Duration) -> &mut HttpOptions {
+        self.batch_interval = interval;
+        self
+    }
+}
+
+impl Default for HttpOptions {
+    fn default() -> HttpOptions {
+        HttpOptions::new()
+    }
+}
+
+/// Records waiting to be sent
+struct Batch {
+    records: VecDeque<Vec<u8>>,
+    /// When the oldest pending record was queued
+    since: Option<Instant>,
+    /// When sending is retried after a failure
+    retry_at: Option<Instant>,
+    backoff: Duration,
+    dropped: u64,
+    closed: bool,
+}
+
+/// State shared between the logger and its worker thread
+struct Shipper {
+    config: Config,
+    options: HttpOptions,
+    network: NetworkOptions,
+    url: String,
+    agent: ureq::Agent,
+    batch: Mutex<Batch>,
+    changed: Condvar,
+}
+
+impl Shipper {
+    /// Sends all pending records, requeueing them if the request fails
+    fn ship<'a>(&'a self, mut batch: MutexGuard<'a, Batch>) -> MutexGuard<'a, Batch> {
+        let records: Vec<_> = batch.records.drain(..).collect();
+        batch.since = None;
+        drop(batch);
+
+        let mut body = Vec::from(&b"["[..]);
+        for (i, record) in records.iter().enumerate() {
+            if i > 0 {
+                body.push(b',');
+            }
+            body.extend_from_slice(record);
+        }
+        body.push(b']');
+
+        let mut request = self
+            .agent
+            .post(&self.url)
+            .set("Content-Type", "application/json");
+        for (name, value) in &self.options.headers {
+            request = request.set(name, value);
+        }
+        let result = while_sending(|| {
+            request
+                .send_bytes(&body)
+                .map_err(|err| io::Error::other(err.to_string()))
+        });
+
+        let mut batch = self.batch.lock().unwrap();
+        match result {
+            Ok(_) => {
+                batch.retry_at = None;
+                batch.backoff = self.network.reconnect_backoff.0;
+                if batch.dropped > 0 {
+                    self.network.report(ConnectionEvent::Dropped(batch.dropped));
+                    batch.dropped = 0;
+                }
+            }
+            Err(err) => {
+                let backoff = batch.backoff;
+                self.network
+                    .report(ConnectionEvent::SendFailed(&err, backoff));
+                batch.retry_at = Some(Instant::now() + backoff);
+                batch.backoff = (backoff * 2).min(self.network.reconnect_backoff.1);
+
+                // put the records back in front of the ones queued in the meantime
+                for record in records.into_iter().rev() {
+                    batch.records.push_front(record);
+                }
+                while batch.records.len() > self.network.buffer_limit.max(1) {
+                    batch.records.pop_front();
+                    batch.dropped += 1;
+                }
+                batch.since.get_or_insert_with(Instant::now);
+            }
+        }
+        batch
+    }
+
+    fn run(&self) {
+        let mut batch = self.batch.lock().unwrap();
+        loop {
+            if batch.closed {
+                if !batch.records.is_empty() {
+                    drop(self.ship(batch));
+                }
+                return;
+            }
+
+            let now = Instant::now();
+            let mut deadline = batch.since.map(|since| since + self.options.batch_interval);
+            let full = batch.records.len() >= self.options.batch_size;
+            if full {
+                deadline = Some(now);
+            }
+            if let Some(retry_at) = batch.retry_at {
+                deadline = deadline.map(|deadline| deadline.max(retry_at));
+            }
+
+            batch = match deadline {
+                Some(deadline) if deadline <= now => self.ship(batch),
+                Some(deadline) => self.changed.wait_timeout(batch, deadline - now).unwrap().0,
+                None => self.changed.wait(batch).unwrap(),
+            };
+        }
+    }
+}
+
+/// Encodes a record as a JSON object
+fn to_json(record: &Record<'_>, config: &Config) -> io::Result<Vec<u8>> {
+    let mut json = Vec::new();
+    write_json(record, &mut json, config, to_config_offset(now(), config))?;
+    Ok(json)
+}
+
+/// The HttpLogger struct. Provides a Logger implementation sending records in batches to an HTTP endpoint.
+///
+/// Records are collected and POSTed as a JSON array of objects with the fields
+/// `time` (RFC 3339), `level`, `target`, `module`, `file`, `line`, `message`
+/// and `kv` (an object holding the key-values of the record, if it has any),
+/// as well as `host`, if enabled by [`ConfigBuilder::set_hostname_level`],
+/// once a batch is full (see [`HttpOptions::set_batch_size`]) or the oldest record waited
+/// long enough (see [`HttpOptions::set_batch_interval`]).
+/// Headers, e.g. for authentication, are added using [`HttpOptions::add_header`].
+///
+/// Requests are made from a background thread. If a request fails, the records
+/// are kept (up to [`NetworkOptions::set_buffer_limit`]) and sent again with the
+/// backoff set by [`NetworkOptions::set_reconnect_backoff`]. Failures are reported to the handler
+/// set by [`NetworkOptions::set_connection_handler`]. Pending records are sent on `flush`
+/// and when the logger is dropped.
+/// The records logged by `ureq` while sending a request are dropped by this logger,
+/// so they neither cause the next request nor leak its headers.
+///
+/// [`ConfigBuilder::set_hostname_level`]: crate::ConfigBuilder::set_hostname_level
+pub struct HttpLogger {
+    level: AtomicLevel,
+    shipper: Arc<Shipper>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl HttpLogger {
+    /// init function. Globally initializes the HttpLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the URL of the endpoint, the settings of the requests
+    /// and of the connection as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let mut options = HttpOptions::new();
+    /// options.add_header("Authorization", "Bearer secret");
+    /// let _ = HttpLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     "https://logs.example.com/ingest",
+    ///     options,
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        url: &str,
+        options: HttpOptions,
+        network: NetworkOptions,
+    ) -> Result<LoggerHandle<HttpLogger>, SetLoggerError> {
+        handle::init(HttpLogger::new(log_level, config, url, options, network))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the URL of the endpoint, the settings of the requests
+    /// and of the connection as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let http_logger = HttpLogger::new(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     "https://logs.example.com/ingest",
+    ///     HttpOptions::default(),
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(
+        log_level: LevelFilter,
+        config: Config,
+        url: &str,
+        options: HttpOptions,
+        network: NetworkOptions,
+    ) -> Box<HttpLogger> {
+        let backoff = network.reconnect_backoff.0;
+        let shipper = Arc::new(Shipper {
+            config,
+            options,
+            network,
+            url: String::from(url),
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            batch: Mutex::new(Batch {
+                records: VecDeque::new(),
+                since: None,
+                retry_at: None,
+                backoff,
+                dropped: 0,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let worker_shipper = shipper.clone();
+        let worker = thread::Builder::new()
+            .name(String::from("simplelog-http"))
+            .spawn(move || worker_shipper.run())
+            .expect("failed to spawn thread");
+
+        Box::new(HttpLogger {
+            level: AtomicLevel::new(log_level),
+            shipper,
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+}
+
+impl Log for HttpLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.shipper.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata())
+            && !sending()
+            && !should_skip(&self.shipper.config, record)
+        {
+            throttled(&self.shipper.config, record, |record| {
+                let json = match to_json(record, &self.shipper.config) {
+                    Ok(json) => json,
+                    Err(_) => return,
+                };
+                let batch_size = self.shipper.options.batch_size;
+                let limit = self.shipper.network.buffer_limit;
+                let mut batch = self.shipper.batch.lock().unwrap();
+                while batch.records.len() >= limit.max(batch_size) {
+                    batch.records.pop_front();
+                    batch.dropped += 1;
+                }
+                batch.records.push_back(json);
+                // the first record of a batch starts its interval, the worker has to wait for it
+                let started = batch.since.is_none();
+                batch.since.get_or_insert_with(Instant::now);
+                if started || batch.records.len() >= batch_size {
+                    self.shipper.changed.notify_one();
+                }
+            });
+        }
+    }
+
+    fn flush(&self) {
+        let batch = self.shipper.batch.lock().unwrap();
+        if !batch.records.is_empty() {
+            drop(self.shipper.ship(batch));
+        }
+    }
+}
+
+impl Drop for HttpLogger {
+    fn drop(&mut self) {
+        self.shipper.batch.lock().unwrap().closed = true;
+        self.shipper.changed.notify_one();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl SharedLogger for HttpLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.shipper.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.shipper.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::Level;
+
+    #[test]
+    fn test_http_logger() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let mut options = HttpOptions::new();
+        options
+            .set_batch_size(2)
+            .set_batch_interval(std::time::Duration::from_secs(3600))
+            .add_header("Authorization", "Bearer secret");
+        let logger = HttpLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            &url,
+            options,
+            NetworkOptions::default(),
+        );
+        for i in 0..2 {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("record \"{}\"", i))
+                    .level(Level::Warn)
+                    .target("http_test")
+                    .line(Some(i))
+                    .build(),
+            );
+        }
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut headers = Vec::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            headers.push(line.trim_end().to_lowercase());
+        }
+        let length: usize = headers
+            .iter()
+            .find_map(|header| header.strip_prefix("content-length: "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        (&stream)
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        drop(logger);
+
+        assert_eq!(headers[0], "post /ingest http/1.1");
+        assert!(headers.contains(&String::from("authorization: bearer secret")));
+        assert!(headers.contains(&String::from("content-type: application/json")));
+        let body = String::from_utf8(body).unwrap();
+        assert!(body.starts_with("[{\"time\":\""), "{}", body);
+        assert!(body.ends_with(
+            ",\"level\":\"WARN\",\"target\":\"http_test\",\"line\":1,\"message\":\"record \\\"1\\\"\"}]"
+        ), "{}", body);
+        assert_eq!(body.matches("\"message\"").count(), 2);
+    }
+
+    #[test]
+    fn test_http_logger_interval() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::time::Duration;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/ingest", listener.local_addr().unwrap());
+        let mut options = HttpOptions::new();
+        options
+            .set_batch_size(100)
+            .set_batch_interval(Duration::from_millis(200));
+        let logger = HttpLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            &url,
+            options,
+            NetworkOptions::default(),
+        );
+        // let the worker go idle without a pending batch
+        std::thread::sleep(Duration::from_millis(100));
+        log(&logger, Level::Warn, "partial batch");
+
+        let (received, receive) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            reader.read_line(&mut request).unwrap();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            (&stream)
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            let _ = received.send(request);
+        });
+        // the batch is sent once the interval passed, without flushing or dropping the logger
+        let request = receive.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(request.trim_end(), "POST /ingest HTTP/1.1");
+        drop(logger);
+    }
+}
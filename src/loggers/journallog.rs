@@ -0,0 +1,277 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the JournalLogger Implementation
+
+use super::logging::{enabled, key_values, max_level, should_skip, throttled, AtomicLevel};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::env;
+use std::io::{self, Write};
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Socket journald receives native protocol messages on
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Maps a `Level` to the matching syslog priority used by the journal
+fn priority(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+/// Appends a field in the journal export format.
+///
+/// Values containing newlines are written as `NAME\n<u64 le length><value>\n`,
+/// everything else as `NAME=value\n`.
+fn write_field(buf: &mut Vec<u8>, name: &str, value: &[u8]) {
+    buf.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        buf.push(b'=');
+    }
+    buf.extend_from_slice(value);
+    buf.push(b'\n');
+}
+
+/// Converts a key into a valid journal field name.
+///
+/// Field names may only consist of uppercase ASCII letters, digits and underscores,
+/// must not start with an underscore or digit and are at most 64 characters long.
+fn field_name(key: &str) -> Option<String> {
+    let name: String = key
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+            _ => '_',
+        })
+        .skip_while(|c| *c == '_' || c.is_ascii_digit())
+        .take(64)
+        .collect();
+    if name.is_empty() {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// The JournalLogger struct. Provides a Logger implementation for systemd-journald.
+///
+/// Records are sent using the native journal protocol, so besides the `MESSAGE`
+/// they carry `PRIORITY`, `TARGET`, `CODE_MODULE`, `CODE_FILE` and `CODE_LINE` as separate fields,
+/// which can be used for filtering, e.g. `journalctl TARGET=my_crate::db`.
+/// The key-values of the record are sent as fields as well, their keys converted to uppercase
+/// and characters not allowed in field names replaced by `_`.
+/// The `SYSLOG_IDENTIFIER` is the file name of the running executable.
+///
+/// Since the journal stores its own timestamps and metadata, the `MESSAGE` only contains
+/// the logged message itself and the formatting options of the `Config` are not applied.
+pub struct JournalLogger {
+    level: AtomicLevel,
+    config: Config,
+    identifier: String,
+    path: PathBuf,
+    socket: Mutex<UnixDatagram>,
+}
+
+impl JournalLogger {
+    /// init function. Globally initializes the JournalLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the journal cannot be reached or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = JournalLogger::init(LevelFilter::Info, Config::default());
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+    ) -> Result<LoggerHandle<JournalLogger>, InitError> {
+        let logger = JournalLogger::new(log_level, config)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let journal_logger = JournalLogger::new(LevelFilter::Info, Config::default()).unwrap();
+    /// # }
+    /// ```
+    pub fn new(log_level: LevelFilter, config: Config) -> io::Result<Box<JournalLogger>> {
+        JournalLogger::with_socket_path(log_level, config, JOURNAL_SOCKET)
+    }
+
+    /// Same as [`JournalLogger::new`], but connects to the journal socket at `path`.
+    pub fn with_socket_path<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+    ) -> io::Result<Box<JournalLogger>> {
+        let path = path.as_ref().to_path_buf();
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(&path)?;
+
+        let identifier = env::current_exe()
+            .ok()
+            .and_then(|exe| {
+                exe.file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| String::from("rust"));
+
+        Ok(Box::new(JournalLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            identifier,
+            path,
+            socket: Mutex::new(socket),
+        }))
+    }
+
+    fn send(&self, msg: &[u8]) -> io::Result<()> {
+        let mut socket = self.socket.lock().unwrap();
+        if socket.send(msg).is_err() {
+            // journald may have been restarted, reconnect once and retry
+            let new_socket = UnixDatagram::unbound()?;
+            new_socket.connect(&self.path)?;
+            *socket = new_socket;
+            socket.send(msg)?;
+        }
+        Ok(())
+    }
+}
+
+impl Log for JournalLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for JournalLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        let mut res = Ok(());
+        if self.enabled(record.metadata()) && !should_skip(&self.config, record) {
+            throttled(&self.config, record, |record| {
+                let mut msg = Vec::new();
+                let mut message = Vec::new();
+                let _ = write!(message, "{}", record.args());
+
+                write_field(&mut msg, "MESSAGE", &message);
+                write_field(
+                    &mut msg,
+                    "PRIORITY",
+                    priority(record.level()).to_string().as_bytes(),
+                );
+                write_field(&mut msg, "SYSLOG_IDENTIFIER", self.identifier.as_bytes());
+                write_field(&mut msg, "TARGET", record.target().as_bytes());
+                if let Some(module) = record.module_path() {
+                    write_field(&mut msg, "CODE_MODULE", module.as_bytes());
+                }
+                if let Some(file) = record.file() {
+                    write_field(&mut msg, "CODE_FILE", file.as_bytes());
+                }
+                if let Some(line) = record.line() {
+                    write_field(&mut msg, "CODE_LINE", line.to_string().as_bytes());
+                }
+                for (key, value) in key_values(record) {
+                    if let Some(name) = field_name(&key) {
+                        write_field(&mut msg, &name, value.as_bytes());
+                    }
+                }
+
+                if res.is_ok() {
+                    res = self.send(&msg);
+                }
+            });
+        }
+        res
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::test_dir;
+
+    #[test]
+    fn test_journal_logger() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = test_dir("test_journal_logger").join("socket");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let logger =
+            JournalLogger::with_socket_path(LevelFilter::Info, Config::default(), &path).unwrap();
+        logger.log(
+            &Record::builder()
+                .args(format_args!("two\nlines"))
+                .level(Level::Error)
+                .target("my_crate::db")
+                .file(Some("src/db.rs"))
+                .line(Some(42))
+                .build(),
+        );
+
+        let mut buf = [0; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        let msg = &buf[..len];
+
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&9u64.to_le_bytes());
+        expected.extend_from_slice(b"two\nlines\nPRIORITY=3\n");
+        assert!(msg.starts_with(&expected));
+        let msg = String::from_utf8_lossy(msg);
+        assert!(msg.contains("\nTARGET=my_crate::db\n"));
+        assert!(msg.ends_with("\nCODE_FILE=src/db.rs\nCODE_LINE=42\n"));
+    }
+}
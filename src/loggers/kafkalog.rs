@@ -0,0 +1,194 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the KafkaLogger Implementation
+
+use super::logging::{enabled, max_level, sending, try_log, while_sending, AtomicLevel};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use kafka::producer::{Producer, Record as KafkaRecord, RequiredAcks};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Key of the messages published by the [`KafkaLogger`], which determines their partition
+///
+/// Messages with the same key are published to the same partition,
+/// e.g. `KafkaKey::Target` keeps the records of every target in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KafkaKey {
+    /// Publish without a key, distributing the messages over all partitions
+    None,
+    /// Use the target of the record
+    Target,
+    /// Use the module path of the record
+    Module,
+    /// Use the level of the record
+    Level,
+}
+
+/// The KafkaLogger struct. Provides a Logger implementation publishing records to a Kafka topic.
+///
+/// Each record is formatted according to the `Config` and published as one message,
+/// waiting for the acknowledgement of the partition leader.
+/// The key of the messages, which determines their partition,
+/// is given as a [`KafkaKey`].
+///
+/// Publishing happens on the logging thread, so consider wrapping
+/// this logger into an [`AsyncLogger`](crate::AsyncLogger).
+/// The records logged by the `kafka` crate while publishing, which reach the loggers if `tracing`
+/// events are passed on to them, are dropped by this logger.
+pub struct KafkaLogger {
+    level: AtomicLevel,
+    config: Config,
+    topic: String,
+    key: KafkaKey,
+    producer: Mutex<Producer>,
+}
+
+impl KafkaLogger {
+    /// init function. Globally initializes the KafkaLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the bootstrap brokers, the topic and the key of the messages as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the brokers cannot be reached or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = KafkaLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     &["kafka.example.com:9092"],
+    ///     "logs",
+    ///     KafkaKey::None,
+    /// );
+    /// # }
+    /// ```
+    pub fn init<S: AsRef<str>>(
+        log_level: LevelFilter,
+        config: Config,
+        brokers: &[S],
+        topic: &str,
+        key: KafkaKey,
+    ) -> Result<LoggerHandle<KafkaLogger>, InitError> {
+        let logger = KafkaLogger::new(log_level, config, brokers, topic, key)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the bootstrap brokers, the topic and the key of the messages as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let kafka_logger = KafkaLogger::new(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     &["localhost:9092"],
+    ///     "logs",
+    ///     KafkaKey::Target,
+    /// )
+    /// .unwrap();
+    /// # }
+    /// ```
+    pub fn new<S: AsRef<str>>(
+        log_level: LevelFilter,
+        config: Config,
+        brokers: &[S],
+        topic: &str,
+        key: KafkaKey,
+    ) -> io::Result<Box<KafkaLogger>> {
+        let producer = Producer::from_hosts(
+            brokers
+                .iter()
+                .map(|broker| String::from(broker.as_ref()))
+                .collect(),
+        )
+        .with_ack_timeout(Duration::from_secs(1))
+        .with_required_acks(RequiredAcks::One)
+        .create()
+        .map_err(|err| match err {
+            kafka::Error::Io(err) => err,
+            err => io::Error::other(err.to_string()),
+        })?;
+
+        Ok(Box::new(KafkaLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            topic: String::from(topic),
+            key,
+            producer: Mutex::new(producer),
+        }))
+    }
+}
+
+impl Log for KafkaLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for KafkaLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) && !sending() {
+            let mut value = Vec::new();
+            try_log(&self.config, record, &mut value)?;
+            if value.is_empty() {
+                return Ok(());
+            }
+            while matches!(value.last(), Some(b'\n') | Some(b'\r')) {
+                value.pop();
+            }
+
+            // an empty key lets the producer distribute the messages over all partitions
+            let key = match self.key {
+                KafkaKey::None => "",
+                KafkaKey::Target => record.target(),
+                KafkaKey::Module => record.module_path().unwrap_or_default(),
+                KafkaKey::Level => record.level().as_str(),
+            };
+            let message = KafkaRecord::from_key_value(&self.topic, key, value);
+            while_sending(|| self.producer.lock().unwrap().send(&message))
+                .map_err(|err| io::Error::other(err.to_string()))?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
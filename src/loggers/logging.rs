@@ -1,10 +1,37 @@
-use crate::config::{TargetPadding, TimeFormat};
-use crate::{Config, LevelPadding, ThreadLogMode, ThreadPadding};
-use log::{LevelFilter, Record};
+use crate::config::{
+    CefHeader, FormatAlign, FormatField, FormatSegment, TargetPadding, TimeFormat, TimePrecision,
+};
+use crate::{context, stats};
+use crate::{
+    Config, FileError, Formatter, LevelPadding, LocationMode, OwnedRecord, RuntimeLevelFilters,
+    ThreadLogMode, ThreadPadding,
+};
+use log::{Level, LevelFilter, Metadata, Record};
+use std::backtrace::Backtrace;
+use std::borrow::Cow;
+#[cfg(any(feature = "http", feature = "kafka", feature = "syslog"))]
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+#[cfg(feature = "termcolor")]
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
 use std::io::{Error, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
 use std::thread;
-#[cfg(all(feature = "termcolor", feature = "ansi_term"))]
-use termcolor::Color;
+use std::time::{Duration, Instant};
+#[cfg(feature = "termcolor")]
+use termcolor::{Color, ColorChoice, ColorSpec};
+use time::format_description::well_known::Rfc3339;
+use time::macros::format_description;
+use time::OffsetDateTime;
+#[cfg(feature = "local-offset")]
+use time::UtcOffset;
 
 #[cfg(all(feature = "termcolor", feature = "ansi_term"))]
 pub fn termcolor_to_ansiterm(color: &Color) -> Option<ansi_term::Color> {
@@ -17,25 +44,431 @@ pub fn termcolor_to_ansiterm(color: &Color) -> Option<ansi_term::Color> {
         Color::Magenta => Some(ansi_term::Color::Purple),
         Color::Cyan => Some(ansi_term::Color::Cyan),
         Color::White => Some(ansi_term::Color::White),
+        Color::Ansi256(index) => Some(ansi_term::Color::Fixed(*index)),
+        Color::Rgb(r, g, b) => Some(ansi_term::Color::RGB(*r, *g, *b)),
         _ => None,
     }
 }
 
+/// Converts a `ColorSpec` into the matching style, `None` if it does not change the style
+///
+/// Colors are degraded to the ones supported by the terminal (see [`color_depth`]).
+#[cfg(all(feature = "termcolor", feature = "ansi_term"))]
+pub fn colorspec_to_ansiterm(spec: &ColorSpec) -> Option<ansi_term::Style> {
+    if spec.is_none() {
+        return None;
+    }
+    let convert = |color: &Color| termcolor_to_ansiterm(&degrade_color(*color, color_depth()));
+    let mut style = ansi_term::Style::new();
+    if let Some(fg) = spec.fg().and_then(convert) {
+        style = style.fg(fg);
+    }
+    if let Some(bg) = spec.bg().and_then(convert) {
+        style = style.on(bg);
+    }
+    if spec.bold() {
+        style = style.bold();
+    }
+    if spec.dimmed() {
+        style = style.dimmed();
+    }
+    if spec.italic() {
+        style = style.italic();
+    }
+    if spec.underline() {
+        style = style.underline();
+    }
+    Some(style)
+}
+
+/// Colors supported by the terminal
+#[cfg(feature = "termcolor")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum ColorDepth {
+    /// The basic 8 colors
+    Basic,
+    /// The 256 colors of `Color::Ansi256`
+    Ansi256,
+    /// 24-bit colors of `Color::Rgb`
+    TrueColor,
+}
+
+/// Detects the colors supported by the terminal using the `COLORTERM` and `TERM` environment variables,
+/// looked up using `var`
+///
+/// `COLORTERM=truecolor` or `COLORTERM=24bit` advertise 24-bit colors and a `TERM` like `xterm-256color`
+/// 256 colors, otherwise only the basic 8 colors are assumed.
+#[cfg(feature = "termcolor")]
+pub(crate) fn color_depth_from_env<F>(var: F) -> ColorDepth
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    let colorterm = var("COLORTERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        ColorDepth::TrueColor
+    } else if var("TERM").is_some_and(|term| term.to_string_lossy().contains("256color")) {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Basic
+    }
+}
+
+/// The colors supported by the terminal, detected once from the environment
+#[cfg(feature = "termcolor")]
+pub(crate) fn color_depth() -> ColorDepth {
+    static DEPTH: OnceLock<ColorDepth> = OnceLock::new();
+    *DEPTH.get_or_init(|| color_depth_from_env(|name| env::var_os(name)))
+}
+
+/// Replaces `color` by the closest one, that is supported with `depth`
+///
+/// 24-bit colors are mapped into the 6x6x6 cube or the grayscale ramp of the 256 colors,
+/// which in turn are mapped to the basic color matching the dominant channels.
+#[cfg(feature = "termcolor")]
+pub(crate) fn degrade_color(color: Color, depth: ColorDepth) -> Color {
+    match color {
+        Color::Rgb(r, g, b) if depth < ColorDepth::TrueColor => {
+            degrade_color(Color::Ansi256(rgb_to_ansi256(r, g, b)), depth)
+        }
+        Color::Ansi256(index) if depth < ColorDepth::Ansi256 => {
+            let basic = [
+                Color::Black,
+                Color::Red,
+                Color::Green,
+                Color::Yellow,
+                Color::Blue,
+                Color::Magenta,
+                Color::Cyan,
+                Color::White,
+            ];
+            if index < 16 {
+                // the bright variants of the basic colors
+                return basic[usize::from(index % 8)];
+            }
+            let (r, g, b) = ansi256_to_rgb(index);
+            let bit = |channel: u8, shift| usize::from(channel >= 128) << shift;
+            basic[bit(r, 0) | bit(g, 1) | bit(b, 2)]
+        }
+        color => color,
+    }
+}
+
+/// Replaces the colors of `spec`, that are not supported with `depth`, see [`degrade_color`]
+#[cfg(feature = "termcolor")]
+pub(crate) fn degrade_spec(spec: &mut ColorSpec, depth: ColorDepth) {
+    let fg = spec.fg().map(|color| degrade_color(*color, depth));
+    let bg = spec.bg().map(|color| degrade_color(*color, depth));
+    spec.set_fg(fg).set_bg(bg);
+}
+
+/// The style used for printing the level of `record`, chosen by the color callback
+/// of the config if it is set, otherwise by its theme
+#[cfg(feature = "termcolor")]
+pub(crate) fn level_style<'a>(config: &'a Config, record: &Record<'_>) -> Cow<'a, ColorSpec> {
+    match config
+        .color_callback
+        .as_ref()
+        .and_then(|callback| (callback.0)(record))
+    {
+        Some(mut spec) => {
+            degrade_spec(&mut spec, color_depth());
+            Cow::Owned(spec)
+        }
+        None => Cow::Borrowed(config.color_theme.level(record.level())),
+    }
+}
+
+/// The index of the closest color of the cube or the grayscale ramp
+#[cfg(feature = "termcolor")]
+fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    if r == g && g == b {
+        return match r {
+            0..=7 => 16,
+            247..=255 => 231,
+            _ => 232 + ((r - 3) / 10).min(23),
+        };
+    }
+    // the levels of the cube are 0, 95, 135, 175, 215 and 255
+    let level = |channel: u8| match channel {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (channel - 35) / 40,
+    };
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// The RGB value of a color of the cube or the grayscale ramp, i.e. with an index above 15
+#[cfg(feature = "termcolor")]
+fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    if index < 232 {
+        let level = |value: u8| if value == 0 { 0 } else { 55 + value * 40 };
+        let index = index - 16;
+        (level(index / 36), level(index / 6 % 6), level(index % 6))
+    } else {
+        let gray = 8 + (index - 232) * 10;
+        (gray, gray, gray)
+    }
+}
+
+/// Resolves `ColorChoice::Auto` according to the `NO_COLOR`, `CLICOLOR_FORCE`
+/// and `CLICOLOR` environment variables and the detected environment, looked up using `var`.
+///
+/// A non-empty `NO_COLOR` disables colors, otherwise `CLICOLOR_FORCE` other than `0`
+/// forces them and `CLICOLOR=0` disables them. Without any of them, colors are disabled
+/// for `TERM=dumb` and forced as ANSI escape codes on GitHub Actions, as its log viewer renders them
+/// although the output is not a terminal. On other CI systems (`CI` is set) colors are disabled,
+/// as their logs are mostly read as plain text. Containers run without a TTY
+/// need no detection, their output is not a terminal. Any other choice is returned unchanged.
+#[cfg(feature = "termcolor")]
+pub fn color_choice_from_env<F>(choice: ColorChoice, var: F) -> ColorChoice
+where
+    F: Fn(&str) -> Option<OsString>,
+{
+    if choice != ColorChoice::Auto {
+        return choice;
+    }
+    let set = |name| var(name).filter(|value| !value.is_empty());
+    if set("NO_COLOR").is_some() {
+        ColorChoice::Never
+    } else if set("CLICOLOR_FORCE").is_some_and(|value| value != "0") {
+        ColorChoice::Always
+    } else if set("CLICOLOR").is_some_and(|value| value == "0")
+        || set("TERM").is_some_and(|value| value == "dumb")
+    {
+        ColorChoice::Never
+    } else if set("GITHUB_ACTIONS").is_some_and(|value| value == "true") {
+        ColorChoice::AlwaysAnsi
+    } else if set("CI").is_some_and(|value| value != "false" && value != "0") {
+        ColorChoice::Never
+    } else {
+        ColorChoice::Auto
+    }
+}
+
 #[inline(always)]
 pub fn try_log<W>(config: &Config, record: &Record<'_>, write: &mut W) -> Result<(), Error>
 where
     W: Write + Sized,
+{
+    with_formatted(config, record, |line| write.write_all(line))
+}
+
+thread_local! {
+    /// Buffer records are formatted into, kept to reuse its allocation
+    static RECORD_BUF: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Formats `record` into a buffer of the current thread and passes the formatted bytes to `write`,
+/// unless the record is filtered.
+///
+/// The buffer is reused for every record, so formatting does not allocate, and loggers
+/// can format a record before taking the lock of their sink, only holding it while `write` runs.
+/// Multiple lines may be passed in turn, e.g. a line reporting suppressed repeats before the record.
+pub fn with_formatted<F>(config: &Config, record: &Record<'_>, mut write: F) -> Result<(), Error>
+where
+    F: FnMut(&[u8]) -> Result<(), Error>,
 {
     if should_skip(config, record) {
         return Ok(());
     }
 
+    let mut result = Ok(());
+    throttled(config, record, |record| {
+        if result.is_ok() {
+            result = RECORD_BUF.with(|buf| match buf.try_borrow_mut() {
+                Ok(mut buf) => {
+                    buf.clear();
+                    let res = write_formatted(config, record, &mut buf, &mut write);
+                    // don't keep the memory of a huge record around
+                    if buf.capacity() > 64 * 1024 {
+                        *buf = Vec::new();
+                    }
+                    res
+                }
+                // formatting the message logged another record on this thread
+                Err(_) => write_formatted(config, record, &mut Vec::new(), &mut write),
+            });
+        }
+    });
+    result
+}
+
+fn write_formatted<F>(
+    config: &Config,
+    record: &Record<'_>,
+    buf: &mut Vec<u8>,
+    write: &mut F,
+) -> Result<(), Error>
+where
+    F: FnMut(&[u8]) -> Result<(), Error>,
+{
+    write_record(config, record, buf)?;
+    match buf.is_empty() {
+        true => Ok(()),
+        false => write(buf),
+    }
+}
+
+/// Allows loggers to style fields written by `write_record_styled`, e.g. to color the level
+pub(crate) trait FieldStyle<W> {
+    /// Writes the level of `record` using `write_level`
+    fn level<F>(&self, record: &Record<'_>, write: &mut W, write_level: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut W) -> Result<(), Error>,
+    {
+        let _ = record;
+        write_level(write)
+    }
+
+    /// Writes the location of `record` using `write_location`
+    fn location<F>(
+        &self,
+        record: &Record<'_>,
+        write: &mut W,
+        write_location: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut W) -> Result<(), Error>,
+    {
+        let _ = record;
+        write_location(write)
+    }
+}
+
+/// Writes all fields as they are
+struct Plain;
+
+impl<W> FieldStyle<W> for Plain {}
+
+/// The layout used by all loggers writing records as text, unless a [`Formatter`] is set
+/// using [`ConfigBuilder::set_formatter`](crate::ConfigBuilder::set_formatter).
+///
+/// It writes a record in the Common Event Format, according to a template of the config,
+/// or by default the fields enabled by the config followed by the message and its key-values.
+/// A custom formatter may use it to wrap the default layout.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use log::Record;
+/// # use std::io::Write;
+/// # fn main() {
+/// let layout = Config::default();
+/// let config = ConfigBuilder::new()
+///     .set_formatter(move |record: &Record<'_>, buf: &mut dyn Write| {
+///         buf.write_all(b"app | ")?;
+///         DefaultFormatter::new(&layout).format(record, buf)
+///     })
+///     .build();
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultFormatter<'a> {
+    config: &'a Config,
+}
+
+impl<'a> DefaultFormatter<'a> {
+    /// Creates the default layout of `config`
+    pub fn new(config: &'a Config) -> DefaultFormatter<'a> {
+        DefaultFormatter { config }
+    }
+}
+
+impl Formatter for DefaultFormatter<'_> {
+    fn format(&self, record: &Record<'_>, mut buf: &mut dyn Write) -> Result<(), Error> {
+        write_layout(self.config, record, &mut buf, &Plain)
+    }
+}
+
+/// Writes a record using the formatter of the config, followed by the backtrace and the line ending
+#[inline(always)]
+pub(crate) fn write_record<W>(
+    config: &Config,
+    record: &Record<'_>,
+    write: &mut W,
+) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    let default = DefaultFormatter::new(config);
+    let formatter: &dyn Formatter = match &config.formatter {
+        Some(formatter) => &*formatter.0,
+        None => &default,
+    };
+    formatter.format(record, write)?;
+    write_trailer(config, record, write)
+}
+
+/// Same as `write_record`, but lets `style` style the level and the location of the default layout
+#[cfg(feature = "termcolor")]
+#[inline(always)]
+pub(crate) fn write_record_styled<W, S>(
+    config: &Config,
+    record: &Record<'_>,
+    write: &mut W,
+    style: &S,
+) -> Result<(), Error>
+where
+    W: Write + Sized,
+    S: FieldStyle<W>,
+{
+    if config.formatter.is_some() {
+        return write_record(config, record, write);
+    }
+    write_layout(config, record, write, style)?;
+    write_trailer(config, record, write)
+}
+
+/// Appends the backtrace, unless the record is written as a CEF event, and the line ending
+fn write_trailer<W>(config: &Config, record: &Record<'_>, write: &mut W) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    if config.cef.is_none() || config.formatter.is_some() {
+        write_backtrace(record, write, config)?;
+    }
+    write!(write, "{}", config.line_ending)
+}
+
+/// Writes the default layout of a record, without the backtrace and the line ending
+#[inline(always)]
+fn write_layout<W, S>(
+    config: &Config,
+    record: &Record<'_>,
+    write: &mut W,
+    style: &S,
+) -> Result<(), Error>
+where
+    W: Write + Sized,
+    S: FieldStyle<W>,
+{
+    if let Some(header) = &config.cef {
+        return write_cef(header, record, write, config);
+    }
+
+    if let Some(format) = config.format_of(record.level()) {
+        return write_format(format, record, write, config, |write, level| {
+            style.level(record, write, |write| {
+                write_level_text(record, level, write, config)
+            })
+        });
+    }
+
     if config.time <= record.level() && config.time != LevelFilter::Off {
         write_time(write, config)?;
     }
 
+    if config.hostname <= record.level() && config.hostname != LevelFilter::Off {
+        write_hostname(write, config)?;
+    }
+
     if config.level <= record.level() && config.level != LevelFilter::Off {
-        write_level(record, write, config)?;
+        style.level(record, write, |write| write_level(record, write, config))?;
+    }
+
+    if config.pid <= record.level() && config.pid != LevelFilter::Off {
+        write_pid(write)?;
     }
 
     if config.thread <= record.level() && config.thread != LevelFilter::Off {
@@ -54,240 +487,1481 @@ where
     }
 
     if config.location <= record.level() && config.location != LevelFilter::Off {
-        write_location(record, write)?;
+        style.location(record, write, |write| write_location(record, write, config))?;
     }
 
     if config.module <= record.level() && config.module != LevelFilter::Off {
         write_module(record, write)?;
     }
 
-    #[cfg(feature = "paris")]
-    return write_args(
-        record,
-        write,
-        config.enable_paris_formatting,
-        &config.line_ending,
-    );
-    #[cfg(not(feature = "paris"))]
-    return write_args(record, write, &config.line_ending);
+    write_args(record, write, config)
 }
 
-#[inline(always)]
-pub fn write_time<W>(write: &mut W, config: &Config) -> Result<(), Error>
+/// Writes a record according to a format template set via `ConfigBuilder::set_format`.
+///
+/// `write_level` is used to write the (already padded) level,
+/// allowing loggers to apply their own styling.
+pub fn write_format<W, F>(
+    format: &[FormatSegment],
+    record: &Record<'_>,
+    write: &mut W,
+    config: &Config,
+    mut write_level: F,
+) -> Result<(), Error>
 where
     W: Write + Sized,
+    F: FnMut(&mut W, fmt::Arguments<'_>) -> Result<(), Error>,
 {
-    use time::error::Format;
-    use time::format_description::well_known::*;
+    for segment in format {
+        match *segment {
+            FormatSegment::Literal(ref text) => write.write_all(text.as_bytes())?,
+            FormatSegment::Field {
+                field: FormatField::Level,
+                align,
+                width,
+                max,
+            } => write_level(
+                write,
+                format_args!(
+                    "{}",
+                    Padded::new(level_label(config, record.level()), align, width, max)
+                ),
+            )?,
+            FormatSegment::Field {
+                field,
+                width: 0,
+                max: None,
+                ..
+            } => write_field(field, record, write, config)?,
+            FormatSegment::Field {
+                field,
+                align,
+                width,
+                max,
+            } => with_field_buf(|buf| {
+                write_field(field, record, buf, config)?;
+                let value = String::from_utf8_lossy(buf);
+                write!(write, "{}", Padded::new(&value, align, width, max))
+            })?,
+        }
+    }
+    Ok(())
+}
 
-    let time = time::OffsetDateTime::now_utc().to_offset(config.time_offset);
-    let res = match config.time_format {
-        TimeFormat::Rfc2822 => time.format_into(write, &Rfc2822),
-        TimeFormat::Rfc3339 => time.format_into(write, &Rfc3339),
-        TimeFormat::Custom(format) => time.format_into(write, &format),
-    };
-    match res {
-        Err(Format::StdIo(err)) => return Err(err),
-        Err(err) => panic!("Invalid time format: {}", err),
-        _ => {}
-    };
+/// Maps a `Level` to a CEF severity, which ranges from `0` (lowest) to `10` (highest)
+fn cef_severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 8,
+        Level::Warn => 6,
+        Level::Info => 3,
+        Level::Debug => 1,
+        Level::Trace => 0,
+    }
+}
 
-    write!(write, " ")?;
-    Ok(())
+/// The characters a value is escaped for, see [`Escaped`]
+#[derive(Clone, Copy)]
+enum Escaping {
+    /// The content of a JSON string
+    Json,
+    /// A value of the CEF header, which may not contain line breaks
+    CefHeader,
+    /// A value of the CEF extension
+    CefExtension,
 }
 
-#[inline(always)]
-pub fn write_level<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
-where
-    W: Write + Sized,
-{
-    #[cfg(all(feature = "termcolor", feature = "ansi_term"))]
-    let color = match &config.level_color[record.level() as usize] {
-        Some(termcolor) => {
-            if config.write_log_enable_colors {
-                termcolor_to_ansiterm(termcolor)
-            } else {
-                None
-            }
+impl Escaping {
+    /// The replacement of `c`, if it needs to be escaped, an empty one standing for `\u00XX`
+    fn of(self, c: char) -> Option<&'static str> {
+        match (self, c) {
+            (_, '\\') => Some("\\\\"),
+            (Escaping::Json, '"') => Some("\\\""),
+            (Escaping::Json, '\n') => Some("\\n"),
+            (Escaping::Json, '\r') => Some("\\r"),
+            (Escaping::Json, '\t') => Some("\\t"),
+            (Escaping::Json, c) if (c as u32) < 0x20 => Some(""),
+            (Escaping::CefHeader, '|') => Some("\\|"),
+            (Escaping::CefHeader, '\r') | (Escaping::CefHeader, '\n') => Some(" "),
+            (Escaping::CefExtension, '=') => Some("\\="),
+            (Escaping::CefExtension, '\r') => Some("\\r"),
+            (Escaping::CefExtension, '\n') => Some("\\n"),
+            _ => None,
         }
-        None => None,
-    };
+    }
+}
 
-    let level = match config.level_padding {
-        LevelPadding::Left => format!("[{: >5}]", record.level()),
-        LevelPadding::Right => format!("[{: <5}]", record.level()),
-        LevelPadding::Off => format!("[{}]", record.level()),
-    };
+/// Displays a value escaped while it is written, so it does not need to be formatted into a string first
+struct Escaped<T>(Escaping, T);
 
-    #[cfg(all(feature = "termcolor", feature = "ansi_term"))]
-    match color {
-        Some(c) => write!(write, "{} ", c.paint(level))?,
-        None => write!(write, "{} ", level)?,
-    };
+impl<T: fmt::Display> fmt::Display for Escaped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        struct Escaper<'a, 'b>(Escaping, &'a mut fmt::Formatter<'b>);
 
-    #[cfg(not(feature = "ansi_term"))]
-    write!(write, "{} ", level)?;
+        impl fmt::Write for Escaper<'_, '_> {
+            fn write_str(&mut self, value: &str) -> fmt::Result {
+                let mut start = 0;
+                for (i, c) in value.char_indices() {
+                    let escaped = match self.0.of(c) {
+                        Some(escaped) => escaped,
+                        None => continue,
+                    };
+                    self.1.write_str(&value[start..i])?;
+                    if escaped.is_empty() {
+                        write!(self.1, "\\u{:04x}", c as u32)?;
+                    } else {
+                        self.1.write_str(escaped)?;
+                    }
+                    start = i + c.len_utf8();
+                }
+                self.1.write_str(&value[start..])
+            }
+        }
 
-    Ok(())
+        fmt::Write::write_fmt(&mut Escaper(self.0, f), format_args!("{}", self.1))
+    }
 }
 
-#[inline(always)]
-pub fn write_target<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+/// Displays the alphanumeric characters of a value, which are the only ones allowed in CEF keys
+struct Alphanumeric<'a>(&'a str);
+
+impl fmt::Display for Alphanumeric<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in self.0.split(|c: char| !c.is_ascii_alphanumeric()) {
+            f.write_str(part)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a record in the Common Event Format, see `ConfigBuilder::set_cef_format`
+pub(crate) fn write_cef<W>(
+    header: &CefHeader,
+    record: &Record<'_>,
+    write: &mut W,
+    config: &Config,
+) -> Result<(), Error>
 where
     W: Write + Sized,
 {
-    // dbg!(&config.target_padding);
-    match config.target_padding {
-        TargetPadding::Left(pad) => {
-            write!(
-                write,
-                "{target:>pad$}: ",
-                pad = pad,
-                target = record.target()
-            )?;
-        }
-        TargetPadding::Right(pad) => {
+    write!(
+        write,
+        "CEF:0|{}|{}|{}|{}|{}|{}|rt={} dvchost={} dvcpid={}",
+        Escaped(Escaping::CefHeader, &header.vendor),
+        Escaped(Escaping::CefHeader, &header.product),
+        Escaped(Escaping::CefHeader, &header.version),
+        Escaped(Escaping::CefHeader, record.target()),
+        Escaped(Escaping::CefHeader, record.args()),
+        cef_severity(record.level()),
+        now().unix_timestamp_nanos() / 1_000_000,
+        Escaped(Escaping::CefExtension, hostname(config)),
+        process::id(),
+    )?;
+    for (key, value) in key_values(record) {
+        if key.chars().any(|c| c.is_ascii_alphanumeric()) {
             write!(
                 write,
-                "{target:<pad$}: ",
-                pad = pad,
-                target = record.target()
+                " {}={}",
+                Alphanumeric(&key),
+                Escaped(Escaping::CefExtension, &value)
             )?;
         }
-        TargetPadding::Off => {
-            write!(write, "{}: ", record.target())?;
-        }
     }
-
     Ok(())
 }
 
-#[inline(always)]
-pub fn write_location<W>(record: &Record<'_>, write: &mut W) -> Result<(), Error>
+fn write_field<W>(
+    field: FormatField,
+    record: &Record<'_>,
+    write: &mut W,
+    config: &Config,
+) -> Result<(), Error>
 where
     W: Write + Sized,
 {
-    let file = record.file().unwrap_or("<unknown>");
-    if let Some(line) = record.line() {
-        write!(write, "[{}:{}] ", file, line)?;
-    } else {
-        write!(write, "[{}:<unknown>] ", file)?;
+    match field {
+        FormatField::Time => format_time(write, config),
+        FormatField::Hostname => write!(write, "{}", hostname(config)),
+        FormatField::Level => write!(write, "{}", level_label(config, record.level())),
+        FormatField::Thread => with_thread_name(|name| match (config.thread_log_mode, name) {
+            (ThreadLogMode::Names, Some(name)) | (ThreadLogMode::Both, Some(name)) => {
+                write!(write, "{}", name)
+            }
+            (ThreadLogMode::Names, None) => Ok(()),
+            _ => with_thread_id(|id| write!(write, "{}", id)),
+        }),
+        FormatField::Pid => write!(write, "{}", process::id()),
+        FormatField::Target => write!(write, "{}", record.target()),
+        FormatField::Location => match record.line() {
+            Some(line) => write!(write, "{}:{}", location_file(record, config), line),
+            None => write!(write, "{}:<unknown>", location_file(record, config)),
+        },
+        FormatField::File => write!(write, "{}", location_file(record, config)),
+        FormatField::Line => match record.line() {
+            Some(line) => write!(write, "{}", line),
+            None => write!(write, "<unknown>"),
+        },
+        FormatField::Module => write!(write, "{}", record.module_path().unwrap_or("<unknown>")),
+        FormatField::Message => write_message(record, write, config),
+        FormatField::Args => {
+            write_message(record, write, config)?;
+            write_key_values(record, write, " ")
+        }
+        FormatField::KeyValues => {
+            for (i, (key, value)) in key_values(record).iter().enumerate() {
+                write_key_value(write, if i == 0 { "" } else { " " }, key, value)?;
+            }
+            Ok(())
+        }
     }
-    Ok(())
 }
 
-#[inline(always)]
-pub fn write_module<W>(record: &Record<'_>, write: &mut W) -> Result<(), Error>
-where
-    W: Write + Sized,
-{
-    let module = record.module_path().unwrap_or("<unknown>");
-    write!(write, "[{}] ", module)?;
-    Ok(())
+thread_local! {
+    /// Buffer fields are formatted into before they are padded, kept to reuse its allocation
+    static FIELD_BUF: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
 }
 
-pub fn write_thread_name<W>(write: &mut W, config: &Config) -> Result<(), Error>
-where
-    W: Write + Sized,
-{
-    if let Some(name) = thread::current().name() {
-        match config.thread_padding {
-            ThreadPadding::Left { 0: qty } => {
-                write!(write, "({name:>0$}) ", qty, name = name)?;
-            }
-            ThreadPadding::Right { 0: qty } => {
-                write!(write, "({name:<0$}) ", qty, name = name)?;
-            }
-            ThreadPadding::Off => {
-                write!(write, "({}) ", name)?;
-            }
+/// Calls `f` with an empty buffer of the current thread
+fn with_field_buf<R>(f: impl FnOnce(&mut Vec<u8>) -> R) -> R {
+    FIELD_BUF.with(|buf| match buf.try_borrow_mut() {
+        Ok(mut buf) => {
+            buf.clear();
+            f(&mut buf)
         }
-    } else if config.thread_log_mode == ThreadLogMode::Both {
-        write_thread_id(write, config)?;
-    }
+        // formatting the field logged another record on this thread
+        Err(_) => f(&mut Vec::new()),
+    })
+}
 
-    Ok(())
+/// Displays a field aligned to `width` characters, and shortened to `max` characters
+struct Padded<'a> {
+    value: &'a str,
+    align: FormatAlign,
+    width: usize,
+    max: Option<usize>,
 }
 
-pub fn write_thread_id<W>(write: &mut W, config: &Config) -> Result<(), Error>
-where
-    W: Write + Sized,
-{
-    let id = format!("{:?}", thread::current().id());
-    let id = id.replace("ThreadId(", "");
-    let id = id.replace(")", "");
-    match config.thread_padding {
-        ThreadPadding::Left { 0: qty } => {
-            write!(write, "({id:>0$}) ", qty, id = id)?;
-        }
-        ThreadPadding::Right { 0: qty } => {
-            write!(write, "({id:<0$}) ", qty, id = id)?;
-        }
-        ThreadPadding::Off => {
-            write!(write, "({}) ", id)?;
+impl<'a> Padded<'a> {
+    fn new(value: &'a str, align: FormatAlign, width: usize, max: Option<usize>) -> Padded<'a> {
+        Padded {
+            value,
+            align,
+            width,
+            max,
         }
     }
-    Ok(())
+}
+
+impl fmt::Display for Padded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let len = self.value.chars().count();
+        let (ellipsis, value, len) = match self.max {
+            Some(max) if len > max => {
+                // keep the end, which is the most specific part of targets and locations
+                let start = self
+                    .value
+                    .char_indices()
+                    .nth(len + 1 - max)
+                    .map_or(self.value.len(), |(i, _)| i);
+                let ellipsis = if max == 0 { "" } else { "…" };
+                (ellipsis, &self.value[start..], max)
+            }
+            _ => ("", self.value, len),
+        };
+        let fill = self.width.saturating_sub(len);
+        let before = match self.align {
+            FormatAlign::Left => 0,
+            FormatAlign::Right => fill,
+            FormatAlign::Center => fill / 2,
+        };
+        write!(
+            f,
+            "{:before$}{}{}{:after$}",
+            "",
+            ellipsis,
+            value,
+            "",
+            before = before,
+            after = fill - before
+        )
+    }
 }
 
 #[inline(always)]
-#[cfg(feature = "paris")]
-pub fn write_args<W>(
-    record: &Record<'_>,
-    write: &mut W,
-    with_colors: bool,
-    line_ending: &str,
-) -> Result<(), Error>
+pub fn write_time<W>(write: &mut W, config: &Config) -> Result<(), Error>
 where
     W: Write + Sized,
 {
-    write!(
-        write,
-        "{}{}",
-        crate::__private::paris::formatter::format_string(
-            format!("{}", record.args()),
-            with_colors
-        ),
-        line_ending
-    )?;
+    format_time(write, config)?;
+    write!(write, " ")?;
     Ok(())
 }
 
 #[inline(always)]
-#[cfg(not(feature = "paris"))]
-pub fn write_args<W>(record: &Record<'_>, write: &mut W, line_ending: &str) -> Result<(), Error>
+fn format_time<W>(write: &mut W, config: &Config) -> Result<(), Error>
 where
     W: Write + Sized,
 {
-    write!(write, "{}{}", record.args(), line_ending)?;
-    Ok(())
-}
+    use time::error::Format;
+    use time::format_description::well_known::*;
 
-#[inline(always)]
-pub fn should_skip(config: &Config, record: &Record<'_>) -> bool {
-    // If a module path and allowed list are available
-    match (record.target(), &*config.filter_allow) {
-        (path, allowed) if !allowed.is_empty() => {
-            // Check that the module path matches at least one allow filter
-            if !allowed.iter().any(|v| path.starts_with(&**v)) {
-                // If not, skip any further writing
-                return true;
+    let time = to_config_offset(now(), config);
+    let res = match &config.time_format {
+        TimeFormat::Rfc2822 => time.format_into(write, &Rfc2822),
+        TimeFormat::Rfc3339 => time.format_into(write, &Rfc3339),
+        TimeFormat::Rfc3339Precision(precision) => format_rfc3339(write, time, *precision),
+        TimeFormat::Custom(format) => time.format_into(write, format),
+        TimeFormat::Owned(format) => time.format_into(write, format),
+    };
+    match res {
+        Err(Format::StdIo(err)) => return Err(err),
+        Err(err) => panic!("Invalid time format: {}", err),
+        _ => {}
+    };
+    Ok(())
+}
+
+/// Writes `time` according to RFC 3339 with a fixed amount of fractional digits
+pub fn format_rfc3339<W>(
+    write: &mut W,
+    time: OffsetDateTime,
+    precision: TimePrecision,
+) -> Result<usize, time::error::Format>
+where
+    W: Write + Sized,
+{
+    let mut len = time.format_into(
+        write,
+        format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]"),
+    )?;
+    len += match precision {
+        TimePrecision::Seconds => 0,
+        TimePrecision::Millis => {
+            time.format_into(write, format_description!(".[subsecond digits:3]"))?
+        }
+        TimePrecision::Micros => {
+            time.format_into(write, format_description!(".[subsecond digits:6]"))?
+        }
+        TimePrecision::Nanos => {
+            time.format_into(write, format_description!(".[subsecond digits:9]"))?
+        }
+    };
+    len += if time.offset().is_utc() {
+        write.write_all(b"Z")?;
+        1
+    } else {
+        time.format_into(
+            write,
+            format_description!("[offset_hour sign:mandatory]:[offset_minute]"),
+        )?
+    };
+    Ok(len)
+}
+
+/// The name of the host set via `ConfigBuilder::set_hostname`,
+/// otherwise the one of the local host, which is looked up once
+pub fn hostname(config: &Config) -> &str {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+
+    config.hostname_override.as_deref().unwrap_or_else(|| {
+        HOSTNAME.get_or_init(|| {
+            env::var("HOSTNAME")
+                .ok()
+                .or_else(|| fs::read_to_string("/etc/hostname").ok())
+                .or_else(|| env::var("COMPUTERNAME").ok())
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| String::from("localhost"))
+        })
+    })
+}
+
+#[inline(always)]
+pub fn write_hostname<W>(write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    write!(write, "{} ", hostname(config))?;
+    Ok(())
+}
+
+/// The label written for `level`, see `ConfigBuilder::set_level_label`
+#[inline(always)]
+pub fn level_label(config: &Config, level: Level) -> &str {
+    &config.level_labels[level as usize - 1]
+}
+
+/// The length of the longest level label, which padded levels are padded to
+pub fn level_width(config: &Config) -> usize {
+    config
+        .level_labels
+        .iter()
+        .map(|label| label.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+#[inline(always)]
+pub fn write_level<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    let label = level_label(config, record.level());
+    let width = level_width(config);
+    match config.level_padding {
+        LevelPadding::Left => write_level_text(
+            record,
+            format_args!("[{: >1$}]", label, width),
+            write,
+            config,
+        )?,
+        LevelPadding::Right => write_level_text(
+            record,
+            format_args!("[{: <1$}]", label, width),
+            write,
+            config,
+        )?,
+        LevelPadding::Off => write_level_text(record, format_args!("[{}]", label), write, config)?,
+    }
+    write!(write, " ")?;
+
+    Ok(())
+}
+
+/// Writes an already formatted level, colored if `write_log_enable_colors` is set
+#[inline(always)]
+#[cfg_attr(
+    not(all(feature = "termcolor", feature = "ansi_term")),
+    allow(unused_variables)
+)]
+pub fn write_level_text<W>(
+    record: &Record<'_>,
+    level: fmt::Arguments<'_>,
+    write: &mut W,
+    config: &Config,
+) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    #[cfg(all(feature = "termcolor", feature = "ansi_term"))]
+    if config.write_log_enable_colors {
+        if let Some(style) = colorspec_to_ansiterm(&level_style(config, record)) {
+            return write!(write, "{}{}{}", style.prefix(), level, style.suffix());
+        }
+    }
+
+    write!(write, "{}", level)
+}
+
+#[inline(always)]
+pub fn write_pid<W>(write: &mut W) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    write!(write, "[{}] ", process::id())?;
+    Ok(())
+}
+
+#[inline(always)]
+pub fn write_target<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    // dbg!(&config.target_padding);
+    match config.target_padding {
+        TargetPadding::Left(pad) => {
+            write!(
+                write,
+                "{target:>pad$}: ",
+                pad = pad,
+                target = record.target()
+            )?;
+        }
+        TargetPadding::Right(pad) => {
+            write!(
+                write,
+                "{target:<pad$}: ",
+                pad = pad,
+                target = record.target()
+            )?;
+        }
+        TargetPadding::Off => {
+            write!(write, "{}: ", record.target())?;
+        }
+    }
+
+    Ok(())
+}
+
+#[inline(always)]
+pub fn write_location<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    let file = location_file(record, config);
+    if let Some(line) = record.line() {
+        write!(write, "[{}:{}] ", file, line)?;
+    } else {
+        write!(write, "[{}:<unknown>] ", file)?;
+    }
+    Ok(())
+}
+
+/// The file of `record`, shortened according to the location mode of the config
+pub fn location_file<'a>(record: &Record<'a>, config: &Config) -> &'a str {
+    let file = match record.file() {
+        Some(file) => file,
+        None => return "<unknown>",
+    };
+    let is_separator = |c: char| c == '/' || c == '\\';
+    match config.location_mode {
+        LocationMode::Full => file,
+        LocationMode::FileName => file.rsplit(is_separator).next().unwrap_or(file),
+        LocationMode::Crate if !Path::new(file).is_absolute() => file,
+        LocationMode::Crate => {
+            let mut end = file.len();
+            for (pos, _) in file.rmatch_indices(is_separator) {
+                if &file[pos + 1..end] == "src" {
+                    // keep the directory containing `src`, which is the one of the crate
+                    return match file[..pos].rfind(is_separator) {
+                        Some(start) => &file[start + 1..],
+                        None => file,
+                    };
+                }
+                end = pos;
+            }
+            file
+        }
+    }
+}
+
+#[inline(always)]
+pub fn write_module<W>(record: &Record<'_>, write: &mut W) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    let module = record.module_path().unwrap_or("<unknown>");
+    write!(write, "[{}] ", module)?;
+    Ok(())
+}
+
+pub fn write_thread_name<W>(write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    with_thread_name(|name| {
+        if let Some(name) = name {
+            match config.thread_padding {
+                ThreadPadding::Left { 0: qty } => {
+                    write!(write, "({name:>0$}) ", qty, name = name)?;
+                }
+                ThreadPadding::Right { 0: qty } => {
+                    write!(write, "({name:<0$}) ", qty, name = name)?;
+                }
+                ThreadPadding::Off => {
+                    write!(write, "({}) ", name)?;
+                }
+            }
+        } else if config.thread_log_mode == ThreadLogMode::Both {
+            write_thread_id(write, config)?;
+        }
+
+        Ok(())
+    })
+}
+
+pub fn write_thread_id<W>(write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    with_thread_id(|id| match config.thread_padding {
+        ThreadPadding::Left { 0: qty } => write!(write, "({id:>0$}) ", qty, id = id),
+        ThreadPadding::Right { 0: qty } => write!(write, "({id:<0$}) ", qty, id = id),
+        ThreadPadding::Off => write!(write, "({}) ", id),
+    })
+}
+
+#[inline(always)]
+pub fn write_args<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    write_message(record, write, config)?;
+    write_key_values(record, write, " ")
+}
+
+/// Writes the message of a record, truncated to the length set by `ConfigBuilder::set_max_message_len`
+/// and starting every continuation line with the prefix set by `ConfigBuilder::set_continuation_prefix`
+fn write_message<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    #[cfg(feature = "paris")]
+    let mut message = crate::__private::paris::formatter::format_string(
+        format!("{}", record.args()),
+        config.enable_paris_formatting,
+    );
+    #[cfg(not(feature = "paris"))]
+    let mut message = match (&config.continuation_prefix, config.max_message_len) {
+        (None, None) => return write!(write, "{}", record.args()),
+        _ => record.args().to_string(),
+    };
+
+    if let Some(max) = config.max_message_len {
+        if message.len() > max {
+            let len = message.len();
+            let mut end = max;
+            while !message.is_char_boundary(end) {
+                end -= 1;
+            }
+            message.truncate(end);
+            message.push_str(&format!("… ({} bytes)", len));
+        }
+    }
+
+    match &config.continuation_prefix {
+        Some(prefix) => {
+            let mut lines = message.split('\n');
+            write.write_all(lines.next().unwrap_or_default().as_bytes())?;
+            for line in lines {
+                write!(write, "\n{}{}", prefix, line)?;
+            }
+            Ok(())
+        }
+        None => write.write_all(message.as_bytes()),
+    }
+}
+
+/// Appends the backtrace of the call site of the record, if enabled for the level of the record
+///
+/// The backtrace captured with the origin of the record is used, if there is one,
+/// otherwise the backtrace of the current thread is captured.
+#[inline(always)]
+pub fn write_backtrace<W>(record: &Record<'_>, write: &mut W, config: &Config) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    if record.level() <= config.backtrace {
+        let captured = ORIGIN.with(|cell| {
+            let origin = cell.borrow();
+            let backtrace = origin.as_ref().and_then(|origin| origin.backtrace.as_ref());
+            backtrace.map(Backtrace::to_string)
+        });
+        let backtrace = captured.unwrap_or_else(|| Backtrace::force_capture().to_string());
+        write!(write, "\nstack backtrace:\n{}", backtrace.trim_end())?;
+    }
+    Ok(())
+}
+
+/// Returns the key-values of a record followed by the ones of the diagnostic context
+/// of the thread the record originates from (see [`crate::context`])
+pub fn key_values(record: &Record<'_>) -> Vec<(String, String)> {
+    let mut key_values = record_key_values(record);
+    ORIGIN.with(|cell| match cell.borrow().as_ref() {
+        Some(origin) => key_values.extend(origin.context.iter().cloned()),
+        None => key_values.extend(context::entries()),
+    });
+    key_values
+}
+
+/// Returns the key-values attached to a record, keys and values converted to strings
+#[cfg(feature = "kv")]
+pub fn record_key_values(record: &Record<'_>) -> Vec<(String, String)> {
+    use log::kv::{Error as KvError, Key, Value, VisitSource};
+
+    struct Collect(Vec<(String, String)>);
+
+    impl<'kvs> VisitSource<'kvs> for Collect {
+        fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), KvError> {
+            self.0.push((key.to_string(), value.to_string()));
+            Ok(())
+        }
+    }
+
+    let mut collect = Collect(Vec::new());
+    let _ = record.key_values().visit(&mut collect);
+    collect.0
+}
+
+/// Returns the key-values attached to a record, these are only available with the `kv` feature
+#[cfg(not(feature = "kv"))]
+pub fn record_key_values(_record: &Record<'_>) -> Vec<(String, String)> {
+    Vec::new()
+}
+
+/// Writes the key-values of a record as `key=value` pairs, each preceded by `separator`.
+///
+/// Values, that are empty or contain whitespace, quotes or `=`, are quoted.
+pub fn write_key_values<W>(record: &Record<'_>, write: &mut W, separator: &str) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    for (key, value) in key_values(record) {
+        write_key_value(write, separator, &key, &value)?;
+    }
+    Ok(())
+}
+
+fn write_key_value<W>(write: &mut W, separator: &str, key: &str, value: &str) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    if value.is_empty()
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '=')
+    {
+        write!(write, "{}{}={:?}", separator, key, value)
+    } else {
+        write!(write, "{}{}={}", separator, key, value)
+    }
+}
+
+/// Writes a record according to a format template, escaping the values of the fields
+/// as the content of a JSON string, while literal parts are written as they are
+#[cfg(feature = "http")]
+pub fn write_json_template<W>(
+    format: &[FormatSegment],
+    record: &Record<'_>,
+    write: &mut W,
+    config: &Config,
+) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    for segment in format {
+        match *segment {
+            FormatSegment::Literal(ref text) => write.write_all(text.as_bytes())?,
+            FormatSegment::Field {
+                field,
+                align,
+                width,
+                max,
+            } => with_field_buf(|buf| {
+                write_field(field, record, buf, config)?;
+                let value = String::from_utf8_lossy(buf);
+                let padded = Padded::new(&value, align, width, max);
+                write!(write, "{}", Escaped(Escaping::Json, padded))
+            })?,
+        }
+    }
+    Ok(())
+}
+
+/// Writes a record as a JSON object on a single line, with the time written as given
+pub fn write_json<W>(
+    record: &Record<'_>,
+    json: &mut W,
+    config: &Config,
+    time: OffsetDateTime,
+) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    json.write_all(b"{\"time\":")?;
+    write_json_str(json, &time.format(&Rfc3339).unwrap_or_default())?;
+    if config.hostname <= record.level() && config.hostname != LevelFilter::Off {
+        json.write_all(b",\"host\":")?;
+        write_json_str(json, hostname(config))?;
+    }
+    json.write_all(b",\"level\":")?;
+    write_json_str(json, record.level().as_str())?;
+    json.write_all(b",\"target\":")?;
+    write_json_str(json, record.target())?;
+    if let Some(module) = record.module_path() {
+        json.write_all(b",\"module\":")?;
+        write_json_str(json, module)?;
+    }
+    if let Some(file) = record.file() {
+        json.write_all(b",\"file\":")?;
+        write_json_str(json, file)?;
+    }
+    if let Some(line) = record.line() {
+        write!(json, ",\"line\":{}", line)?;
+    }
+    json.write_all(b",\"message\":")?;
+    write!(json, "\"{}\"", Escaped(Escaping::Json, record.args()))?;
+    let key_values = key_values(record);
+    if !key_values.is_empty() {
+        json.write_all(b",\"kv\":{")?;
+        for (i, (key, value)) in key_values.iter().enumerate() {
+            if i > 0 {
+                json.write_all(b",")?;
             }
+            write_json_str(json, key)?;
+            json.write_all(b":")?;
+            write_json_str(json, value)?;
+        }
+        json.write_all(b"}")?;
+    }
+    json.write_all(b"}")
+}
+
+/// Writes `value` as a quoted JSON string, escaping it as necessary
+pub fn write_json_str<W>(write: &mut W, value: &str) -> Result<(), Error>
+where
+    W: Write + Sized,
+{
+    write!(write, "\"{}\"", Escaped(Escaping::Json, value))
+}
+
+/// Time, thread, diagnostic context and backtrace a record originates from,
+/// used when a record is written on behalf of another thread (see `AsyncLogger`)
+/// or by multiple loggers (see `CombinedLogger`)
+pub struct Origin {
+    time: OffsetDateTime,
+    thread_name: Option<String>,
+    thread_id: String,
+    context: Vec<(String, String)>,
+    backtrace: Option<Backtrace>,
+}
+
+impl Origin {
+    /// Captures the current time, thread and its diagnostic context,
+    /// as well as a backtrace, if enabled for the level of `record` by `backtrace`
+    pub fn capture(record: &Record<'_>, backtrace: LevelFilter) -> Origin {
+        let thread = thread::current();
+        Origin {
+            time: OffsetDateTime::now_utc(),
+            thread_name: thread.name().map(str::to_string),
+            thread_id: with_thread_id(str::to_string),
+            context: context::entries(),
+            backtrace: (record.level() <= backtrace).then(Backtrace::force_capture),
+        }
+    }
+}
+
+thread_local! {
+    static ORIGIN: RefCell<Option<Origin>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with the time and thread written by the loggers taken from `origin`
+pub fn with_origin<R>(origin: Origin, f: impl FnOnce() -> R) -> R {
+    ORIGIN.with(|cell| cell.replace(Some(origin)));
+    let res = f();
+    ORIGIN.with(|cell| cell.take());
+    res
+}
+
+/// Runs `f` logging `record` with a backtrace captured beforehand, if it is enabled for the level
+/// of the record by `backtrace` and the record does not have an origin already.
+/// So the backtrace is captured once, instead of once by every logger writing the record.
+pub fn with_backtrace<R>(record: &Record<'_>, backtrace: LevelFilter, f: impl FnOnce() -> R) -> R {
+    if record.level() <= backtrace && ORIGIN.with(|cell| cell.borrow().is_none()) {
+        with_origin(Origin::capture(record, backtrace), f)
+    } else {
+        f()
+    }
+}
+
+/// The time of the record currently being logged
+pub fn now() -> OffsetDateTime {
+    ORIGIN
+        .with(|cell| cell.borrow().as_ref().map(|origin| origin.time))
+        .unwrap_or_else(OffsetDateTime::now_utc)
+}
+
+/// Converts `time` into the offset, times are written in according to the config
+pub fn to_config_offset(time: OffsetDateTime, config: &Config) -> OffsetDateTime {
+    #[cfg(feature = "local-offset")]
+    if config.time_local {
+        let offset = UtcOffset::local_offset_at(time).unwrap_or(config.time_offset);
+        return time.to_offset(offset);
+    }
+    time.to_offset(config.time_offset)
+}
+
+/// Calls `f` with the name of the thread, the record currently being logged originates from
+fn with_thread_name<R>(f: impl FnOnce(Option<&str>) -> R) -> R {
+    ORIGIN.with(|cell| match &*cell.borrow() {
+        Some(origin) => f(origin.thread_name.as_deref()),
+        None => f(thread::current().name()),
+    })
+}
+
+thread_local! {
+    /// The id of the current thread, formatted once
+    static THREAD_ID: String = {
+        let id = format!("{:?}", thread::current().id());
+        id.replace("ThreadId(", "").replace(')', "")
+    };
+}
+
+/// Calls `f` with the id of the thread, the record currently being logged originates from
+fn with_thread_id<R>(f: impl FnOnce(&str) -> R) -> R {
+    ORIGIN.with(|cell| match &*cell.borrow() {
+        Some(origin) => f(&origin.thread_id),
+        None => THREAD_ID.with(|id| f(id)),
+    })
+}
+
+#[cfg(any(feature = "http", feature = "kafka", feature = "syslog"))]
+thread_local! {
+    /// Set while a network logger sends on the current thread
+    static SENDING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether a network logger is sending on the current thread right now.
+///
+/// The libraries used by network loggers log through the `log` crate themselves, e.g. while
+/// connecting or sending a request. Network loggers drop these records, so they neither
+/// take the lock of a sink held by the current thread once more nor cause new requests.
+#[cfg(any(feature = "http", feature = "kafka", feature = "syslog"))]
+pub fn sending() -> bool {
+    SENDING.with(Cell::get)
+}
+
+/// Runs `send` marking the current thread as sending, see [`sending`]
+#[cfg(any(feature = "http", feature = "kafka", feature = "syslog"))]
+pub fn while_sending<R>(send: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SENDING.with(|sending| sending.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SENDING.with(|sending| sending.replace(true)));
+    send()
+}
+
+/// A `LevelFilter`, that can be changed while the logger is in use
+#[derive(Debug, Default)]
+pub struct AtomicLevel(AtomicUsize);
+
+impl AtomicLevel {
+    pub const fn new(level: LevelFilter) -> AtomicLevel {
+        AtomicLevel(AtomicUsize::new(level as usize))
+    }
+
+    #[inline(always)]
+    pub fn get(&self) -> LevelFilter {
+        match self.0.load(Ordering::Relaxed) {
+            0 => LevelFilter::Off,
+            1 => LevelFilter::Error,
+            2 => LevelFilter::Warn,
+            3 => LevelFilter::Info,
+            4 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    pub fn set(&self, level: LevelFilter) {
+        self.0.store(level as usize, Ordering::Relaxed);
+    }
+}
+
+/// Returns the level applying to `target`, taking the runtime level filters followed by the config
+/// and the level filters of the config into account
+#[inline(always)]
+pub fn target_level(level: LevelFilter, config: &Config, target: &str) -> LevelFilter {
+    let runtime = config.runtime_level_filters.as_ref();
+    if let Some(level) = runtime.and_then(|filters| filters.target_level(target)) {
+        return level;
+    }
+    config
+        .level_filters
+        .iter()
+        .filter(|(prefix, _)| target.starts_with(&**prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map_or(level, |(_, level)| *level)
+}
+
+/// Shared implementation of `Log::enabled` for loggers with a config,
+/// taking the level filters and the allowed and ignored targets into account
+#[inline(always)]
+pub fn enabled(level: LevelFilter, config: &Config, metadata: &Metadata<'_>) -> bool {
+    // records more verbose than every level are rejected without taking any lock
+    metadata.level() <= max_level(level, config)
+        && metadata.level() <= target_level(level, config, metadata.target())
+        && !is_filtered(config, metadata.target())
+}
+
+/// The most verbose level any record may be logged at, taking the level filters into account
+pub fn max_level(level: LevelFilter, config: &Config) -> LevelFilter {
+    config
+        .level_filters
+        .iter()
+        .map(|(_, level)| *level)
+        .fold(level.max(runtime_max_level(config)), std::cmp::max)
+}
+
+/// The most verbose level of the runtime level filters followed by the config, if any
+#[inline(always)]
+pub(crate) fn runtime_max_level(config: &Config) -> LevelFilter {
+    config
+        .runtime_level_filters
+        .as_ref()
+        .map_or(LevelFilter::Off, RuntimeLevelFilters::max_level)
+}
+
+/// Calls `print` through the print gate of the config, if one is set
+pub fn print_gated<F>(config: &Config, mut print: F)
+where
+    F: FnMut(),
+{
+    match &config.print_gate {
+        Some(gate) => (gate.0)(&mut print),
+        None => print(),
+    }
+}
+
+/// Per target state of the rate limit of a config
+///
+/// Cloning it starts over with fresh windows, so loggers sharing a config do not
+/// count each others records.
+pub(crate) struct RateLimiter {
+    count: u32,
+    window: Duration,
+    targets: Mutex<HashMap<String, RateWindow>>,
+}
+
+struct RateWindow {
+    start: Instant,
+    logged: u32,
+    suppressed: u64,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(count: u32, window: Duration) -> RateLimiter {
+        RateLimiter {
+            count,
+            window,
+            targets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts a record of `target`, returning `None` if it exceeds the limit
+    /// or the amount of records suppressed in the previous window otherwise
+    fn admit(&self, target: &str) -> Option<u64> {
+        let now = Instant::now();
+        let mut targets = self.targets.lock().unwrap();
+        if !targets.contains_key(target) {
+            targets.insert(
+                String::from(target),
+                RateWindow {
+                    start: now,
+                    logged: 0,
+                    suppressed: 0,
+                },
+            );
+        }
+        let window = targets.get_mut(target).unwrap();
+
+        let mut suppressed = 0;
+        if now.duration_since(window.start) >= self.window {
+            suppressed = window.suppressed;
+            window.start = now;
+            window.logged = 0;
+            window.suppressed = 0;
+        }
+        if window.logged >= self.count {
+            window.suppressed += 1;
+            return None;
+        }
+        window.logged += 1;
+        Some(suppressed)
+    }
+}
+
+impl Clone for RateLimiter {
+    fn clone(&self) -> RateLimiter {
+        RateLimiter::new(self.count, self.window)
+    }
+}
+
+impl fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("count", &self.count)
+            .field("window", &self.window)
+            .finish()
+    }
+}
+
+/// Counter of the sampling of a config
+///
+/// Like the `RateLimiter`, cloning it starts over.
+pub(crate) struct Sampler {
+    level: LevelFilter,
+    every: u64,
+    counter: AtomicU64,
+}
+
+impl Sampler {
+    pub(crate) fn new(level: LevelFilter, every: u64) -> Sampler {
+        Sampler {
+            level,
+            every: every.max(1),
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    /// Checks if a record of `level` is sampled, but not picked
+    #[inline(always)]
+    fn drops(&self, level: Level) -> bool {
+        level > self.level
+            && !self
+                .counter
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(self.every)
+    }
+}
+
+impl Clone for Sampler {
+    fn clone(&self) -> Sampler {
+        Sampler::new(self.level, self.every)
+    }
+}
+
+impl fmt::Debug for Sampler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Sampler")
+            .field("level", &self.level)
+            .field("every", &self.every)
+            .finish()
+    }
+}
+
+/// State of the folding of repeated records of a config
+///
+/// Like the `RateLimiter`, cloning it starts over.
+pub(crate) struct RepeatFolder {
+    timeout: Duration,
+    last: Mutex<Option<LastRecord>>,
+}
+
+struct LastRecord {
+    level: Level,
+    target: String,
+    message: String,
+    since: Instant,
+    repeated: u64,
+}
+
+/// Summary of the repeats of a record, passed on instead of them
+struct Repeats {
+    level: Level,
+    target: String,
+    count: u64,
+}
+
+impl RepeatFolder {
+    pub(crate) fn new(timeout: Duration) -> RepeatFolder {
+        RepeatFolder {
+            timeout,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Compares `record` with the previous one, returning if it is to be logged
+    /// and the repeats of the previous record to report first
+    fn admit(&self, record: &Record<'_>) -> (bool, Option<Repeats>) {
+        let now = Instant::now();
+        let message = record.args().to_string();
+        let mut last = self.last.lock().unwrap();
+
+        if let Some(last) = last.as_mut().filter(|last| {
+            last.level == record.level()
+                && last.target == record.target()
+                && last.message == message
+        }) {
+            // report the repeats once in a while, even if they never end
+            if now.duration_since(last.since) < self.timeout {
+                last.repeated += 1;
+                return (false, None);
+            }
+            let repeats = Repeats {
+                level: last.level,
+                target: last.target.clone(),
+                count: last.repeated,
+            };
+            last.since = now;
+            last.repeated = 1;
+            return (false, Some(repeats).filter(|repeats| repeats.count > 0));
+        }
+
+        let repeats = last
+            .replace(LastRecord {
+                level: record.level(),
+                target: String::from(record.target()),
+                message,
+                since: now,
+                repeated: 0,
+            })
+            .filter(|last| last.repeated > 0)
+            .map(|last| Repeats {
+                level: last.level,
+                target: last.target,
+                count: last.repeated,
+            });
+        (true, repeats)
+    }
+}
+
+impl Clone for RepeatFolder {
+    fn clone(&self) -> RepeatFolder {
+        RepeatFolder::new(self.timeout)
+    }
+}
+
+impl fmt::Debug for RepeatFolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RepeatFolder")
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+
+/// Passes `record` through the transforms of the config and then to `log`, unless a transform dropped it,
+/// it is not picked by the sampling, a repeat of the previous record or its target exceeded the rate limit of the config.
+/// Summaries of folded repeats and of records suppressed by the rate limit are passed as records as well.
+pub fn throttled<F>(config: &Config, record: &Record<'_>, log: F)
+where
+    F: FnMut(&Record<'_>),
+{
+    if config.transforms.is_empty() {
+        return admit(config, record, log);
+    }
+
+    let mut owned = OwnedRecord {
+        level: record.level(),
+        target: String::from(record.target()),
+        message: record.args().to_string(),
+        key_values: record_key_values(record),
+    };
+    for transform in &config.transforms {
+        if !transform.0.transform(&mut owned) {
+            stats::count_dropped();
+            return;
+        }
+    }
+
+    let mut builder = Record::builder();
+    builder
+        .level(owned.level)
+        .target(&owned.target)
+        .module_path(record.module_path())
+        .file(record.file())
+        .line(record.line());
+    #[cfg(feature = "kv")]
+    builder.key_values(&owned.key_values);
+    admit(
+        config,
+        &builder.args(format_args!("{}", owned.message)).build(),
+        log,
+    );
+}
+
+/// The part of `throttled` after the transforms were applied
+fn admit<F>(config: &Config, record: &Record<'_>, mut log: F)
+where
+    F: FnMut(&Record<'_>),
+{
+    let mut log = |record: &Record<'_>| {
+        stats::count_emitted(record.level());
+        log(record)
+    };
+
+    if let Some(sampler) = &config.sampling {
+        if sampler.drops(record.level()) {
+            stats::count_dropped();
+            return;
+        }
+    }
+
+    if let Some(folder) = &config.fold_repeats {
+        let (admitted, repeats) = folder.admit(record);
+        if let Some(repeats) = repeats {
+            log(&Record::builder()
+                .args(format_args!(
+                    "last message repeated {} times",
+                    repeats.count
+                ))
+                .level(repeats.level)
+                .target(&repeats.target)
+                .build());
+        }
+        if !admitted {
+            stats::count_suppressed();
+            return;
+        }
+    }
+
+    let suppressed = match &config.rate_limit {
+        Some(limiter) => match limiter.admit(record.target()) {
+            Some(suppressed) => suppressed,
+            None => {
+                stats::count_rate_limited();
+                return;
+            }
+        },
+        None => 0,
+    };
+
+    if suppressed > 0 {
+        log(&Record::builder()
+            .args(format_args!("suppressed {} messages", suppressed))
+            .level(record.level())
+            .target(record.target())
+            .module_path(record.module_path())
+            .build());
+    }
+
+    match &config.redactor {
+        Some(redactor) => {
+            let formatted;
+            let message = match record.args().as_str() {
+                Some(message) => message,
+                None => {
+                    formatted = record.args().to_string();
+                    &formatted
+                }
+            };
+            match (redactor.0)(message) {
+                Cow::Borrowed(_) => log(record),
+                Cow::Owned(redacted) => {
+                    // keep the key-values of the record, if they are supported
+                    #[cfg(feature = "kv")]
+                    let mut builder = record.to_builder();
+                    #[cfg(not(feature = "kv"))]
+                    let mut builder = Record::builder();
+                    builder
+                        .metadata(record.metadata().clone())
+                        .module_path(record.module_path())
+                        .file(record.file())
+                        .line(record.line());
+                    log(&builder.args(format_args!("{}", redacted)).build())
+                }
+            }
+        }
+        None => log(record),
+    }
+}
+
+/// Options to open a log file for appending, creating it with the file mode of the config
+pub fn file_options(config: &Config) -> OpenOptions {
+    let mut options = OpenOptions::new();
+    options.create(true).append(true);
+    #[cfg(unix)]
+    if let Some(mode) = config.file_mode {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = config;
+    options
+}
+
+/// Opens the log file at `path`, creating it and its parent directories according to the config
+pub fn open_file(path: &Path, config: &Config) -> Result<File, FileError> {
+    if config.create_dirs {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(|err| FileError::CreateDir(dir.to_path_buf(), err))?;
+        }
+    }
+
+    // truncating keeps the file in append mode, so multiple writers never overwrite each other
+    let file = file_options(config)
+        .open(path)
+        .map_err(|err| FileError::Open(path.to_path_buf(), err))?;
+    if config.file_truncate {
+        file.set_len(0)
+            .map_err(|err| FileError::Open(path.to_path_buf(), err))?;
+    }
+    Ok(file)
+}
+
+/// Checks if a record belongs on stderr instead of stdout
+#[inline(always)]
+pub fn use_stderr(config: &Config, record: &Record<'_>) -> bool {
+    record.level() <= config.stderr
+}
+
+#[inline(always)]
+pub fn should_skip(config: &Config, record: &Record<'_>) -> bool {
+    is_filtered(config, record.target())
+        || is_message_filtered(config, record)
+        || !config
+            .record_filters
+            .iter()
+            .all(|filter| (filter.0)(record))
+}
+
+/// Checks if the message of `record` is not allowed or ignored by the message filters of the config
+#[cfg(feature = "regex")]
+fn is_message_filtered(config: &Config, record: &Record<'_>) -> bool {
+    if config.message_filter_allow.is_empty() && config.message_filter_ignore.is_empty() {
+        return false;
+    }
+
+    let formatted;
+    let message = match record.args().as_str() {
+        Some(message) => message,
+        None => {
+            formatted = record.args().to_string();
+            &formatted
+        }
+    };
+    (!config.message_filter_allow.is_empty()
+        && !config
+            .message_filter_allow
+            .iter()
+            .any(|regex| regex.is_match(message)))
+        || config
+            .message_filter_ignore
+            .iter()
+            .any(|regex| regex.is_match(message))
+}
+
+#[cfg(not(feature = "regex"))]
+#[inline(always)]
+fn is_message_filtered(_config: &Config, _record: &Record<'_>) -> bool {
+    false
+}
+
+/// Checks if `target` is not allowed or ignored by the target filters of the config
+#[inline(always)]
+pub fn is_filtered(config: &Config, target: &str) -> bool {
+    // If a module path and allowed list are available
+    match (target, &*config.filter_allow) {
+        // Check that the module path matches at least one allow filter
+        (path, allowed)
+            if !allowed.is_empty() && !allowed.iter().any(|v| path.starts_with(&**v)) =>
+        {
+            // If not, skip any further writing
+            return true;
         }
         _ => {}
     }
 
     // If a module path and ignore list are available
-    match (record.target(), &*config.filter_ignore) {
-        (path, ignore) if !ignore.is_empty() => {
-            // Check that the module path does not match any ignore filters
-            if ignore.iter().any(|v| path.starts_with(&**v)) {
-                // If not, skip any further writing
-                return true;
-            }
+    match (target, &*config.filter_ignore) {
+        // Check that the module path does not match any ignore filters
+        (path, ignore) if !ignore.is_empty() && ignore.iter().any(|v| path.starts_with(&**v)) => {
+            // If not, skip any further writing
+            return true;
         }
         _ => {}
     }
 
+    #[cfg(feature = "regex")]
+    {
+        if !config.filter_allow_regex.is_empty()
+            && !config
+                .filter_allow_regex
+                .iter()
+                .any(|regex| regex.is_match(target))
+        {
+            return true;
+        }
+        if config
+            .filter_ignore_regex
+            .iter()
+            .any(|regex| regex.is_match(target))
+        {
+            return true;
+        }
+    }
+
     false
 }
@@ -0,0 +1,183 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the MemoryLogger Implementation
+
+use super::logging::{enabled, max_level, with_formatted, AtomicLevel};
+use crate::{handle, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+struct Inner {
+    level: AtomicLevel,
+    config: Config,
+    capacity: usize,
+    records: Mutex<VecDeque<String>>,
+}
+
+/// The MemoryLogger struct. Provides a Logger implementation keeping the most recent records in memory.
+///
+/// Records are formatted according to the `Config` and stored without their line ending
+/// in a ring buffer, once it is full the oldest record is discarded for every new one.
+/// The stored records can be inspected using [`MemoryLogger::snapshot`] or taken using [`MemoryLogger::drain`],
+/// e.g. to show them in a GUI or to attach them to a bug report.
+///
+/// Clones of a MemoryLogger share the same buffer and level. This allows to keep a clone around
+/// to read the records, when the logger itself is moved into a `CombinedLogger`.
+#[derive(Clone)]
+pub struct MemoryLogger {
+    inner: Arc<Inner>,
+}
+
+impl MemoryLogger {
+    /// init function. Globally initializes the MemoryLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config` and the amount of records to keep as arguments.
+    /// The level can be changed and the stored records read later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = MemoryLogger::init(LevelFilter::Info, Config::default(), 1000);
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        capacity: usize,
+    ) -> Result<LoggerHandle<MemoryLogger>, SetLoggerError> {
+        handle::init(MemoryLogger::new(log_level, config, capacity))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config` and the amount of records to keep as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let memory_logger = MemoryLogger::new(LevelFilter::Info, Config::default(), 1000);
+    /// let recent = memory_logger.clone();
+    /// let _ = CombinedLogger::init(vec![memory_logger as Box<dyn SharedLogger>]);
+    /// // later on
+    /// for record in recent.snapshot() {
+    ///     println!("{}", record);
+    /// }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(log_level: LevelFilter, config: Config, capacity: usize) -> Box<MemoryLogger> {
+        Box::new(MemoryLogger {
+            inner: Arc::new(Inner {
+                level: AtomicLevel::new(log_level),
+                config,
+                capacity,
+                records: Mutex::new(VecDeque::with_capacity(capacity)),
+            }),
+        })
+    }
+
+    /// Returns a copy of the stored records, oldest first
+    pub fn snapshot(&self) -> Vec<String> {
+        self.inner.records.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Removes and returns the stored records, oldest first
+    pub fn drain(&self) -> Vec<String> {
+        self.inner.records.lock().unwrap().drain(..).collect()
+    }
+
+    /// Removes all stored records
+    pub fn clear(&self) {
+        self.inner.records.lock().unwrap().clear();
+    }
+
+    /// Returns the maximum amount of records kept
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity
+    }
+}
+
+impl Log for MemoryLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.inner.level.get(), &self.inner.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.inner.capacity > 0 && self.enabled(record.metadata()) {
+            let _ = with_formatted(&self.inner.config, record, |buf| {
+                let mut line = String::from_utf8_lossy(buf).into_owned();
+                if line.ends_with(&self.inner.config.line_ending) {
+                    line.truncate(line.len() - self.inner.config.line_ending.len());
+                }
+
+                let mut records = self.inner.records.lock().unwrap();
+                if records.len() >= self.inner.capacity {
+                    records.pop_front();
+                }
+                records.push_back(line);
+                Ok(())
+            });
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for MemoryLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.inner.level.get(), &self.inner.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.inner.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.inner.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::{CombinedLogger, ConfigBuilder, Level};
+
+    #[test]
+    fn test_memory_logger() {
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Off)
+            .build();
+        let logger = MemoryLogger::new(LevelFilter::Info, config, 2);
+        let reader = logger.clone();
+        let combined = CombinedLogger::new(vec![logger as Box<dyn SharedLogger>]);
+        for i in 0..3 {
+            log(&combined, Level::Info, &format!("record {}", i));
+        }
+        log(&combined, Level::Debug, "filtered");
+
+        assert_eq!(reader.snapshot(), ["[INFO] record 1", "[INFO] record 2"]);
+        assert_eq!(reader.drain(), ["[INFO] record 1", "[INFO] record 2"]);
+        assert!(reader.snapshot().is_empty());
+    }
+}
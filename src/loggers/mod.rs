@@ -1,16 +1,94 @@
+mod asynclog;
 mod comblog;
+mod containerlog;
+#[cfg(all(windows, feature = "debugview"))]
+mod debugviewlog;
+#[cfg(feature = "smtp")]
+mod emaillog;
+#[cfg(all(windows, feature = "etw"))]
+mod etwlog;
+mod filelog;
+#[cfg(feature = "gelf")]
+mod gelflog;
+#[cfg(feature = "http")]
+mod httplog;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+mod journallog;
+#[cfg(feature = "kafka")]
+mod kafkalog;
 pub mod logging;
+mod memorylog;
+mod nulllog;
+#[cfg(all(target_vendor = "apple", feature = "oslog"))]
+mod oslog;
+#[cfg(windows)]
+mod pipelog;
+#[cfg(feature = "syslog")]
+mod remotesyslog;
 mod simplelog;
+mod splitlog;
+#[cfg(feature = "sqlite")]
+mod sqlitelog;
+#[cfg(all(unix, feature = "syslog"))]
+mod syslog;
+mod tcplog;
+mod teelog;
 #[cfg(feature = "termcolor")]
-mod termlog;
+pub(crate) mod termlog;
 #[cfg(feature = "test")]
-mod testlog;
+pub(crate) mod testlog;
+#[cfg(unix)]
+mod unixlog;
+#[cfg(feature = "http")]
+mod webhooklog;
+#[cfg(all(windows, feature = "wineventlog"))]
+mod wineventlog;
 mod writelog;
 
-pub use self::comblog::CombinedLogger;
+pub use self::asynclog::{AsyncLogger, OverflowPolicy};
+pub(crate) use self::comblog::update_runtime_levels;
+pub use self::comblog::{CombinedLogger, LoggerId};
+pub use self::containerlog::ContainerLogger;
+#[cfg(all(windows, feature = "debugview"))]
+pub use self::debugviewlog::DebugViewLogger;
+#[cfg(feature = "smtp")]
+pub use self::emaillog::{EmailLogger, EmailOptions};
+#[cfg(all(windows, feature = "etw"))]
+pub use self::etwlog::EtwLogger;
+pub use self::filelog::{FileLogger, Rotation};
+#[cfg(feature = "gelf")]
+pub use self::gelflog::{GelfLogger, GelfOptions};
+#[cfg(feature = "http")]
+pub use self::httplog::{HttpLogger, HttpOptions};
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub use self::journallog::JournalLogger;
+#[cfg(feature = "kafka")]
+pub use self::kafkalog::{KafkaKey, KafkaLogger};
+pub use self::logging::DefaultFormatter;
+pub use self::memorylog::MemoryLogger;
+pub use self::nulllog::NullLogger;
+#[cfg(all(target_vendor = "apple", feature = "oslog"))]
+pub use self::oslog::OsLogger;
+#[cfg(windows)]
+pub use self::pipelog::NamedPipeLogger;
+#[cfg(feature = "syslog")]
+pub use self::remotesyslog::{Facility, RemoteSyslogLogger, SyslogFormat, SyslogOptions};
 pub use self::simplelog::SimpleLogger;
+pub use self::splitlog::SplitFileLogger;
+#[cfg(feature = "sqlite")]
+pub use self::sqlitelog::{SqliteLogger, SqliteOptions};
+#[cfg(all(unix, feature = "syslog"))]
+pub use self::syslog::SyslogLogger;
+pub use self::tcplog::{ConnectionEvent, NetworkOptions, TcpLogger};
+pub use self::teelog::TeeLogger;
 #[cfg(feature = "termcolor")]
 pub use self::termlog::{TermLogger, TerminalMode};
 #[cfg(feature = "test")]
-pub use self::testlog::TestLogger;
+pub use self::testlog::{CapturedRecord, TestLogger};
+#[cfg(unix)]
+pub use self::unixlog::{UnixSocketKind, UnixSocketLogger};
+#[cfg(feature = "http")]
+pub use self::webhooklog::{WebhookLogger, WebhookOptions};
+#[cfg(all(windows, feature = "wineventlog"))]
+pub use self::wineventlog::WinEventLogger;
 pub use self::writelog::WriteLogger;
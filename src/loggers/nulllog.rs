@@ -0,0 +1,99 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the NullLogger Implementation
+
+use super::logging::{enabled, max_level, try_log, AtomicLevel};
+use crate::{handle, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::sink;
+
+/// The NullLogger struct. Provides a Logger implementation discarding all records.
+///
+/// Enabled records are still formatted according to the `Config`, so it can be used
+/// to measure the formatting overhead without any I/O, or as a placeholder in a `CombinedLogger`.
+pub struct NullLogger {
+    level: AtomicLevel,
+    config: Config,
+}
+
+impl NullLogger {
+    /// init function. Globally initializes the NullLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments. The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = NullLogger::init(LevelFilter::Info, Config::default());
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+    ) -> Result<LoggerHandle<NullLogger>, SetLoggerError> {
+        handle::init(NullLogger::new(log_level, config))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let null_logger = NullLogger::new(LevelFilter::Info, Config::default());
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(log_level: LevelFilter, config: Config) -> Box<NullLogger> {
+        Box::new(NullLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+        })
+    }
+}
+
+impl Log for NullLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let _ = try_log(&self.config, record, &mut sink());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for NullLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the OsLogger Implementation
+
+use super::logging::{enabled, max_level, try_log, AtomicLevel};
+use crate::{handle, Config, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CString};
+use std::sync::Mutex;
+
+#[allow(non_camel_case_types)]
+type os_log_t = *mut c_void;
+
+extern "C" {
+    /// Header of the image containing this code, relative to which the format string is stored
+    static __dso_handle: c_void;
+
+    fn os_log_create(subsystem: *const c_char, category: *const c_char) -> os_log_t;
+    fn os_log_type_enabled(log: os_log_t, log_type: u8) -> bool;
+    fn _os_log_impl(
+        dso: *const c_void,
+        log: os_log_t,
+        log_type: u8,
+        format: *const c_char,
+        buf: *const u8,
+        size: u32,
+    );
+    fn os_release(object: *mut c_void);
+}
+
+/// Format string of every record, which needs to be placed in the section the `os_log` macros use
+#[link_section = "__TEXT,__oslogstring,cstring_literals"]
+static FORMAT: [u8; 11] = *b"%{public}s\0";
+
+const OS_LOG_TYPE_DEFAULT: u8 = 0x00;
+const OS_LOG_TYPE_INFO: u8 = 0x01;
+const OS_LOG_TYPE_DEBUG: u8 = 0x02;
+const OS_LOG_TYPE_ERROR: u8 = 0x10;
+
+/// Maps a `Level` to the matching log type, there are no types for warnings and traces
+fn log_type(level: Level) -> u8 {
+    match level {
+        Level::Error => OS_LOG_TYPE_ERROR,
+        Level::Warn => OS_LOG_TYPE_DEFAULT,
+        Level::Info => OS_LOG_TYPE_INFO,
+        Level::Debug | Level::Trace => OS_LOG_TYPE_DEBUG,
+    }
+}
+
+/// Log object of a subsystem and category
+struct OsLog(os_log_t);
+
+// Log objects are thread-safe
+unsafe impl Send for OsLog {}
+unsafe impl Sync for OsLog {}
+
+impl OsLog {
+    fn new(subsystem: &CString, category: &str) -> OsLog {
+        let category = CString::new(category.replace('\0', "")).unwrap();
+        OsLog(unsafe { os_log_create(subsystem.as_ptr(), category.as_ptr()) })
+    }
+
+    fn write(&self, log_type: u8, msg: &CString) {
+        // encodes the single public string argument the way the `os_log` macros do:
+        // summary (has non-scalar items), argument count, descriptor (public string), size, pointer
+        let mut buf = [0u8; 12];
+        buf[..4].copy_from_slice(&[0x02, 0x01, 0x22, 0x08]);
+        buf[4..].copy_from_slice(&(msg.as_ptr() as usize as u64).to_ne_bytes());
+        unsafe {
+            _os_log_impl(
+                &__dso_handle,
+                self.0,
+                log_type,
+                FORMAT.as_ptr() as *const c_char,
+                buf.as_ptr(),
+                buf.len() as u32,
+            );
+        }
+    }
+}
+
+impl Drop for OsLog {
+    fn drop(&mut self) {
+        unsafe {
+            os_release(self.0);
+        }
+    }
+}
+
+/// The OsLogger struct. Provides a Logger implementation writing records to Apple's unified logging system.
+///
+/// Records are logged with the given subsystem, usually a reverse DNS name like `com.example.app`,
+/// and their target as the category, so they can be filtered by both in Console.app or with `log stream`.
+/// The levels are mapped to the log types error, default (`Warn`), info and debug (`Debug` and `Trace`).
+/// Note that the system only keeps info and debug messages, if configured to do so for the subsystem.
+///
+/// Records are only formatted, if their type is enabled for the category.
+/// The logger is available on Apple platforms only and requires the `oslog` feature.
+pub struct OsLogger {
+    level: AtomicLevel,
+    config: Config,
+    subsystem: CString,
+    logs: Mutex<HashMap<String, OsLog>>,
+}
+
+impl OsLogger {
+    /// init function. Globally initializes the OsLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config` and the subsystem as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = OsLogger::init(LevelFilter::Info, Config::default(), "com.example.app");
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        subsystem: &str,
+    ) -> Result<LoggerHandle<OsLogger>, SetLoggerError> {
+        handle::init(OsLogger::new(log_level, config, subsystem))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config` and the subsystem as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let os_logger = OsLogger::new(LevelFilter::Info, Config::default(), "com.example.app");
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(log_level: LevelFilter, config: Config, subsystem: &str) -> Box<OsLogger> {
+        Box::new(OsLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            subsystem: CString::new(subsystem.replace('\0', "")).unwrap(),
+            logs: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+impl Log for OsLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let log_type = log_type(record.level());
+        let mut logs = self.logs.lock().unwrap();
+        let log = logs
+            .entry(String::from(record.target()))
+            .or_insert_with(|| OsLog::new(&self.subsystem, record.target()));
+        if !unsafe { os_log_type_enabled(log.0, log_type) } {
+            return;
+        }
+
+        let mut msg = Vec::new();
+        if try_log(&self.config, record, &mut msg).is_err() || msg.is_empty() {
+            return;
+        }
+        while matches!(msg.last(), Some(b'\n') | Some(b'\r')) {
+            msg.pop();
+        }
+        msg.retain(|&b| b != 0);
+        log.write(log_type, &CString::new(msg).unwrap());
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for OsLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Level;
+
+    #[test]
+    fn test_os_logger() {
+        let logger = OsLogger::new(LevelFilter::Trace, Config::default(), "org.simplelog.test");
+        for level in &[Level::Error, Level::Warn, Level::Info, Level::Debug] {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("test message"))
+                    .level(*level)
+                    .target("simplelog::tests")
+                    .build(),
+            );
+        }
+        logger.flush();
+    }
+}
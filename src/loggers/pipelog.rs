@@ -0,0 +1,191 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the NamedPipeLogger Implementation
+
+use super::logging::{enabled, max_level, with_formatted, AtomicLevel};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::windows::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use windows_sys::Win32::Foundation::ERROR_PIPE_BUSY;
+use windows_sys::Win32::System::Pipes::WaitNamedPipeW;
+
+/// Time to wait for an instance of a busy pipe to become available, in milliseconds
+const BUSY_TIMEOUT: u32 = 1000;
+
+/// The connection to the collector
+struct Pipe {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Pipe {
+    fn open(path: &Path) -> io::Result<File> {
+        match OpenOptions::new().write(true).open(path) {
+            Err(err) if err.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) => {
+                // all instances of the pipe are connected, wait for one to be released
+                let name: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+                if unsafe { WaitNamedPipeW(name.as_ptr(), BUSY_TIMEOUT) } == 0 {
+                    return Err(err);
+                }
+                OpenOptions::new().write(true).open(path)
+            }
+            res => res,
+        }
+    }
+
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            if file.write_all(msg).is_ok() {
+                return Ok(());
+            }
+        }
+        // the pipe is broken, e.g. because the collector was restarted, reconnect and retry
+        self.file = None;
+        let mut file = Pipe::open(&self.path)?;
+        file.write_all(msg)?;
+        self.file = Some(file);
+        Ok(())
+    }
+}
+
+/// The NamedPipeLogger struct. Provides a Logger implementation writing records to a Windows named pipe.
+///
+/// Each record is written as a line formatted according to the `Config` to a pipe like `\\.\pipe\collector`,
+/// which log collection agents on Windows usually listen on.
+/// If the pipe is broken, e.g. because the agent was restarted, it is opened again with the next record
+/// and the record is written again, otherwise the record is dropped.
+pub struct NamedPipeLogger {
+    level: AtomicLevel,
+    config: Config,
+    pipe: Mutex<Pipe>,
+}
+
+impl NamedPipeLogger {
+    /// init function. Globally initializes the NamedPipeLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config` and the path of the pipe as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the pipe cannot be opened or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = NamedPipeLogger::init(LevelFilter::Info, Config::default(), r"\\.\pipe\collector");
+    /// # }
+    /// ```
+    pub fn init<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+    ) -> Result<LoggerHandle<NamedPipeLogger>, InitError> {
+        let logger = NamedPipeLogger::new(log_level, config, path)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config` and the path of the pipe as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// If all instances of the pipe are busy, up to a second is waited for one to become available.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let pipe_logger = NamedPipeLogger::new(LevelFilter::Info, Config::default(), r"\\.\pipe\collector").unwrap();
+    /// # }
+    /// ```
+    pub fn new<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+    ) -> io::Result<Box<NamedPipeLogger>> {
+        let path = path.as_ref().to_path_buf();
+        let file = Pipe::open(&path)?;
+        Ok(Box::new(NamedPipeLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            pipe: Mutex::new(Pipe {
+                path,
+                file: Some(file),
+            }),
+        }))
+    }
+}
+
+impl Log for NamedPipeLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.pipe.lock().unwrap().file.as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+impl SharedLogger for NamedPipeLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            with_formatted(&self.config, record, |msg| {
+                self.pipe.lock().unwrap().send(msg)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_named_pipe_logger() {
+        let err = NamedPipeLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            format!(r"\\.\pipe\simplelog-missing-{}", std::process::id()),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,748 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the RemoteSyslogLogger Implementation
+
+use super::logging::{
+    enabled, format_rfc3339, hostname, key_values, max_level, sending, while_sending,
+    with_formatted, AtomicLevel,
+};
+use super::tcplog::{Connection, NetworkOptions, Wrap};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger, TimePrecision};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+#[cfg(feature = "syslog-tls")]
+use rustls::{pki_types::ServerName, ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+#[cfg(feature = "syslog-tls")]
+use std::convert::TryFrom;
+use std::env;
+use std::io::{self, Write};
+use std::net::ToSocketAddrs;
+use std::process;
+#[cfg(feature = "syslog-tls")]
+use std::sync::Arc;
+use std::sync::Mutex;
+use time::{macros::format_description, OffsetDateTime};
+
+/// SD-ID of the structured data element, using the enterprise number reserved for documentation
+const DEFAULT_SD_ID: &str = "kv@32473";
+
+/// Format of the messages sent by the `SyslogLogger` and the `RemoteSyslogLogger`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyslogFormat {
+    /// The legacy BSD format of RFC 3164, `<PRI>TIMESTAMP HOSTNAME TAG[PID]: MSG`
+    Rfc3164,
+    /// The format of RFC 5424, `<PRI>1 TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA MSG`
+    Rfc5424,
+}
+
+/// Settings of the messages sent by the syslog loggers
+///
+/// # Examples
+/// ```no_run
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let mut options = SyslogOptions::new();
+/// options.set_structured_data_id("0123abcd@41058");
+/// let _ = RemoteSyslogLogger::init(
+///     LevelFilter::Info,
+///     Config::default(),
+///     Facility::User,
+///     "logs.example.com:601",
+///     options,
+///     NetworkOptions::default(),
+/// );
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SyslogOptions {
+    pub(super) format: Option<SyslogFormat>,
+    app_name: Option<String>,
+    msg_id: Option<String>,
+    structured_data_id: Option<String>,
+}
+
+impl SyslogOptions {
+    /// Creates the default settings
+    pub fn new() -> SyslogOptions {
+        SyslogOptions {
+            format: None,
+            app_name: None,
+            msg_id: None,
+            structured_data_id: None,
+        }
+    }
+
+    /// set the format of the messages
+    /// (default is RFC 3164 for the `SyslogLogger` and RFC 5424 for the `RemoteSyslogLogger`)
+    ///
+    /// Older syslog daemons only accept the legacy BSD format of RFC 3164. The `RemoteSyslogLogger`
+    /// terminates these messages by a line feed instead of prefixing them with their length.
+    pub fn set_format(&mut self, format: SyslogFormat) -> &mut SyslogOptions {
+        self.format = Some(format);
+        self
+    }
+
+    /// set the APP-NAME of RFC 5424 and the tag of RFC 3164 messages
+    /// (default is the file name of the running executable)
+    pub fn set_app_name<S: Into<String>>(&mut self, app_name: S) -> &mut SyslogOptions {
+        self.app_name = Some(app_name.into());
+        self
+    }
+
+    /// set the MSGID of RFC 5424 messages, identifying the type of the messages
+    /// (default is `-`, meaning none)
+    pub fn set_msg_id<S: Into<String>>(&mut self, msg_id: S) -> &mut SyslogOptions {
+        self.msg_id = Some(msg_id.into());
+        self
+    }
+
+    /// set the SD-ID of the structured data element the key-values are written into
+    /// (default is `kv@32473`, only written for records with key-values)
+    ///
+    /// Once set, the element is written for every record, even without key-values,
+    /// as some services expect a token in it, e.g. `0123abcd@41058`.
+    pub fn set_structured_data_id<S: Into<String>>(&mut self, id: S) -> &mut SyslogOptions {
+        self.structured_data_id = Some(id.into());
+        self
+    }
+}
+
+impl Default for SyslogOptions {
+    fn default() -> SyslogOptions {
+        SyslogOptions::new()
+    }
+}
+
+/// Syslog facility, describing the kind of program that logged a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Facility {
+    /// Kernel messages
+    Kern = 0,
+    /// User-level messages
+    User = 1,
+    /// Mail system
+    Mail = 2,
+    /// System daemons
+    Daemon = 3,
+    /// Security/authorization messages
+    Auth = 4,
+    /// Messages generated internally by syslogd
+    Syslog = 5,
+    /// Line printer subsystem
+    Lpr = 6,
+    /// Network news subsystem
+    News = 7,
+    /// UUCP subsystem
+    Uucp = 8,
+    /// Clock daemon
+    Cron = 9,
+    /// Private security/authorization messages
+    AuthPriv = 10,
+    /// FTP daemon
+    Ftp = 11,
+    /// Locally used facility 0
+    Local0 = 16,
+    /// Locally used facility 1
+    Local1 = 17,
+    /// Locally used facility 2
+    Local2 = 18,
+    /// Locally used facility 3
+    Local3 = 19,
+    /// Locally used facility 4
+    Local4 = 20,
+    /// Locally used facility 5
+    Local5 = 21,
+    /// Locally used facility 6
+    Local6 = 22,
+    /// Locally used facility 7
+    Local7 = 23,
+}
+
+/// Maps a `Level` to the matching syslog severity
+pub(super) fn severity(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,                // err
+        Level::Warn => 4,                 // warning
+        Level::Info => 6,                 // info
+        Level::Debug | Level::Trace => 7, // debug
+    }
+}
+
+/// Keeps the printable ASCII characters of `value` allowed in a header field, at most `max` of them
+fn header_field(value: &str, max: usize) -> String {
+    let field: String = value
+        .chars()
+        .filter(char::is_ascii_graphic)
+        .take(max)
+        .collect();
+    match field.is_empty() {
+        true => String::from("-"),
+        false => field,
+    }
+}
+
+/// Keeps the characters of `name` allowed in an SD-ID or a parameter name
+fn sd_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_ascii_graphic() && !matches!(c, '=' | ']' | '"'))
+        .take(32)
+        .collect();
+    match name.is_empty() {
+        true => String::from("_"),
+        false => name,
+    }
+}
+
+/// Writes the structured data element of the key-values, `-` if there is none
+fn write_structured_data(
+    msg: &mut Vec<u8>,
+    options: &SyslogOptions,
+    key_values: &[(String, String)],
+) {
+    if options.structured_data_id.is_none() && key_values.is_empty() {
+        msg.push(b'-');
+        return;
+    }
+
+    msg.push(b'[');
+    let id = options
+        .structured_data_id
+        .as_deref()
+        .unwrap_or(DEFAULT_SD_ID);
+    msg.extend_from_slice(sd_name(id).as_bytes());
+    for (key, value) in key_values {
+        let _ = write!(msg, " {}=\"", sd_name(key));
+        for c in value.bytes() {
+            if matches!(c, b'"' | b'\\' | b']') {
+                msg.push(b'\\');
+            }
+            msg.push(c);
+        }
+        msg.push(b'"');
+    }
+    msg.push(b']');
+}
+
+/// The APP-NAME or tag of the messages, the one set via `SyslogOptions::set_app_name`
+/// or the file name of the running executable
+pub(super) fn app_name(options: &SyslogOptions) -> String {
+    match &options.app_name {
+        Some(app_name) => header_field(app_name, 48),
+        None => env::current_exe()
+            .ok()
+            .and_then(|exe| {
+                exe.file_name()
+                    .map(|name| header_field(&name.to_string_lossy(), 48))
+            })
+            .unwrap_or_else(|| String::from("rust")),
+    }
+}
+
+/// Writes the header of an RFC 5424 message up to and including the structured data
+pub(super) fn write_rfc5424_header(
+    msg: &mut Vec<u8>,
+    priority: u8,
+    record: &Record<'_>,
+    config: &Config,
+    options: &SyslogOptions,
+    app_name: &str,
+    pid: u32,
+) {
+    let _ = write!(msg, "<{}>1 ", priority);
+    if format_rfc3339(msg, OffsetDateTime::now_utc(), TimePrecision::Micros).is_err() {
+        msg.push(b'-');
+    }
+    let msg_id = match &options.msg_id {
+        Some(msg_id) => header_field(msg_id, 32),
+        None => String::from("-"),
+    };
+    let _ = write!(
+        msg,
+        " {} {} {} {} ",
+        header_field(hostname(config), 255),
+        app_name,
+        pid,
+        msg_id
+    );
+    write_structured_data(msg, options, &key_values(record));
+}
+
+/// Removes the line ending of a formatted record, syslog messages are delimited otherwise
+pub(super) fn trim_line_ending(mut line: &[u8]) -> &[u8] {
+    while let Some((&(b'\n' | b'\r'), rest)) = line.split_last() {
+        line = rest;
+    }
+    line
+}
+
+/// Sets up a TLS session for `domain` on every connected socket
+#[cfg(feature = "syslog-tls")]
+fn tls(tls: Arc<ClientConfig>, domain: &str) -> io::Result<Box<Wrap>> {
+    let name = ServerName::try_from(String::from(domain))
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+    Ok(Box::new(move |mut socket| {
+        let mut session =
+            ClientConnection::new(tls.clone(), name.clone()).map_err(io::Error::other)?;
+        // complete the handshake now, so failures are reported as failed connection attempts
+        while session.is_handshaking() {
+            session.complete_io(&mut socket)?;
+        }
+        Ok(Box::new(StreamOwned::new(session, socket)))
+    }))
+}
+
+/// The RemoteSyslogLogger struct. Provides a Logger implementation sending records to a syslog server over TCP,
+/// optionally using TLS (requires the `syslog-tls` feature).
+///
+/// Records are sent in the format of RFC 5424, framed by their length as described in RFC 6587,
+/// which many managed log services require, or in the legacy format of RFC 3164 terminated by a line feed
+/// for older syslog servers (see [`SyslogOptions::set_format`]).
+/// The priority is made up of the configured [`Facility`] and the severity corresponding to the level
+/// of the record, the app name is the file name of the running executable
+/// (see [`SyslogOptions::set_app_name`]) and the hostname is the one of [`ConfigBuilder::set_hostname`].
+/// The key-values of a record are written as parameters of a structured data element,
+/// see [`SyslogOptions::set_structured_data_id`], in addition to the message formatted according to the `Config`.
+///
+/// Like the [`TcpLogger`](crate::TcpLogger), records are buffered while the server is unreachable,
+/// reconnecting with an exponential backoff, and connecting and sending happens on the logging thread.
+/// Records logged on the same thread while connecting or sending are dropped by this logger.
+///
+/// [`ConfigBuilder::set_hostname`]: crate::ConfigBuilder::set_hostname
+pub struct RemoteSyslogLogger {
+    level: AtomicLevel,
+    config: Config,
+    options: SyslogOptions,
+    facility: Facility,
+    app_name: String,
+    pid: u32,
+    connection: Mutex<Connection>,
+}
+
+impl RemoteSyslogLogger {
+    /// init function. Globally initializes the RemoteSyslogLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, `Facility`, the address of the server, the settings
+    /// of the messages and of the connection as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the address cannot be resolved or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = RemoteSyslogLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     Facility::User,
+    ///     "logs.example.com:601",
+    ///     SyslogOptions::default(),
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        addr: A,
+        options: SyslogOptions,
+        network: NetworkOptions,
+    ) -> Result<LoggerHandle<RemoteSyslogLogger>, InitError> {
+        let logger = RemoteSyslogLogger::new(log_level, config, facility, addr, options, network)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, `Facility`, the address of the server, the settings
+    /// of the messages and of the connection as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// The address is resolved once, if the server is unreachable for now,
+    /// records are buffered until a connection can be established.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let syslog_logger = RemoteSyslogLogger::new(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     Facility::User,
+    ///     "127.0.0.1:601",
+    ///     SyslogOptions::default(),
+    ///     NetworkOptions::default(),
+    /// )
+    /// .unwrap();
+    /// # }
+    /// ```
+    pub fn new<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        addr: A,
+        options: SyslogOptions,
+        network: NetworkOptions,
+    ) -> io::Result<Box<RemoteSyslogLogger>> {
+        RemoteSyslogLogger::with_wrap(
+            log_level,
+            config,
+            facility,
+            addr,
+            options,
+            network,
+            Box::new(|stream| Ok(Box::new(stream))),
+        )
+    }
+
+    /// Globally initializes a RemoteSyslogLogger sending records over TLS, see [`RemoteSyslogLogger::new_tls`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = RemoteSyslogLogger::init_tls(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     Facility::User,
+    ///     "logs.example.com:6514",
+    ///     SyslogOptions::default(),
+    ///     NetworkOptions::default(),
+    ///     "logs.example.com",
+    /// );
+    /// # }
+    /// ```
+    #[cfg(feature = "syslog-tls")]
+    pub fn init_tls<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        addr: A,
+        options: SyslogOptions,
+        network: NetworkOptions,
+        domain: &str,
+    ) -> Result<LoggerHandle<RemoteSyslogLogger>, InitError> {
+        let logger = RemoteSyslogLogger::new_tls(
+            log_level, config, facility, addr, options, network, domain,
+        )?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// Same as [`RemoteSyslogLogger::new`], but sends the records over TLS,
+    /// verifying the certificate of the server for `domain` using the Mozilla root certificates.
+    ///
+    /// Fails if `domain` is not a valid DNS name.
+    #[cfg(feature = "syslog-tls")]
+    pub fn new_tls<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        addr: A,
+        options: SyslogOptions,
+        network: NetworkOptions,
+        domain: &str,
+    ) -> io::Result<Box<RemoteSyslogLogger>> {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        let tls_config =
+            ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+                .with_safe_default_protocol_versions()
+                .map_err(io::Error::other)?
+                .with_root_certificates(roots)
+                .with_no_client_auth();
+        RemoteSyslogLogger::with_tls_config(
+            log_level,
+            config,
+            facility,
+            addr,
+            options,
+            network,
+            domain,
+            Arc::new(tls_config),
+        )
+    }
+
+    /// Same as [`RemoteSyslogLogger::new_tls`], but uses the given TLS configuration,
+    /// e.g. to trust a private certificate authority or to authenticate with a client certificate.
+    #[cfg(feature = "syslog-tls")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn with_tls_config<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        addr: A,
+        options: SyslogOptions,
+        network: NetworkOptions,
+        domain: &str,
+        tls_config: Arc<ClientConfig>,
+    ) -> io::Result<Box<RemoteSyslogLogger>> {
+        let wrap = tls(tls_config, domain)?;
+        RemoteSyslogLogger::with_wrap(log_level, config, facility, addr, options, network, wrap)
+    }
+
+    fn with_wrap<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        addr: A,
+        options: SyslogOptions,
+        network: NetworkOptions,
+        wrap: Box<Wrap>,
+    ) -> io::Result<Box<RemoteSyslogLogger>> {
+        let app_name = app_name(&options);
+        let connection = Connection::new(addr.to_socket_addrs()?.collect(), wrap, network);
+
+        Ok(Box::new(RemoteSyslogLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            options,
+            facility,
+            app_name,
+            pid: process::id(),
+            connection: Mutex::new(connection),
+        }))
+    }
+
+    /// Frames a formatted record as message in the configured format
+    fn frame(&self, record: &Record<'_>, line: &[u8]) -> Vec<u8> {
+        let priority = self.facility as u8 * 8 + severity(record.level());
+        let mut msg = Vec::new();
+        let format = self.options.format.unwrap_or(SyslogFormat::Rfc5424);
+        match format {
+            SyslogFormat::Rfc3164 => {
+                let _ = write!(msg, "<{}>", priority);
+                let now = OffsetDateTime::now_utc();
+                if now
+                    .format_into(
+                        &mut msg,
+                        format_description!(
+                            "[month repr:short] [day padding:space] [hour]:[minute]:[second]"
+                        ),
+                    )
+                    .is_err()
+                {
+                    msg.push(b'-');
+                }
+                let _ = write!(
+                    msg,
+                    " {} {}[{}]:",
+                    header_field(hostname(&self.config), 255),
+                    self.app_name,
+                    self.pid
+                );
+            }
+            SyslogFormat::Rfc5424 => write_rfc5424_header(
+                &mut msg,
+                priority,
+                record,
+                &self.config,
+                &self.options,
+                &self.app_name,
+                self.pid,
+            ),
+        }
+
+        // the frame delimits the message, so the line ending is not needed
+        let line = trim_line_ending(line);
+        if !line.is_empty() {
+            msg.push(b' ');
+            msg.extend_from_slice(line);
+        }
+
+        match format {
+            // messages are terminated by a line feed, so they must not contain any
+            SyslogFormat::Rfc3164 => {
+                for c in &mut msg {
+                    if *c == b'\n' {
+                        *c = b' ';
+                    }
+                }
+                msg.push(b'\n');
+                msg
+            }
+            SyslogFormat::Rfc5424 => {
+                let mut frame = format!("{} ", msg.len()).into_bytes();
+                frame.append(&mut msg);
+                frame
+            }
+        }
+    }
+}
+
+impl Log for RemoteSyslogLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {
+        while_sending(|| self.connection.lock().unwrap().flush());
+    }
+}
+
+impl SharedLogger for RemoteSyslogLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) && !sending() {
+            with_formatted(&self.config, record, |line| {
+                let frame = self.frame(record, line);
+                while_sending(|| {
+                    let mut connection = self.connection.lock().unwrap();
+                    connection.push(frame);
+                    connection.send();
+                });
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::{scoped_context, ConfigBuilder, ConnectionEvent};
+
+    #[test]
+    fn test_remote_syslog_logger() {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        fn read_frame(stream: &mut std::net::TcpStream) -> String {
+            let mut len = Vec::new();
+            let mut byte = [0];
+            while stream.read_exact(&mut byte).is_ok() && byte[0] != b' ' {
+                len.push(byte[0]);
+            }
+            let len: usize = String::from_utf8(len).unwrap().parse().unwrap();
+            let mut msg = vec![0; len];
+            stream.read_exact(&mut msg).unwrap();
+            String::from_utf8(msg).unwrap()
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Off)
+            .set_hostname("web 1")
+            .build();
+        let logger = RemoteSyslogLogger::new(
+            LevelFilter::Info,
+            config,
+            Facility::Local3,
+            listener.local_addr().unwrap(),
+            SyslogOptions::default(),
+            NetworkOptions::default(),
+        )
+        .unwrap();
+        let (mut stream, _) = listener.accept().unwrap();
+        {
+            let _request = scoped_context("request", "a\"b]");
+            log(&logger, Level::Warn, "hello syslog");
+        }
+        log(&logger, Level::Info, "two\nlines");
+
+        let exe = std::env::current_exe().unwrap();
+        let app: String = exe
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .chars()
+            .take(48)
+            .collect();
+        for (msg, priority, rest) in [
+            (
+                read_frame(&mut stream),
+                "<156>1 ",
+                "[kv@32473 request=\"a\\\"b\\]\"] [WARN] hello syslog request=\"a\\\"b]\"",
+            ),
+            (read_frame(&mut stream), "<158>1 ", "- [INFO] two\nlines"),
+        ] {
+            assert!(msg.starts_with(priority), "{}", msg);
+            // the timestamp is in UTC with microseconds, the space is removed from the hostname
+            let (time, msg) = msg[priority.len()..].split_at(27);
+            assert!(time.ends_with('Z'), "{}", time);
+            assert_eq!(
+                msg,
+                format!(" web1 {} {} - {}", app, std::process::id(), rest)
+            );
+        }
+    }
+
+    #[cfg(feature = "syslog-tls")]
+    #[test]
+    fn test_remote_syslog_logger_tls() {
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let err = RemoteSyslogLogger::new_tls(
+            LevelFilter::Info,
+            Config::default(),
+            Facility::User,
+            addr,
+            SyslogOptions::default(),
+            NetworkOptions::default(),
+            "not a domain",
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        // a server closing the connection fails the handshake
+        let server = std::thread::spawn(move || drop(listener.accept().unwrap()));
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_events = events.clone();
+        let mut network = NetworkOptions::new();
+        network.set_connection_handler(move |event| {
+            handler_events
+                .lock()
+                .unwrap()
+                .push(matches!(event, ConnectionEvent::ConnectFailed(..)))
+        });
+        RemoteSyslogLogger::new_tls(
+            LevelFilter::Info,
+            Config::default(),
+            Facility::User,
+            addr,
+            SyslogOptions::default(),
+            network,
+            "localhost",
+        )
+        .unwrap();
+        server.join().unwrap();
+        assert_eq!(*events.lock().unwrap(), [true]);
+    }
+}
@@ -7,17 +7,15 @@
 
 //! Module providing the SimpleLogger Implementation
 
-use super::logging::try_log;
-use crate::{Config, SharedLogger};
-use log::{
-    set_boxed_logger, set_max_level, Level, LevelFilter, Log, Metadata, Record, SetLoggerError,
-};
-use std::io::{stderr, stdout};
+use super::logging::{enabled, max_level, print_gated, use_stderr, with_formatted, AtomicLevel};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::{self, stderr, stdout, Write};
 use std::sync::Mutex;
 
 /// The SimpleLogger struct. Provides a very basic Logger implementation
 pub struct SimpleLogger {
-    level: LevelFilter,
+    level: AtomicLevel,
     config: Config,
     output_lock: Mutex<()>,
 }
@@ -25,7 +23,7 @@ pub struct SimpleLogger {
 impl SimpleLogger {
     /// init function. Globally initializes the SimpleLogger as the one and only used log facility.
     ///
-    /// Takes the desired `Level` and `Config` as arguments. They cannot be changed later on.
+    /// Takes the desired `Level` and `Config` as arguments. The level can be changed later on using the returned handle.
     /// Fails if another Logger was already initialized.
     ///
     /// # Examples
@@ -36,9 +34,11 @@ impl SimpleLogger {
     /// let _ = SimpleLogger::init(LevelFilter::Info, Config::default());
     /// # }
     /// ```
-    pub fn init(log_level: LevelFilter, config: Config) -> Result<(), SetLoggerError> {
-        set_max_level(log_level);
-        set_boxed_logger(SimpleLogger::new(log_level, config))
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+    ) -> Result<LoggerHandle<SimpleLogger>, SetLoggerError> {
+        handle::init(SimpleLogger::new(log_level, config))
     }
 
     /// allows to create a new logger, that can be independently used, no matter what is globally set.
@@ -46,7 +46,7 @@ impl SimpleLogger {
     /// no macros are provided for this case and you probably
     /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
     ///
-    /// Takes the desired `Level` and `Config` as arguments. They cannot be changed later on.
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
     ///
     /// # Examples
     /// ```
@@ -59,7 +59,7 @@ impl SimpleLogger {
     #[must_use]
     pub fn new(log_level: LevelFilter, config: Config) -> Box<SimpleLogger> {
         Box::new(SimpleLogger {
-            level: log_level,
+            level: AtomicLevel::new(log_level),
             config,
             output_lock: Mutex::new(()),
         })
@@ -68,43 +68,53 @@ impl SimpleLogger {
 
 impl Log for SimpleLogger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= self.level
+        enabled(self.level.get(), &self.config, metadata)
     }
 
     fn log(&self, record: &Record<'_>) {
-        if self.enabled(record.metadata()) {
-            let _lock = self.output_lock.lock().unwrap();
-
-            match record.level() {
-                Level::Error => {
-                    let stderr = stderr();
-                    let mut stderr_lock = stderr.lock();
-                    let _ = try_log(&self.config, record, &mut stderr_lock);
-                }
-                _ => {
-                    let stdout = stdout();
-                    let mut stdout_lock = stdout.lock();
-                    let _ = try_log(&self.config, record, &mut stdout_lock);
-                }
-            }
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
         }
     }
 
     fn flush(&self) {
-        use std::io::Write;
         let _ = stdout().flush();
     }
 }
 
 impl SharedLogger for SimpleLogger {
     fn level(&self) -> LevelFilter {
-        self.level
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
     }
 
     fn config(&self) -> Option<&Config> {
         Some(&self.config)
     }
 
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        let mut res = Ok(());
+        if self.enabled(record.metadata()) {
+            res = with_formatted(&self.config, record, |line| {
+                let _lock = self.output_lock.lock().unwrap();
+
+                let mut res = Ok(());
+                print_gated(&self.config, || {
+                    res = if use_stderr(&self.config, record) {
+                        stderr().lock().write_all(line)
+                    } else {
+                        stdout().lock().write_all(line)
+                    }
+                });
+                res
+            });
+        }
+        res
+    }
+
     fn as_log(self: Box<Self>) -> Box<dyn Log> {
         Box::new(*self)
     }
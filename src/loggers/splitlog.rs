@@ -0,0 +1,169 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the SplitFileLogger Implementation
+
+use super::filelog::{FileLogger, Rotation};
+use crate::{handle, stats, Config, FileError, InitError, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io;
+use std::path::Path;
+
+/// The SplitFileLogger struct. Provides a Logger implementation writing all records into one file
+/// and additionally the warnings and errors into a second one, e.g. `app.log` and `errors.log`.
+///
+/// Both files are [`FileLogger`]s managed by this logger, they are created if necessary,
+/// rotated according to the same [`Rotation`] policy and reopened together.
+/// The level only applies to the first file, the second one always receives
+/// `Warn` and `Error` records, even if the level is set to `Error` or `Off` later on.
+pub struct SplitFileLogger {
+    all: Box<FileLogger>,
+    errors: Box<FileLogger>,
+}
+
+impl SplitFileLogger {
+    /// init function. Globally initializes the SplitFileLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the paths of both files and the `Rotation` policy
+    /// as arguments. The level can be changed later on using the returned handle.
+    /// Fails if a file cannot be opened or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = SplitFileLogger::init(LevelFilter::Info, Config::default(), "app.log", "errors.log", Rotation::Daily);
+    /// # }
+    /// ```
+    pub fn init<P: AsRef<Path>, Q: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        error_path: Q,
+        rotation: Rotation,
+    ) -> Result<LoggerHandle<SplitFileLogger>, InitError> {
+        let logger = SplitFileLogger::new(log_level, config, path, error_path, rotation)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the paths of both files and the `Rotation` policy
+    /// as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// The files are created if they do not exist and appended to otherwise,
+    /// fails with a [`FileError`] naming the path, that could not be opened.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let split_logger = SplitFileLogger::new(LevelFilter::Info, Config::default(), "app.log", "errors.log", Rotation::Daily).unwrap();
+    /// # }
+    /// ```
+    pub fn new<P: AsRef<Path>, Q: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        error_path: Q,
+        rotation: Rotation,
+    ) -> Result<Box<SplitFileLogger>, FileError> {
+        let errors = FileLogger::new(LevelFilter::Warn, config.clone(), error_path, rotation)?;
+        let all = FileLogger::new(log_level, config, path, rotation)?;
+        Ok(Box::new(SplitFileLogger { all, errors }))
+    }
+
+    /// Closes both files and opens the files at their paths again, creating them if necessary
+    ///
+    /// See [`FileLogger::reopen`].
+    pub fn reopen(&self) -> io::Result<()> {
+        self.all.reopen()?;
+        self.errors.reopen()
+    }
+}
+
+impl Log for SplitFileLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.all.enabled(metadata) || self.errors.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {
+        self.all.flush();
+        self.errors.flush();
+    }
+}
+
+impl SharedLogger for SplitFileLogger {
+    fn level(&self) -> LevelFilter {
+        self.all.level().max(self.errors.level())
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.all.set_level(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        self.all.config()
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        let res = self.all.try_log(record);
+        // level filters of the config may enable more verbose levels, which do not belong into the second file
+        if record.level() <= Level::Warn {
+            self.errors.try_log(record)?;
+        }
+        res
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, test_dir};
+    use crate::ConfigBuilder;
+
+    #[test]
+    fn test_split_file_logger() {
+        let dir = test_dir("test_split_file_logger");
+
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let logger = SplitFileLogger::new(
+            LevelFilter::Info,
+            conf,
+            dir.join("app.log"),
+            dir.join("errors.log"),
+            Rotation::Never,
+        )
+        .unwrap();
+        log(&logger, Level::Debug, "hidden");
+        log(&logger, Level::Info, "started");
+        log(&logger, Level::Warn, "slow");
+        logger.set_level(LevelFilter::Off);
+        log(&logger, Level::Error, "failed");
+        logger.flush();
+
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("app.log"), "[INFO] started\n[WARN] slow\n");
+        assert_eq!(read("errors.log"), "[WARN] slow\n[ERROR] failed\n");
+    }
+}
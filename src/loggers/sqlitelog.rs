@@ -0,0 +1,395 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the SqliteLogger Implementation
+
+use super::logging::{
+    enabled, max_level, now, should_skip, throttled, to_config_offset, AtomicLevel,
+};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use rusqlite::{params, Connection};
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use time::format_description::well_known::Rfc3339;
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS logs (\
+    id INTEGER PRIMARY KEY, \
+    timestamp TEXT NOT NULL, \
+    level TEXT NOT NULL, \
+    target TEXT NOT NULL, \
+    file TEXT, \
+    line INTEGER, \
+    message TEXT NOT NULL)";
+const INSERT: &str = "INSERT INTO logs (timestamp, level, target, file, line, message) \
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6)";
+
+/// Settings of the batches inserted by the [`SqliteLogger`]
+#[derive(Debug, Clone)]
+pub struct SqliteOptions {
+    batch_size: usize,
+    batch_interval: Duration,
+    buffer_limit: usize,
+}
+
+impl SqliteOptions {
+    /// Creates the default settings
+    pub fn new() -> SqliteOptions {
+        SqliteOptions {
+            batch_size: 100,
+            batch_interval: Duration::from_secs(5),
+            buffer_limit: 1000,
+        }
+    }
+
+    /// set the amount of records collected, before inserting them in one transaction (default is 100)
+    pub fn set_batch_size(&mut self, size: usize) -> &mut SqliteOptions {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    /// set the maximum time a record waits to be inserted, before an incomplete batch is inserted (default is 5s)
+    pub fn set_batch_interval(&mut self, interval: Duration) -> &mut SqliteOptions {
+        self.batch_interval = interval;
+        self
+    }
+
+    /// set the maximum amount of records kept while inserting fails (default is 1000)
+    ///
+    /// Once the limit is reached, the oldest records are dropped.
+    pub fn set_buffer_limit(&mut self, limit: usize) -> &mut SqliteOptions {
+        self.buffer_limit = limit;
+        self
+    }
+}
+
+impl Default for SqliteOptions {
+    fn default() -> SqliteOptions {
+        SqliteOptions::new()
+    }
+}
+
+/// A record waiting to be inserted
+struct Row {
+    timestamp: String,
+    level: &'static str,
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+    message: String,
+}
+
+/// Open database and the rows waiting to be inserted
+struct Database {
+    connection: Connection,
+    pending: Vec<Row>,
+    /// When the oldest pending row was collected
+    since: Option<Instant>,
+    /// Maximum amount of rows kept while inserting fails
+    limit: usize,
+}
+
+fn sql_error(err: rusqlite::Error) -> io::Error {
+    io::Error::other(err)
+}
+
+impl Database {
+    fn open(path: &Path, limit: usize) -> io::Result<Database> {
+        let connection = Connection::open(path).map_err(sql_error)?;
+        // wait for other connections writing to the database, instead of failing right away
+        connection
+            .busy_timeout(Duration::from_secs(5))
+            .map_err(sql_error)?;
+        connection.execute_batch(CREATE_TABLE).map_err(sql_error)?;
+        Ok(Database {
+            connection,
+            pending: Vec::new(),
+            since: None,
+            limit,
+        })
+    }
+
+    fn insert(&mut self) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut insert = transaction.prepare_cached(INSERT)?;
+            for row in &self.pending {
+                insert.execute(params![
+                    row.timestamp,
+                    row.level,
+                    row.target,
+                    row.file,
+                    row.line,
+                    row.message
+                ])?;
+            }
+        }
+        // dropping the transaction without committing it rolls it back
+        transaction.commit()
+    }
+
+    /// Inserts all pending rows in one transaction.
+    ///
+    /// The rows are kept if that fails, up to the limit, the oldest ones beyond it are dropped
+    /// and counted as write errors.
+    fn commit(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let result = self.insert();
+        if result.is_ok() {
+            self.pending.clear();
+            self.since = None;
+        }
+        let excess = self.pending.len().saturating_sub(self.limit.max(1));
+        self.pending.drain(..excess);
+        for _ in 0..excess {
+            stats::count_write_error();
+        }
+        result.map_err(sql_error)
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        if self.commit().is_err() {
+            // the kept rows are lost for good
+            for _ in 0..self.pending.len() {
+                stats::count_write_error();
+            }
+        }
+    }
+}
+
+/// The SqliteLogger struct. Provides a Logger implementation inserting records into a SQLite database.
+///
+/// Records are inserted into the table `logs` with the columns `timestamp` (RFC 3339), `level`,
+/// `target`, `file`, `line` and `message`, which is created if it does not exist yet.
+/// The records are collected and inserted in one transaction, once a batch is full
+/// (see [`SqliteOptions::set_batch_size`]) or the first record after the oldest collected one
+/// waited long enough (see [`SqliteOptions::set_batch_interval`]), on `flush` and when the logger is dropped.
+///
+/// If inserting fails, e.g. because another connection locks the database for too long, the records are kept
+/// and inserted with the next batch, up to [`SqliteOptions::set_buffer_limit`]. Dropped records are counted
+/// as write errors (see [`stats`](crate::stats)).
+///
+/// The logger links to the SQLite library of the system and requires the `sqlite` feature,
+/// the `sqlite-bundled` feature builds SQLite along with the crate instead.
+pub struct SqliteLogger {
+    level: AtomicLevel,
+    config: Config,
+    options: SqliteOptions,
+    database: Mutex<Database>,
+}
+
+impl SqliteLogger {
+    /// init function. Globally initializes the SqliteLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the path of the database and the settings of the batches as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the database cannot be opened or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = SqliteLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     "logs.db",
+    ///     SqliteOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        options: SqliteOptions,
+    ) -> Result<LoggerHandle<SqliteLogger>, InitError> {
+        let logger = SqliteLogger::new(log_level, config, path, options)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the path of the database and the settings of the batches as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let mut options = SqliteOptions::default();
+    /// options.set_batch_size(500).set_batch_interval(Duration::from_secs(1));
+    /// let sqlite_logger =
+    ///     SqliteLogger::new(LevelFilter::Info, Config::default(), "logs.db", options).unwrap();
+    /// # }
+    /// ```
+    pub fn new<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        options: SqliteOptions,
+    ) -> io::Result<Box<SqliteLogger>> {
+        Ok(Box::new(SqliteLogger {
+            level: AtomicLevel::new(log_level),
+            database: Mutex::new(Database::open(path.as_ref(), options.buffer_limit)?),
+            config,
+            options,
+        }))
+    }
+}
+
+impl Log for SqliteLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        // rows failing to be inserted are kept, only the dropped ones are counted as write errors
+        let _ = self.try_log(record);
+    }
+
+    fn flush(&self) {
+        let _ = self.database.lock().unwrap().commit();
+    }
+}
+
+impl SharedLogger for SqliteLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if !self.enabled(record.metadata()) || should_skip(&self.config, record) {
+            return Ok(());
+        }
+        let mut database = self.database.lock().unwrap();
+        throttled(&self.config, record, |record| {
+            database.pending.push(Row {
+                timestamp: to_config_offset(now(), &self.config)
+                    .format(&Rfc3339)
+                    .unwrap_or_default(),
+                level: record.level().as_str(),
+                target: String::from(record.target()),
+                file: record.file().map(String::from),
+                line: record.line(),
+                message: record.args().to_string(),
+            });
+        });
+
+        if database.pending.is_empty() {
+            return Ok(());
+        }
+        let since = *database.since.get_or_insert_with(Instant::now);
+        if database.pending.len() >= self.options.batch_size
+            || since.elapsed() >= self.options.batch_interval
+        {
+            database.commit()?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, test_dir};
+    use crate::Level;
+
+    #[test]
+    fn test_sqlite_logger() {
+        let path = test_dir("test_sqlite_logger").join("logs.db");
+
+        let mut options = SqliteOptions::default();
+        options.set_batch_size(2);
+        let logger =
+            SqliteLogger::new(LevelFilter::Info, Config::default(), &path, options).unwrap();
+        for i in 0..3 {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("record '{}'", i))
+                    .level(Level::Warn)
+                    .target("sqlite_test")
+                    .file(Some("main.rs"))
+                    .line(Some(i))
+                    .build(),
+            );
+        }
+        // SQLite stores text as it is, so the records can be found in the pages of the database
+        let contents = || String::from_utf8_lossy(&std::fs::read(&path).unwrap()).into_owned();
+        // the first batch is inserted, the third record is still pending
+        assert!(contents().contains("record '1'"));
+        assert!(!contents().contains("record '2'"));
+        drop(logger);
+
+        // reopening continues the existing table
+        let logger = SqliteLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            &path,
+            SqliteOptions::default(),
+        )
+        .unwrap();
+        log(&logger, Level::Error, "after reopening");
+        drop(logger);
+
+        let contents = contents();
+        assert!(contents.contains("record '0'"));
+        assert!(contents.contains("record '2'"));
+        assert!(contents.contains("after reopening"));
+        assert!(contents.contains("CREATE TABLE logs"));
+
+        // rows failing to be inserted are kept, until a later transaction succeeds
+        let logger = SqliteLogger::new(
+            LevelFilter::Info,
+            Config::default(),
+            &path,
+            SqliteOptions::default(),
+        )
+        .unwrap();
+        let other = rusqlite::Connection::open(&path).unwrap();
+        other
+            .execute_batch("ALTER TABLE logs RENAME TO moved")
+            .unwrap();
+        log(&logger, Level::Error, "kept");
+        logger.flush();
+        other
+            .execute_batch("ALTER TABLE moved RENAME TO logs")
+            .unwrap();
+        logger.flush();
+        let kept: u32 = other
+            .query_row(
+                "SELECT COUNT(*) FROM logs WHERE message = 'kept'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(kept, 1);
+    }
+}
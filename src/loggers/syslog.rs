@@ -0,0 +1,348 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the SyslogLogger Implementation
+
+use super::logging::{enabled, max_level, try_log, AtomicLevel};
+use super::remotesyslog::{
+    app_name, severity, trim_line_ending, write_rfc5424_header, Facility, SyslogFormat,
+    SyslogOptions,
+};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::io;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+
+/// Sockets the local syslog daemon usually listens on, in order of preference
+const DEFAULT_SOCKETS: &[&str] = &["/dev/log", "/var/run/syslog", "/var/run/log"];
+
+/// The connection to the syslog daemon
+struct Socket {
+    path: PathBuf,
+    socket: UnixDatagram,
+}
+
+impl Socket {
+    fn connect(path: PathBuf) -> io::Result<Socket> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(&path)?;
+        Ok(Socket { path, socket })
+    }
+
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        if self.socket.send(msg).is_err() {
+            // the daemon may have been restarted, reconnect once and retry
+            *self = Socket::connect(self.path.clone())?;
+            self.socket.send(msg)?;
+        }
+        Ok(())
+    }
+}
+
+/// The SyslogLogger struct. Provides a Logger implementation for the local syslog daemon.
+///
+/// Records are sent as datagrams in the traditional `<PRI>tag[pid]: message` format
+/// or the one of RFC 5424 (see [`SyslogOptions::set_format`]),
+/// with the priority made up of the configured [`Facility`] and the severity
+/// corresponding to the level of the record.
+/// The tag is the file name of the running executable, unless set by [`SyslogOptions::set_app_name`].
+pub struct SyslogLogger {
+    level: AtomicLevel,
+    config: Config,
+    options: SyslogOptions,
+    facility: Facility,
+    tag: String,
+    pid: u32,
+    socket: Mutex<Socket>,
+}
+
+impl SyslogLogger {
+    /// init function. Globally initializes the SyslogLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, `Facility` and the settings of the messages as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if no syslog daemon can be reached or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = SyslogLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     Facility::Daemon,
+    ///     SyslogOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        options: SyslogOptions,
+    ) -> Result<LoggerHandle<SyslogLogger>, InitError> {
+        let logger = SyslogLogger::new(log_level, config, facility, options)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, `Facility` and the settings of the messages as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// Connects to the first of `/dev/log`, `/var/run/syslog` and `/var/run/log` that accepts a connection.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let syslog_logger = SyslogLogger::new(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     Facility::Daemon,
+    ///     SyslogOptions::default(),
+    /// )
+    /// .unwrap();
+    /// # }
+    /// ```
+    pub fn new(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        options: SyslogOptions,
+    ) -> io::Result<Box<SyslogLogger>> {
+        let mut last_err = None;
+        for path in DEFAULT_SOCKETS {
+            match Socket::connect(PathBuf::from(path)) {
+                Ok(socket) => {
+                    return Ok(SyslogLogger::with_socket(
+                        log_level, config, facility, options, socket,
+                    ))
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+
+    /// Same as [`SyslogLogger::new`], but connects to the syslog socket at `path`.
+    pub fn with_socket_path<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        options: SyslogOptions,
+        path: P,
+    ) -> io::Result<Box<SyslogLogger>> {
+        let socket = Socket::connect(path.as_ref().to_path_buf())?;
+        Ok(SyslogLogger::with_socket(
+            log_level, config, facility, options, socket,
+        ))
+    }
+
+    fn with_socket(
+        log_level: LevelFilter,
+        config: Config,
+        facility: Facility,
+        options: SyslogOptions,
+        socket: Socket,
+    ) -> Box<SyslogLogger> {
+        let tag = app_name(&options);
+
+        Box::new(SyslogLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            options,
+            facility,
+            tag,
+            pid: process::id(),
+            socket: Mutex::new(socket),
+        })
+    }
+}
+
+impl Log for SyslogLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for SyslogLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            let priority = self.facility as u8 * 8 + severity(record.level());
+            let mut msg = match self.options.format {
+                Some(SyslogFormat::Rfc5424) => {
+                    let mut msg = Vec::new();
+                    write_rfc5424_header(
+                        &mut msg,
+                        priority,
+                        record,
+                        &self.config,
+                        &self.options,
+                        &self.tag,
+                        self.pid,
+                    );
+                    msg.push(b' ');
+                    msg
+                }
+                _ => format!("<{}>{}[{}]: ", priority, self.tag, self.pid).into_bytes(),
+            };
+            let len = msg.len();
+            try_log(&self.config, record, &mut msg)?;
+            if msg.len() == len {
+                return Ok(());
+            }
+            // syslog terminates messages itself
+            let len = trim_line_ending(&msg).len();
+            msg.truncate(len);
+            self.socket.lock().unwrap().send(&msg)?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, test_dir};
+    use crate::{ConfigBuilder, Level, NetworkOptions, RemoteSyslogLogger};
+
+    #[test]
+    fn test_syslog_logger() {
+        use std::os::unix::net::UnixDatagram;
+
+        let path = test_dir("test_syslog_logger").join("socket");
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Off)
+            .build();
+        let logger = SyslogLogger::with_socket_path(
+            LevelFilter::Info,
+            config,
+            Facility::Local3,
+            SyslogOptions::default(),
+            &path,
+        )
+        .unwrap();
+        log(&logger, Level::Warn, "hello syslog");
+        log(&logger, Level::Debug, "filtered");
+
+        let mut buf = [0; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        let msg = String::from_utf8_lossy(&buf[..len]).into_owned();
+
+        // local3 (19) * 8 + warning (4)
+        assert!(msg.starts_with("<156>"), "{}", msg);
+        assert!(
+            msg.ends_with(&format!("[{}]: [WARN] hello syslog", std::process::id())),
+            "{}",
+            msg
+        );
+        server.set_nonblocking(true).unwrap();
+        assert!(server.recv(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_syslog_format() {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+        use std::os::unix::net::UnixDatagram;
+
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_hostname("web1")
+            .build();
+        let options = |format| {
+            let mut options = SyslogOptions::new();
+            options
+                .set_format(format)
+                .set_app_name("my app")
+                .set_msg_id("ID47");
+            options
+        };
+        let record = Record::builder()
+            .args(format_args!("two\nlines"))
+            .level(Level::Warn)
+            .build();
+
+        let path = test_dir("test_syslog_format").join("socket");
+        let server = UnixDatagram::bind(&path).unwrap();
+        let logger = SyslogLogger::with_socket_path(
+            LevelFilter::Info,
+            config.clone(),
+            Facility::Local3,
+            options(SyslogFormat::Rfc5424),
+            &path,
+        )
+        .unwrap();
+        logger.log(&record);
+        let mut buf = [0; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        let msg = String::from_utf8_lossy(&buf[..len]).into_owned();
+        let (time, msg) = msg["<156>1 ".len()..].split_at(27);
+        assert!(time.ends_with('Z'), "{}", time);
+        assert_eq!(
+            msg,
+            format!(
+                " web1 myapp {} ID47 - [WARN] two\nlines",
+                std::process::id()
+            )
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let logger = RemoteSyslogLogger::new(
+            LevelFilter::Info,
+            config,
+            Facility::Local3,
+            listener.local_addr().unwrap(),
+            options(SyslogFormat::Rfc3164),
+            NetworkOptions::default(),
+        )
+        .unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        logger.log(&record);
+        let msg = BufReader::new(stream).lines().next().unwrap().unwrap();
+        assert!(msg.starts_with("<156>"), "{}", msg);
+        // skip the timestamp, e.g. `Oct  4 09:05:00`
+        assert_eq!(
+            &msg[5 + 15..],
+            format!(" web1 myapp[{}]: [WARN] two lines", std::process::id())
+        );
+    }
+}
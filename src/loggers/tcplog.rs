@@ -0,0 +1,426 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the TcpLogger Implementation
+
+use super::logging::{enabled, max_level, with_formatted, AtomicLevel};
+use crate::{handle, Config, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Timeout for connecting and writing, so a stalled collector cannot block logging forever
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Change of the connection state of a network logger, like the `TcpLogger`
+#[derive(Debug)]
+pub enum ConnectionEvent<'a> {
+    /// A connection to the given address was established
+    Connected(SocketAddr),
+    /// The connection was lost, records are buffered until it is reestablished
+    Disconnected(&'a io::Error),
+    /// Connecting failed, the next attempt is made after the given delay
+    ConnectFailed(&'a io::Error, Duration),
+    /// Sending records failed, they are kept and sent again after the given delay
+    SendFailed(&'a io::Error, Duration),
+    /// The given amount of records were dropped, because the buffer was full while disconnected.
+    /// Reported once the connection is reestablished.
+    Dropped(u64),
+}
+
+/// Callback invoked when the connection state of a network logger changes
+#[derive(Clone)]
+struct ConnectionHandler(Arc<ConnectionFn>);
+
+type ConnectionFn = dyn Fn(&ConnectionEvent<'_>) + Send + Sync;
+
+impl fmt::Debug for ConnectionHandler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ConnectionHandler")
+    }
+}
+
+/// Settings of the connection of network loggers like the [`TcpLogger`]
+///
+/// # Examples
+/// ```no_run
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # use std::time::Duration;
+/// # fn main() {
+/// let mut network = NetworkOptions::default();
+/// network
+///     .set_buffer_limit(10_000)
+///     .set_reconnect_backoff(Duration::from_secs(1), Duration::from_secs(60))
+///     .set_connection_handler(|event| eprintln!("log collector: {:?}", event));
+/// let _ = TcpLogger::init(LevelFilter::Info, Config::default(), "logs.example.com:5170", network);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct NetworkOptions {
+    pub(super) buffer_limit: usize,
+    pub(super) reconnect_backoff: (Duration, Duration),
+    connection_handler: Option<ConnectionHandler>,
+}
+
+impl NetworkOptions {
+    /// Creates the default settings
+    pub fn new() -> NetworkOptions {
+        NetworkOptions {
+            buffer_limit: 1000,
+            reconnect_backoff: (Duration::from_millis(100), Duration::from_secs(30)),
+            connection_handler: None,
+        }
+    }
+
+    /// set the maximum amount of records buffered while disconnected (default is 1000)
+    ///
+    /// Once the limit is reached, the oldest records are dropped.
+    pub fn set_buffer_limit(&mut self, limit: usize) -> &mut NetworkOptions {
+        self.buffer_limit = limit;
+        self
+    }
+
+    /// set the delays between reconnection attempts (default is 100ms to 30s)
+    ///
+    /// The first attempt is made right after the connection is lost, the delay starts at `min`
+    /// and doubles after every failed attempt, up to `max`.
+    pub fn set_reconnect_backoff(&mut self, min: Duration, max: Duration) -> &mut NetworkOptions {
+        self.reconnect_backoff = (min, max.max(min));
+        self
+    }
+
+    /// set a callback, that is invoked when the connection state changes
+    /// (default is to ignore these events)
+    pub fn set_connection_handler<F>(&mut self, handler: F) -> &mut NetworkOptions
+    where
+        F: Fn(&ConnectionEvent<'_>) + Send + Sync + 'static,
+    {
+        self.connection_handler = Some(ConnectionHandler(Arc::new(handler)));
+        self
+    }
+
+    /// Passes `event` to the connection handler, if one is set
+    pub(super) fn report(&self, event: ConnectionEvent<'_>) {
+        if let Some(handler) = &self.connection_handler {
+            (handler.0)(&event);
+        }
+    }
+}
+
+impl Default for NetworkOptions {
+    fn default() -> NetworkOptions {
+        NetworkOptions::new()
+    }
+}
+
+/// Sets up a stream on top of a newly connected socket, e.g. a TLS session
+pub(super) type Wrap = dyn Fn(TcpStream) -> io::Result<Box<dyn Write + Send>> + Send + Sync;
+
+/// Connection to the collector and the records waiting to be sent to it
+pub(super) struct Connection {
+    network: NetworkOptions,
+    addrs: Vec<SocketAddr>,
+    wrap: Box<Wrap>,
+    stream: Option<Box<dyn Write + Send>>,
+    buffer: VecDeque<Vec<u8>>,
+    dropped: u64,
+    backoff: Duration,
+    next_attempt: Instant,
+}
+
+impl Connection {
+    /// Creates a connection to the collector at `addrs` and attempts to connect,
+    /// every connected socket is passed through `wrap`
+    pub(super) fn new(
+        addrs: Vec<SocketAddr>,
+        wrap: Box<Wrap>,
+        network: NetworkOptions,
+    ) -> Connection {
+        let mut connection = Connection {
+            backoff: network.reconnect_backoff.0,
+            network,
+            addrs,
+            wrap,
+            stream: None,
+            buffer: VecDeque::new(),
+            dropped: 0,
+            next_attempt: Instant::now(),
+        };
+        connection.connect();
+        connection
+    }
+
+    fn connect(&mut self) {
+        let mut last_err = None;
+        for addr in &self.addrs {
+            match TcpStream::connect_timeout(addr, TIMEOUT).and_then(|stream| {
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                stream.set_read_timeout(Some(TIMEOUT))?;
+                (self.wrap)(stream)
+            }) {
+                Ok(stream) => {
+                    self.network.report(ConnectionEvent::Connected(*addr));
+                    if self.dropped > 0 {
+                        self.network.report(ConnectionEvent::Dropped(self.dropped));
+                        self.dropped = 0;
+                    }
+                    self.stream = Some(stream);
+                    self.backoff = self.network.reconnect_backoff.0;
+                    return;
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        let err = last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::AddrNotAvailable, "no address to connect to")
+        });
+        self.network
+            .report(ConnectionEvent::ConnectFailed(&err, self.backoff));
+        self.next_attempt = Instant::now() + self.backoff;
+        self.backoff = (self.backoff * 2).min(self.network.reconnect_backoff.1);
+    }
+
+    pub(super) fn push(&mut self, msg: Vec<u8>) {
+        if self.network.buffer_limit == 0 {
+            self.dropped += 1;
+            return;
+        }
+        while self.buffer.len() >= self.network.buffer_limit {
+            self.buffer.pop_front();
+            self.dropped += 1;
+        }
+        self.buffer.push_back(msg);
+    }
+
+    /// Sends as many buffered records as possible
+    pub(super) fn send(&mut self) {
+        if self.stream.is_none() && Instant::now() >= self.next_attempt {
+            self.connect();
+        }
+        while let (Some(stream), Some(msg)) = (self.stream.as_mut(), self.buffer.front()) {
+            match stream.write_all(msg) {
+                Ok(()) => {
+                    self.buffer.pop_front();
+                }
+                Err(err) => {
+                    // a partially written record is sent again with the next attempt,
+                    // the collector is expected to discard the incomplete line
+                    self.network.report(ConnectionEvent::Disconnected(&err));
+                    self.stream = None;
+                    self.next_attempt = Instant::now();
+                }
+            }
+        }
+    }
+
+    /// Sends the buffered records and flushes the stream
+    pub(super) fn flush(&mut self) {
+        self.send();
+        if let Some(stream) = self.stream.as_mut() {
+            let _ = stream.flush();
+        }
+    }
+}
+
+/// The TcpLogger struct. Provides a Logger implementation sending records to a log collector over TCP.
+///
+/// Each record is sent as a line formatted according to the `Config`.
+/// While the collector is unreachable, records are buffered (see [`NetworkOptions::set_buffer_limit`])
+/// and reconnection is attempted with an exponential backoff (see [`NetworkOptions::set_reconnect_backoff`]).
+/// Changes of the connection state are reported to the handler set by
+/// [`NetworkOptions::set_connection_handler`].
+///
+/// Connecting and sending happens on the logging thread, so consider wrapping
+/// this logger into an [`AsyncLogger`](crate::AsyncLogger).
+pub struct TcpLogger {
+    level: AtomicLevel,
+    config: Config,
+    connection: Mutex<Connection>,
+}
+
+impl TcpLogger {
+    /// init function. Globally initializes the TcpLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the address of the collector and the settings of the connection as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the address cannot be resolved or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = TcpLogger::init(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     "logs.example.com:5170",
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        addr: A,
+        network: NetworkOptions,
+    ) -> Result<LoggerHandle<TcpLogger>, InitError> {
+        let logger = TcpLogger::new(log_level, config, addr, network)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the address of the collector and the settings of the connection as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    /// The address is resolved once, if the collector is unreachable for now,
+    /// records are buffered until a connection can be established.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let tcp_logger = TcpLogger::new(
+    ///     LevelFilter::Info,
+    ///     Config::default(),
+    ///     "127.0.0.1:5170",
+    ///     NetworkOptions::default(),
+    /// )
+    /// .unwrap();
+    /// # }
+    /// ```
+    pub fn new<A: ToSocketAddrs>(
+        log_level: LevelFilter,
+        config: Config,
+        addr: A,
+        network: NetworkOptions,
+    ) -> io::Result<Box<TcpLogger>> {
+        let connection = Connection::new(
+            addr.to_socket_addrs()?.collect(),
+            Box::new(|stream| Ok(Box::new(stream))),
+            network,
+        );
+
+        Ok(Box::new(TcpLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            connection: Mutex::new(connection),
+        }))
+    }
+}
+
+impl Log for TcpLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let _ = with_formatted(&self.config, record, |msg| {
+                let mut connection = self.connection.lock().unwrap();
+                connection.push(msg.to_vec());
+                connection.send();
+                Ok(())
+            });
+        }
+    }
+
+    fn flush(&self) {
+        self.connection.lock().unwrap().flush();
+    }
+}
+
+impl SharedLogger for TcpLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::{ConfigBuilder, Level};
+
+    #[test]
+    fn test_tcp_logger() {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let handler_events = events.clone();
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .set_target_level(LevelFilter::Off)
+            .build();
+        let mut network = NetworkOptions::new();
+        network
+            .set_buffer_limit(2)
+            .set_reconnect_backoff(std::time::Duration::ZERO, std::time::Duration::ZERO)
+            .set_connection_handler(move |event| {
+                let name = match event {
+                    ConnectionEvent::Connected(_) => String::from("connected"),
+                    ConnectionEvent::Disconnected(_) => String::from("disconnected"),
+                    ConnectionEvent::ConnectFailed(..) | ConnectionEvent::SendFailed(..) => {
+                        String::from("failed")
+                    }
+                    ConnectionEvent::Dropped(n) => format!("dropped {}", n),
+                };
+                handler_events.lock().unwrap().push(name);
+            });
+        let logger = TcpLogger::new(LevelFilter::Info, config, addr, network).unwrap();
+        let log = |i| log(&logger, Level::Info, &format!("record {}", i));
+
+        // nobody is listening, so records are buffered
+        for i in 0..3 {
+            log(i);
+        }
+        let listener = TcpListener::bind(addr).unwrap();
+        log(3);
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "[INFO] record 2");
+        assert_eq!(lines.next().unwrap().unwrap(), "[INFO] record 3");
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "failed",
+                "failed",
+                "failed",
+                "failed",
+                "connected",
+                "dropped 2"
+            ]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the TeeLogger Implementation
+
+use super::logging::{enabled, max_level, with_formatted, AtomicLevel};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+struct Writers<W1, W2> {
+    first: W1,
+    second: W2,
+}
+
+/// The TeeLogger struct. Provides a Logger implementation duplicating every record into two structs implementing `Write`
+///
+/// Each record is formatted only once and the result is written into both writers,
+/// unlike a `CombinedLogger` of two `WriteLogger`s formatting it twice.
+/// If writing into the first writer fails, the record is still written into the second one.
+///
+/// The writers are written to directly, wrap them into a `BufWriter` to buffer them.
+pub struct TeeLogger<W1: Write + Send + 'static, W2: Write + Send + 'static> {
+    level: AtomicLevel,
+    config: Config,
+    writers: Mutex<Writers<W1, W2>>,
+}
+
+impl<W1: Write + Send + 'static, W2: Write + Send + 'static> TeeLogger<W1, W2> {
+    /// init function. Globally initializes the TeeLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config` and both `Write` structs as arguments. The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let _ = TeeLogger::init(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap(), std::io::stderr());
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        first: W1,
+        second: W2,
+    ) -> Result<LoggerHandle<TeeLogger<W1, W2>>, SetLoggerError> {
+        handle::init(TeeLogger::new(log_level, config, first, second))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config` and both `Write` structs as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::fs::File;
+    /// # fn main() {
+    /// let tee_logger = TeeLogger::new(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap(), Vec::new());
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(
+        log_level: LevelFilter,
+        config: Config,
+        first: W1,
+        second: W2,
+    ) -> Box<TeeLogger<W1, W2>> {
+        Box::new(TeeLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            writers: Mutex::new(Writers { first, second }),
+        })
+    }
+
+    /// Calls `f` with both writers, e.g. to read an in-memory buffer
+    pub fn with_writers<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut W1, &mut W2) -> T,
+    {
+        let mut writers = self.writers.lock().unwrap();
+        let writers = &mut *writers;
+        f(&mut writers.first, &mut writers.second)
+    }
+}
+
+impl<W1: Write + Send + 'static, W2: Write + Send + 'static> Log for TeeLogger<W1, W2> {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {
+        let mut writers = self.writers.lock().unwrap();
+        let _ = writers.first.flush();
+        let _ = writers.second.flush();
+    }
+}
+
+impl<W1: Write + Send + 'static, W2: Write + Send + 'static> SharedLogger for TeeLogger<W1, W2> {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            with_formatted(&self.config, record, |line| {
+                let mut writers = self.writers.lock().unwrap();
+                let first = writers.first.write_all(line);
+                writers.second.write_all(line)?;
+                first
+            })?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, SharedBuf};
+    use crate::{ConfigBuilder, Level};
+
+    #[test]
+    fn test_tee_logger() {
+        let conf = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let buf = SharedBuf::default();
+        let logger = TeeLogger::new(LevelFilter::Info, conf, buf.clone(), Vec::new());
+        for level in [Level::Debug, Level::Info, Level::Error] {
+            log(&logger, level, "tee");
+        }
+
+        assert_eq!(buf.contents(), "[INFO] tee\n[ERROR] tee\n");
+        logger.with_writers(|_, second| assert_eq!(second.as_slice(), buf.contents().as_bytes()));
+    }
+}
@@ -1,53 +1,122 @@
 //! Module providing the TermLogger Implementation
 
-use log::{
-    set_boxed_logger, set_max_level, Level, LevelFilter, Log, Metadata, Record, SetLoggerError,
-};
-use std::io::{Error, Write};
-use std::sync::Mutex;
-use termcolor::{BufferedStandardStream, ColorChoice};
-#[cfg(not(feature = "ansi_term"))]
-use termcolor::{ColorSpec, WriteColor};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::env;
+use std::fmt::Write as _;
+use std::io::{self, Error, IsTerminal, Write};
+use std::path::Path;
+use termcolor::{BufferWriter, ColorChoice, WriteColor};
 
 use super::logging::*;
 
-use crate::{Config, SharedLogger, ThreadLogMode};
+use crate::{handle, stats, Config, LoggerHandle, SharedLogger};
+
+/// A standard stream, records are formatted for and then printed to at once
+struct OutputStream {
+    writer: BufferWriter,
+    stdout: bool,
+}
+
+impl OutputStream {
+    fn new(stdout: bool, color_choice: ColorChoice) -> OutputStream {
+        let writer = match stdout {
+            true => BufferWriter::stdout(stream_choice(color_choice, io::stdout().is_terminal())),
+            false => BufferWriter::stderr(stream_choice(color_choice, io::stderr().is_terminal())),
+        };
+        OutputStream { writer, stdout }
+    }
+
+    /// Prints a formatted record, only locking the stream to copy and flush it
+    fn print(&self, buf: &termcolor::Buffer) -> Result<(), Error> {
+        self.writer.print(buf)?;
+        // The log crate holds the logger as a `static mut`, which isn't dropped
+        // at program exit: https://doc.rust-lang.org/reference/items/static-items.html
+        // Sadly, this means we can't rely on buffered streams flushing
+        // themselves on the way out, so flush each entry.
+        match self.stdout {
+            true => io::stdout().flush(),
+            false => io::stderr().flush(),
+        }
+    }
+}
 
 struct OutputStreams {
-    err: BufferedStandardStream,
-    out: BufferedStandardStream,
+    err: OutputStream,
+    out: OutputStream,
 }
 
 /// Specifies which streams should be used when logging
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum TerminalMode {
     /// Only use Stdout
     Stdout,
     /// Only use Stderr
     Stderr,
-    /// Use Stderr for Errors and Stdout otherwise
+    /// Use Stderr for Errors and Stdout otherwise,
+    /// which levels are written to Stderr can be changed using `ConfigBuilder::set_stderr_level`
+    #[default]
     Mixed,
 }
 
-impl Default for TerminalMode {
-    fn default() -> TerminalMode {
-        TerminalMode::Mixed
+/// Disables colors for a stream, that is not a terminal, unless they are forced
+pub(crate) fn stream_choice(choice: ColorChoice, is_terminal: bool) -> ColorChoice {
+    match choice {
+        ColorChoice::Auto if !is_terminal => ColorChoice::Never,
+        choice => choice,
+    }
+}
+
+/// The URL of `file` and `line` according to a template set by `ConfigBuilder::set_location_link`
+pub(crate) fn location_url(template: &str, file: &str, line: Option<u32>) -> String {
+    let path = Path::new(file);
+    let path = match env::current_dir() {
+        Ok(dir) if !path.is_absolute() => dir.join(path),
+        _ => path.to_path_buf(),
+    };
+    let path = path.to_string_lossy();
+    let mut url = String::new();
+    if !path.starts_with('/') {
+        url.push('/');
+    }
+    for c in path.chars() {
+        match c {
+            '\\' => url.push('/'),
+            c if c.is_ascii_alphanumeric() || "/-_.~:".contains(c) => url.push(c),
+            c => {
+                for byte in c.encode_utf8(&mut [0; 4]).bytes() {
+                    let _ = write!(url, "%{:02X}", byte);
+                }
+            }
+        }
     }
+    template
+        .replace("{path}", &url)
+        .replace("{line}", &line.unwrap_or(1).to_string())
 }
 
 /// The TermLogger struct. Provides a stderr/out based Logger implementation
 ///
-/// Supports colored output
+/// Supports colored output. With `ColorChoice::Auto` colors are only written to streams,
+/// that are connected to a terminal, so output redirected into a file or piped
+/// into another program stays plain. `ColorChoice::Always` (or `ColorChoice::AlwaysAnsi`)
+/// writes colors regardless, e.g. when piping into `less -R` or in CI systems rendering ANSI codes.
 pub struct TermLogger {
-    level: LevelFilter,
+    level: AtomicLevel,
     config: Config,
-    streams: Mutex<OutputStreams>,
+    streams: OutputStreams,
+    /// Whether the terminal may support hyperlinks
+    links: bool,
 }
 
 impl TermLogger {
     /// init function. Globally initializes the TermLogger as the one and only used log facility.
     ///
-    /// Takes the desired `Level` and `Config` as arguments. They cannot be changed later on.
+    /// Takes the desired `Level` and `Config` as arguments. The level can be changed later on using the returned handle.
     /// Fails if another Logger was already initialized
     ///
     /// # Examples
@@ -68,11 +137,8 @@ impl TermLogger {
         config: Config,
         mode: TerminalMode,
         color_choice: ColorChoice,
-    ) -> Result<(), SetLoggerError> {
-        let logger = TermLogger::new(log_level, config, mode, color_choice);
-        set_max_level(log_level);
-        set_boxed_logger(logger)?;
-        Ok(())
+    ) -> Result<LoggerHandle<TermLogger>, SetLoggerError> {
+        handle::init(TermLogger::new(log_level, config, mode, color_choice))
     }
 
     /// allows to create a new logger, that can be independently used, no matter whats globally set.
@@ -80,7 +146,14 @@ impl TermLogger {
     /// no macros are provided for this case and you probably
     /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
     ///
-    /// Takes the desired `Level` and `Config` as arguments. They cannot be changed later on.
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// With `ColorChoice::Auto` the `NO_COLOR`, `CLICOLOR_FORCE` and `CLICOLOR` environment variables
+    /// are honored and colors are disabled for `TERM=dumb` and on CI systems, except for GitHub Actions,
+    /// where they are enabled, unless disabled by `ConfigBuilder::set_honor_color_env`.
+    /// An explicit `ColorChoice::Always` or `ColorChoice::AlwaysAnsi` forces colors, even if
+    /// the streams are not terminals or `NO_COLOR` is set, like a `--color=always` argument would.
+    /// `ColorChoice::Never` disables them, even if `CLICOLOR_FORCE` is set.
     ///
     /// Returns a `Box`ed TermLogger
     ///
@@ -100,143 +173,161 @@ impl TermLogger {
     #[must_use]
     pub fn new(
         log_level: LevelFilter,
-        config: Config,
+        mut config: Config,
         mode: TerminalMode,
         color_choice: ColorChoice,
     ) -> Box<TermLogger> {
+        config.color_theme = config.color_theme.degraded(color_depth());
+        let color_choice = if config.honor_color_env {
+            color_choice_from_env(color_choice, |name| env::var_os(name))
+        } else {
+            color_choice
+        };
+        #[cfg(feature = "ansi_term")]
+        if config.write_log_enable_colors {
+            crate::enable_ansi_support();
+        }
+        let stdout = || OutputStream::new(true, color_choice);
+        let stderr = || OutputStream::new(false, color_choice);
         let streams = match mode {
             TerminalMode::Stdout => OutputStreams {
-                err: BufferedStandardStream::stdout(color_choice),
-                out: BufferedStandardStream::stdout(color_choice),
+                err: stdout(),
+                out: stdout(),
             },
             TerminalMode::Stderr => OutputStreams {
-                err: BufferedStandardStream::stderr(color_choice),
-                out: BufferedStandardStream::stderr(color_choice),
+                err: stderr(),
+                out: stderr(),
             },
             TerminalMode::Mixed => OutputStreams {
-                err: BufferedStandardStream::stderr(color_choice),
-                out: BufferedStandardStream::stdout(color_choice),
+                err: stderr(),
+                out: stdout(),
             },
         };
 
         Box::new(TermLogger {
-            level: log_level,
+            level: AtomicLevel::new(log_level),
+            links: config.location_link.is_some()
+                && env::var_os("TERM").is_none_or(|term| term != "dumb"),
             config,
-            streams: Mutex::new(streams),
+            streams,
         })
     }
 
-    fn try_log_term(
+    /// Formats `record` into `write`, coloring the level and linking the location, if supported
+    pub(crate) fn write_record<W: WriteColor>(
         &self,
         record: &Record<'_>,
-        term_lock: &mut BufferedStandardStream,
+        write: &mut W,
     ) -> Result<(), Error> {
-        #[cfg(not(feature = "ansi_term"))]
-        let color = self.config.level_color[record.level() as usize];
-
-        if self.config.time <= record.level() && self.config.time != LevelFilter::Off {
-            write_time(term_lock, &self.config)?;
-        }
-
-        if self.config.level <= record.level() && self.config.level != LevelFilter::Off {
-            #[cfg(not(feature = "ansi_term"))]
-            if !self.config.write_log_enable_colors {
-                term_lock.set_color(ColorSpec::new().set_fg(color))?;
-            }
-
-            write_level(record, term_lock, &self.config)?;
-
-            #[cfg(not(feature = "ansi_term"))]
-            if !self.config.write_log_enable_colors {
-                term_lock.reset()?;
-            }
-        }
-
-        if self.config.thread <= record.level() && self.config.thread != LevelFilter::Off {
-            match self.config.thread_log_mode {
-                ThreadLogMode::IDs => {
-                    write_thread_id(term_lock, &self.config)?;
-                }
-                ThreadLogMode::Names | ThreadLogMode::Both => {
-                    write_thread_name(term_lock, &self.config)?;
-                }
-            }
-        }
+        write_record_styled(&self.config, record, write, self)
+    }
+}
 
-        if self.config.target <= record.level() && self.config.target != LevelFilter::Off {
-            write_target(record, term_lock, &self.config)?;
+impl<W: WriteColor> FieldStyle<W> for TermLogger {
+    #[cfg_attr(feature = "ansi_term", allow(unused_variables))]
+    fn level<F>(&self, record: &Record<'_>, write: &mut W, write_level: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut W) -> Result<(), Error>,
+    {
+        #[cfg(not(feature = "ansi_term"))]
+        if !self.config.write_log_enable_colors {
+            write.set_color(&level_style(&self.config, record))?;
         }
 
-        if self.config.location <= record.level() && self.config.location != LevelFilter::Off {
-            write_location(record, term_lock)?;
-        }
+        write_level(write)?;
 
-        if self.config.module <= record.level() && self.config.module != LevelFilter::Off {
-            write_module(record, term_lock)?;
+        #[cfg(not(feature = "ansi_term"))]
+        if !self.config.write_log_enable_colors {
+            write.reset()?;
         }
 
-        #[cfg(feature = "paris")]
-        write_args(
-            record,
-            term_lock,
-            self.config.enable_paris_formatting,
-            &self.config.line_ending,
-        )?;
-        #[cfg(not(feature = "paris"))]
-        write_args(record, term_lock, &self.config.line_ending)?;
-
-        // The log crate holds the logger as a `static mut`, which isn't dropped
-        // at program exit: https://doc.rust-lang.org/reference/items/static-items.html
-        // Sadly, this means we can't rely on the BufferedStandardStreams flushing
-        // themselves on the way out, so to avoid the Case of the Missing 8k,
-        // flush each entry.
-        term_lock.flush()
+        Ok(())
     }
 
-    fn try_log(&self, record: &Record<'_>) -> Result<(), Error> {
-        if self.enabled(record.metadata()) {
-            if should_skip(&self.config, record) {
-                return Ok(());
-            }
-
-            let mut streams = self.streams.lock().unwrap();
-
-            if record.level() == Level::Error {
-                self.try_log_term(record, &mut streams.err)
-            } else {
-                self.try_log_term(record, &mut streams.out)
-            }
-        } else {
-            Ok(())
+    fn location<F>(
+        &self,
+        record: &Record<'_>,
+        write: &mut W,
+        write_location: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut W) -> Result<(), Error>,
+    {
+        let url = match &self.config.location_link {
+            Some(template) if self.links && write.supports_color() => record
+                .file()
+                .map(|file| location_url(template, file, record.line())),
+            _ => None,
+        };
+        if let Some(url) = &url {
+            write!(write, "\x1b]8;;{}\x1b\\", url)?;
+        }
+        write_location(write)?;
+        if url.is_some() {
+            write.write_all(b"\x1b]8;;\x1b\\")?;
         }
+        Ok(())
     }
 }
 
 impl Log for TermLogger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= self.level
+        enabled(self.level.get(), &self.config, metadata)
     }
 
     fn log(&self, record: &Record<'_>) {
-        let _ = self.try_log(record);
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
     }
 
     fn flush(&self) {
-        let mut streams = self.streams.lock().unwrap();
-        let _ = streams.out.flush();
-        let _ = streams.err.flush();
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
     }
 }
 
 impl SharedLogger for TermLogger {
     fn level(&self) -> LevelFilter {
-        self.level
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
     }
 
     fn config(&self) -> Option<&Config> {
         Some(&self.config)
     }
 
+    fn try_log(&self, record: &Record<'_>) -> Result<(), Error> {
+        if self.enabled(record.metadata()) {
+            if should_skip(&self.config, record) {
+                return Ok(());
+            }
+
+            let mut res = Ok(());
+            throttled(&self.config, record, |record| {
+                if res.is_err() {
+                    return;
+                }
+                let stream = match use_stderr(&self.config, record) {
+                    true => &self.streams.err,
+                    false => &self.streams.out,
+                };
+                // formatted before taking the lock of the stream, so other threads are not blocked meanwhile
+                let mut buf = stream.writer.buffer();
+                res = self.write_record(record, &mut buf);
+                if res.is_ok() {
+                    print_gated(&self.config, || res = stream.print(&buf));
+                }
+            });
+            res
+        } else {
+            Ok(())
+        }
+    }
+
     fn as_log(self: Box<Self>) -> Box<dyn Log> {
         Box::new(*self)
     }
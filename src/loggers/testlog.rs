@@ -7,22 +7,65 @@
 
 //! Module providing the TestLogger Implementation
 
-use super::logging::should_skip;
-use crate::{config::TimeFormat, Config, LevelPadding, SharedLogger};
-use log::{set_boxed_logger, set_max_level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use super::logging::{
+    enabled, format_rfc3339, hostname, level_label, level_width, location_file, max_level,
+    should_skip, throttled, to_config_offset, write_backtrace, write_key_values, write_record,
+    write_thread_id, write_thread_name, AtomicLevel,
+};
+use crate::{
+    config::TimeFormat, handle, Config, LevelPadding, LoggerHandle, SharedLogger, ThreadLogMode,
+};
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
 
-use std::thread;
+use std::cell::RefCell;
+use std::fmt::Write;
+
+thread_local! {
+    static CAPTURED: RefCell<Vec<CapturedRecord>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A record captured by the [`TestLogger`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedRecord {
+    /// The level of the record
+    pub level: Level,
+    /// The target of the record
+    pub target: String,
+    /// The formatted message of the record
+    pub message: String,
+    /// The source file of the record, if known
+    pub file: Option<String>,
+    /// The line of the record in its source file, if known
+    pub line: Option<u32>,
+}
 
 /// The TestLogger struct. Provides a very basic Logger implementation that may be captured by cargo.
+///
+/// Additionally all printed records are captured and can be inspected using
+/// [`TestLogger::records`] or [`TestLogger::contains`].
+/// Just like the output of `print!()`, captured records are kept per thread,
+/// so every test only sees the records logged by itself.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # #[macro_use] extern crate log;
+/// # use simplelog::*;
+/// # fn main() {
+/// let _ = TestLogger::init(LevelFilter::Info, Config::default());
+/// warn!("connection timeout after {}s", 5);
+/// assert!(TestLogger::contains(Level::Warn, "timeout"));
+/// # }
+/// ```
 pub struct TestLogger {
-    level: LevelFilter,
+    level: AtomicLevel,
     config: Config,
 }
 
 impl TestLogger {
     /// init function. Globally initializes the TestLogger as the one and only used log facility.
     ///
-    /// Takes the desired `Level` and `Config` as arguments. They cannot be changed later on.
+    /// Takes the desired `Level` and `Config` as arguments. The level can be changed later on using the returned handle.
     /// Fails if another Logger was already initialized.
     ///
     /// # Examples
@@ -37,9 +80,11 @@ impl TestLogger {
     /// let _ = TestLogger::init(LevelFilter::Info, Config::default());
     /// # }
     /// ```
-    pub fn init(log_level: LevelFilter, config: Config) -> Result<(), SetLoggerError> {
-        set_max_level(log_level);
-        set_boxed_logger(TestLogger::new(log_level, config))
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+    ) -> Result<LoggerHandle<TestLogger>, SetLoggerError> {
+        handle::init(TestLogger::new(log_level, config))
     }
 
     /// allows to create a new logger, that can be independently used, no matter what is globally set.
@@ -47,7 +92,7 @@ impl TestLogger {
     /// no macros are provided for this case and you probably
     /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
     ///
-    /// Takes the desired `Level` and `Config` as arguments. They cannot be changed later on.
+    /// Takes the desired `Level` and `Config` as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
     ///
     /// # Examples
     /// ```
@@ -64,20 +109,51 @@ impl TestLogger {
     #[must_use]
     pub fn new(log_level: LevelFilter, config: Config) -> Box<TestLogger> {
         Box::new(TestLogger {
-            level: log_level,
+            level: AtomicLevel::new(log_level),
             config,
         })
     }
+
+    /// Returns the records captured on the current thread, oldest first
+    pub fn records() -> Vec<CapturedRecord> {
+        CAPTURED.with(|captured| captured.borrow().clone())
+    }
+
+    /// Checks if a record with the given level, whose message contains `text`, was captured on the current thread
+    pub fn contains(level: Level, text: &str) -> bool {
+        CAPTURED.with(|captured| {
+            captured
+                .borrow()
+                .iter()
+                .any(|record| record.level == level && record.message.contains(text))
+        })
+    }
+
+    /// Removes all records captured on the current thread
+    pub fn clear() {
+        CAPTURED.with(|captured| captured.borrow_mut().clear());
+    }
 }
 
 impl Log for TestLogger {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= self.level
+        enabled(self.level.get(), &self.config, metadata)
     }
 
     fn log(&self, record: &Record<'_>) {
-        if self.enabled(record.metadata()) {
-            let _ = log(&self.config, record);
+        if self.enabled(record.metadata()) && !should_skip(&self.config, record) {
+            throttled(&self.config, record, |record| {
+                CAPTURED.with(|captured| {
+                    captured.borrow_mut().push(CapturedRecord {
+                        level: record.level(),
+                        target: String::from(record.target()),
+                        message: record.args().to_string(),
+                        file: record.file().map(String::from),
+                        line: record.line(),
+                    })
+                });
+                log(&self.config, record);
+            });
         }
     }
 
@@ -86,7 +162,11 @@ impl Log for TestLogger {
 
 impl SharedLogger for TestLogger {
     fn level(&self) -> LevelFilter {
-        self.level
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
     }
 
     fn config(&self) -> Option<&Config> {
@@ -100,92 +180,180 @@ impl SharedLogger for TestLogger {
 
 #[inline(always)]
 pub fn log(config: &Config, record: &Record<'_>) {
-    if should_skip(&config, &record) {
+    if should_skip(config, record) {
         return;
     }
+    // printed at once, so the output of concurrent tests is not interleaved within a line
+    print!("{}", format(config, record));
+}
+
+/// Formats `record` the way the TestLogger prints it
+pub(crate) fn format(config: &Config, record: &Record<'_>) -> String {
+    // only the default positional layout differs from the one of the other loggers
+    if config.formatter.is_some()
+        || config.cef.is_some()
+        || config.format_of(record.level()).is_some()
+    {
+        let mut buf = Vec::new();
+        let _ = write_record(config, record, &mut buf);
+        return String::from_utf8_lossy(&buf).into_owned();
+    }
 
+    let mut out = String::new();
     if config.time <= record.level() && config.time != LevelFilter::Off {
-        write_time(config);
+        write_time(&mut out, config);
+    }
+
+    if config.hostname <= record.level() && config.hostname != LevelFilter::Off {
+        let _ = write!(out, "{} ", hostname(config));
     }
 
     if config.level <= record.level() && config.level != LevelFilter::Off {
-        write_level(record, config);
+        write_level(&mut out, record, config);
+    }
+
+    if config.pid <= record.level() && config.pid != LevelFilter::Off {
+        write_pid(&mut out);
     }
 
-    if config.thread < record.level() && config.thread != LevelFilter::Off {
-        write_thread_id();
+    if config.thread <= record.level() && config.thread != LevelFilter::Off {
+        write_thread(&mut out, config);
     }
 
     if config.target <= record.level() && config.target != LevelFilter::Off {
-        write_target(record);
+        write_target(&mut out, record);
     }
 
     if config.location <= record.level() && config.location != LevelFilter::Off {
-        write_location(record);
+        write_location(&mut out, record, config);
     }
 
     if config.module <= record.level() && config.module != LevelFilter::Off {
-        write_module(record);
+        write_module(&mut out, record);
     }
 
-    write_args(record);
+    write_args(&mut out, record, config);
+    out
 }
 
 #[inline(always)]
-pub fn write_time(config: &Config) {
+pub fn write_time(out: &mut String, config: &Config) {
     use time::format_description::well_known::*;
 
-    let time = time::OffsetDateTime::now_utc().to_offset(config.time_offset);
-    let res = match config.time_format {
+    let time = to_config_offset(time::OffsetDateTime::now_utc(), config);
+    let res = match &config.time_format {
         TimeFormat::Rfc2822 => time.format(&Rfc2822),
         TimeFormat::Rfc3339 => time.format(&Rfc3339),
-        TimeFormat::Custom(format) => time.format(&format),
+        TimeFormat::Rfc3339Precision(precision) => {
+            let mut buf = Vec::new();
+            format_rfc3339(&mut buf, time, *precision)
+                .map(|_| String::from_utf8_lossy(&buf).into_owned())
+        }
+        TimeFormat::Custom(format) => time.format(format),
+        TimeFormat::Owned(format) => time.format(format),
     };
     match res {
-        Ok(time) => print!("{} ", time),
+        Ok(time) => {
+            let _ = write!(out, "{} ", time);
+        }
         Err(err) => panic!("Invalid time format: {}", err),
     };
 }
 
 #[inline(always)]
-pub fn write_level(record: &Record<'_>, config: &Config) {
-    match config.level_padding {
-        LevelPadding::Left => print!("[{: >5}] ", record.level()),
-        LevelPadding::Right => print!("[{: <5}] ", record.level()),
-        LevelPadding::Off => print!("[{}] ", record.level()),
+pub fn write_level(out: &mut String, record: &Record<'_>, config: &Config) {
+    let label = level_label(config, record.level());
+    let _ = match config.level_padding {
+        LevelPadding::Left => write!(out, "[{: >1$}] ", label, level_width(config)),
+        LevelPadding::Right => write!(out, "[{: <1$}] ", label, level_width(config)),
+        LevelPadding::Off => write!(out, "[{}] ", label),
     };
 }
 
 #[inline(always)]
-pub fn write_thread_id() {
-    let id = format!("{:?}", thread::current().id());
-    let id = id.replace("ThreadId(", "");
-    let id = id.replace(")", "");
-    print!("({}) ", id);
+pub fn write_pid(out: &mut String) {
+    let _ = write!(out, "[{}] ", std::process::id());
 }
 
 #[inline(always)]
-pub fn write_target(record: &Record<'_>) {
-    print!("{}: ", record.target());
+pub fn write_thread(out: &mut String, config: &Config) {
+    let mut buf = Vec::new();
+    let _ = match config.thread_log_mode {
+        ThreadLogMode::IDs => write_thread_id(&mut buf, config),
+        ThreadLogMode::Names | ThreadLogMode::Both => write_thread_name(&mut buf, config),
+    };
+    out.push_str(&String::from_utf8_lossy(&buf));
 }
 
 #[inline(always)]
-pub fn write_location(record: &Record<'_>) {
-    let file = record.file().unwrap_or("<unknown>");
-    if let Some(line) = record.line() {
-        print!("[{}:{}] ", file, line);
-    } else {
-        print!("[{}:<unknown>] ", file);
-    }
+pub fn write_target(out: &mut String, record: &Record<'_>) {
+    let _ = write!(out, "{}: ", record.target());
 }
 
 #[inline(always)]
-pub fn write_module(record: &Record<'_>) {
+pub fn write_location(out: &mut String, record: &Record<'_>, config: &Config) {
+    let file = location_file(record, config);
+    let _ = match record.line() {
+        Some(line) => write!(out, "[{}:{}] ", file, line),
+        None => write!(out, "[{}:<unknown>] ", file),
+    };
+}
+
+#[inline(always)]
+pub fn write_module(out: &mut String, record: &Record<'_>) {
     let module = record.module_path().unwrap_or("<unknown>");
-    print!("[{}] ", module);
+    let _ = write!(out, "[{}] ", module);
 }
 
 #[inline(always)]
-pub fn write_args(record: &Record<'_>) {
-    println!("{}", record.args());
+pub fn write_args(out: &mut String, record: &Record<'_>, config: &Config) {
+    let mut trailer = Vec::new();
+    let _ = write_key_values(record, &mut trailer, " ");
+    let _ = write_backtrace(record, &mut trailer, config);
+    let _ = writeln!(
+        out,
+        "{}{}",
+        record.args(),
+        String::from_utf8_lossy(&trailer)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+
+    #[test]
+    fn test_test_logger_capture() {
+        let logger = TestLogger::new(LevelFilter::Info, Config::default());
+        TestLogger::clear();
+        logger.log(
+            &Record::builder()
+                .args(format_args!("connection timeout"))
+                .level(Level::Warn)
+                .target("net")
+                .build(),
+        );
+        log(&logger, Level::Debug, "filtered");
+
+        assert_eq!(
+            TestLogger::records(),
+            [CapturedRecord {
+                level: Level::Warn,
+                target: String::from("net"),
+                message: String::from("connection timeout"),
+                file: None,
+                line: None,
+            }]
+        );
+        assert!(TestLogger::contains(Level::Warn, "timeout"));
+        assert!(!TestLogger::contains(Level::Error, "timeout"));
+
+        // records of other threads are not visible
+        std::thread::spawn(|| assert!(TestLogger::records().is_empty()))
+            .join()
+            .unwrap();
+        TestLogger::clear();
+        assert!(TestLogger::records().is_empty());
+    }
 }
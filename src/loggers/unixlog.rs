@@ -0,0 +1,237 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the UnixSocketLogger Implementation
+
+use super::logging::{enabled, max_level, with_formatted, AtomicLevel};
+use crate::{handle, stats, Config, InitError, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record};
+use std::io::{self, Write};
+use std::os::unix::net::{UnixDatagram, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Timeout for writing, so a stalled collector cannot block logging forever
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Type of the Unix domain socket a `UnixSocketLogger` connects to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnixSocketKind {
+    /// A stream socket, records are written one after another
+    Stream,
+    /// A datagram socket, every record is sent as one datagram
+    Datagram,
+}
+
+enum Connection {
+    Stream(UnixStream),
+    Datagram(UnixDatagram),
+}
+
+/// The connection to the collector
+struct Socket {
+    path: PathBuf,
+    kind: UnixSocketKind,
+    connection: Connection,
+}
+
+impl Socket {
+    fn connect(path: PathBuf, kind: UnixSocketKind) -> io::Result<Socket> {
+        let connection = match kind {
+            UnixSocketKind::Stream => {
+                let stream = UnixStream::connect(&path)?;
+                stream.set_write_timeout(Some(TIMEOUT))?;
+                Connection::Stream(stream)
+            }
+            UnixSocketKind::Datagram => {
+                let socket = UnixDatagram::unbound()?;
+                socket.connect(&path)?;
+                socket.set_write_timeout(Some(TIMEOUT))?;
+                Connection::Datagram(socket)
+            }
+        };
+        Ok(Socket {
+            path,
+            kind,
+            connection,
+        })
+    }
+
+    fn write(&mut self, msg: &[u8]) -> io::Result<()> {
+        match &mut self.connection {
+            Connection::Stream(stream) => stream.write_all(msg),
+            Connection::Datagram(socket) => socket.send(msg).map(|_| ()),
+        }
+    }
+
+    fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        if self.write(msg).is_err() {
+            // the collector may have been restarted, reconnect once and retry
+            *self = Socket::connect(self.path.clone(), self.kind)?;
+            self.write(msg)?;
+        }
+        Ok(())
+    }
+}
+
+/// The UnixSocketLogger struct. Provides a Logger implementation writing records to a Unix domain socket.
+///
+/// Each record is written as a line formatted according to the `Config`, e.g. to the socket
+/// of a local collector like Vector or fluent-bit. Stream sockets receive the records
+/// one after another, datagram sockets one datagram per record.
+/// If writing fails, e.g. because the collector was restarted, the socket is reconnected once
+/// and the record is written again, otherwise it is dropped.
+pub struct UnixSocketLogger {
+    level: AtomicLevel,
+    config: Config,
+    socket: Mutex<Socket>,
+}
+
+impl UnixSocketLogger {
+    /// init function. Globally initializes the UnixSocketLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the path of the socket and its kind as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the socket cannot be connected to or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = UnixSocketLogger::init(LevelFilter::Info, Config::default(), "/run/vector.sock", UnixSocketKind::Stream);
+    /// # }
+    /// ```
+    pub fn init<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        kind: UnixSocketKind,
+    ) -> Result<LoggerHandle<UnixSocketLogger>, InitError> {
+        let logger = UnixSocketLogger::new(log_level, config, path, kind)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the path of the socket and its kind as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let unix_logger = UnixSocketLogger::new(LevelFilter::Info, Config::default(), "/run/fluent-bit.sock", UnixSocketKind::Datagram).unwrap();
+    /// # }
+    /// ```
+    pub fn new<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+        kind: UnixSocketKind,
+    ) -> io::Result<Box<UnixSocketLogger>> {
+        let socket = Socket::connect(path.as_ref().to_path_buf(), kind)?;
+        Ok(Box::new(UnixSocketLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            socket: Mutex::new(socket),
+        }))
+    }
+}
+
+impl Log for UnixSocketLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for UnixSocketLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            with_formatted(&self.config, record, |msg| {
+                self.socket.lock().unwrap().send(msg)
+            })?;
+        }
+        Ok(())
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{log, test_dir};
+    use crate::{ConfigBuilder, Level};
+
+    #[test]
+    fn test_unix_socket_logger() {
+        use std::io::{BufRead, BufReader};
+        use std::os::unix::net::{UnixDatagram, UnixListener};
+
+        let config = ConfigBuilder::new()
+            .set_time_level(LevelFilter::Off)
+            .build();
+        let dir = test_dir("test_unix_socket_logger");
+
+        let path = dir.join("stream");
+        let listener = UnixListener::bind(&path).unwrap();
+        let logger = UnixSocketLogger::new(
+            LevelFilter::Info,
+            config.clone(),
+            &path,
+            UnixSocketKind::Stream,
+        )
+        .unwrap();
+        let (stream, _) = listener.accept().unwrap();
+        log(&logger, Level::Info, "first");
+        log(&logger, Level::Info, "second");
+        let mut lines = BufReader::new(stream).lines();
+        assert_eq!(lines.next().unwrap().unwrap(), "[INFO] first");
+        assert_eq!(lines.next().unwrap().unwrap(), "[INFO] second");
+
+        let path = dir.join("dgram");
+        let server = UnixDatagram::bind(&path).unwrap();
+        let logger =
+            UnixSocketLogger::new(LevelFilter::Info, config, &path, UnixSocketKind::Datagram)
+                .unwrap();
+        log(&logger, Level::Info, "first");
+        log(&logger, Level::Info, "second");
+        let mut buf = [0; 1024];
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"[INFO] first\n");
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"[INFO] second\n");
+    }
+}
@@ -0,0 +1,383 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the WebhookLogger Implementation
+
+use super::logging::{
+    enabled, max_level, sending, should_skip, throttled, while_sending, write_json_template,
+    AtomicLevel,
+};
+use super::tcplog::{ConnectionEvent, NetworkOptions};
+use crate::config::{parse_format, FormatSegment};
+use crate::FormatError;
+use crate::{handle, Config, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Timeout of a single request, so a stalled webhook cannot block the worker forever
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Template used, if none is set by `WebhookOptions::set_template`
+const DEFAULT_TEMPLATE: &str = "{{\"text\":\"[{level}] {target}: {message}\"}}";
+
+/// Settings of the requests posted by the [`WebhookLogger`]
+#[derive(Debug, Clone)]
+pub struct WebhookOptions {
+    template: Vec<FormatSegment>,
+}
+
+impl WebhookOptions {
+    /// Creates the default settings
+    pub fn new() -> WebhookOptions {
+        WebhookOptions {
+            template: parse_format(DEFAULT_TEMPLATE).expect("valid default template"),
+        }
+    }
+
+    /// set the template of the JSON body (default is `{{"text":"[{level}] {target}: {message}"}}`)
+    ///
+    /// The template uses the fields of [`ConfigBuilder::set_format`], their values are escaped
+    /// to be placed inside of a JSON string. Literal braces have to be doubled, e.g.
+    /// `{{"content":"{level}: {message}"}}` results in `{"content":"ERROR: disk full"}`.
+    ///
+    /// [`ConfigBuilder::set_format`]: crate::ConfigBuilder::set_format
+    pub fn set_template(&mut self, template: &str) -> Result<&mut WebhookOptions, FormatError> {
+        self.template = parse_format(template)?;
+        Ok(self)
+    }
+}
+
+impl Default for WebhookOptions {
+    fn default() -> WebhookOptions {
+        WebhookOptions::new()
+    }
+}
+
+/// Bodies waiting to be posted
+struct Queue {
+    bodies: VecDeque<Vec<u8>>,
+    /// When posting is retried after a failure
+    retry_at: Option<Instant>,
+    backoff: Duration,
+    dropped: u64,
+    closed: bool,
+}
+
+/// State shared between the logger and its worker thread
+struct Poster {
+    config: Config,
+    network: NetworkOptions,
+    url: String,
+    agent: ureq::Agent,
+    queue: Mutex<Queue>,
+    changed: Condvar,
+}
+
+impl Poster {
+    /// Posts the pending bodies one by one, stopping at the first failure
+    fn post<'a>(&'a self, mut queue: MutexGuard<'a, Queue>) -> MutexGuard<'a, Queue> {
+        while let Some(body) = queue.bodies.pop_front() {
+            drop(queue);
+            let result = while_sending(|| {
+                self.agent
+                    .post(&self.url)
+                    .set("Content-Type", "application/json")
+                    .send_bytes(&body)
+                    .map_err(|err| io::Error::other(err.to_string()))
+            });
+            queue = self.queue.lock().unwrap();
+
+            match result {
+                Ok(_) => {
+                    queue.retry_at = None;
+                    queue.backoff = self.network.reconnect_backoff.0;
+                    if queue.dropped > 0 {
+                        self.network.report(ConnectionEvent::Dropped(queue.dropped));
+                        queue.dropped = 0;
+                    }
+                }
+                Err(err) => {
+                    let backoff = queue.backoff;
+                    self.network
+                        .report(ConnectionEvent::SendFailed(&err, backoff));
+                    queue.retry_at = Some(Instant::now() + backoff);
+                    queue.backoff = (backoff * 2).min(self.network.reconnect_backoff.1);
+                    queue.bodies.push_front(body);
+                    while queue.bodies.len() > self.network.buffer_limit.max(1) {
+                        queue.bodies.pop_back();
+                        queue.dropped += 1;
+                    }
+                    break;
+                }
+            }
+        }
+        queue
+    }
+
+    fn run(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        loop {
+            if queue.closed {
+                if !queue.bodies.is_empty() {
+                    drop(self.post(queue));
+                }
+                return;
+            }
+
+            let now = Instant::now();
+            queue = match (queue.bodies.is_empty(), queue.retry_at) {
+                (true, _) => self.changed.wait(queue).unwrap(),
+                (false, Some(retry_at)) if retry_at > now => {
+                    self.changed.wait_timeout(queue, retry_at - now).unwrap().0
+                }
+                (false, _) => self.post(queue),
+            };
+        }
+    }
+}
+
+/// The WebhookLogger struct. Provides a Logger implementation posting records to a webhook,
+/// e.g. of Slack, Discord or Microsoft Teams, to use them as alerts.
+///
+/// Every record is posted as its own request with a JSON body built from the template set by
+/// [`WebhookOptions::set_template`], by default `{"text":"[<level>] <target>: <message>"}`,
+/// as understood by Slack and Teams. Usually this logger is created with the `Error` or `Warn` level
+/// and combined with other loggers using a `CombinedLogger`.
+///
+/// Requests are made from a background thread. If a request fails, the records
+/// are kept (up to [`NetworkOptions::set_buffer_limit`]) and posted again with the
+/// backoff set by [`NetworkOptions::set_reconnect_backoff`]. Failures are reported to the handler
+/// set by [`NetworkOptions::set_connection_handler`]. Pending records are posted on `flush`
+/// and when the logger is dropped.
+/// The records logged by `ureq` while posting are dropped by this logger, so they cannot cause
+/// further requests.
+pub struct WebhookLogger {
+    level: AtomicLevel,
+    template: Vec<FormatSegment>,
+    poster: Arc<Poster>,
+    worker: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl WebhookLogger {
+    /// init function. Globally initializes the WebhookLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config`, the URL of the webhook, the settings of the requests
+    /// and of the connection as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = WebhookLogger::init(
+    ///     LevelFilter::Error,
+    ///     Config::default(),
+    ///     "https://hooks.slack.com/services/T000/B000/XXXX",
+    ///     WebhookOptions::default(),
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        url: &str,
+        options: WebhookOptions,
+        network: NetworkOptions,
+    ) -> Result<LoggerHandle<WebhookLogger>, SetLoggerError> {
+        handle::init(WebhookLogger::new(log_level, config, url, options, network))
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config`, the URL of the webhook, the settings of the requests
+    /// and of the connection as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// // Discord expects the message in the `content` field
+    /// let mut options = WebhookOptions::new();
+    /// options
+    ///     .set_template("{{\"content\":\"**{level}** {message}\"}}")
+    ///     .unwrap();
+    /// let webhook_logger = WebhookLogger::new(
+    ///     LevelFilter::Error,
+    ///     Config::default(),
+    ///     "https://discord.com/api/webhooks/0/XXXX",
+    ///     options,
+    ///     NetworkOptions::default(),
+    /// );
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn new(
+        log_level: LevelFilter,
+        config: Config,
+        url: &str,
+        options: WebhookOptions,
+        network: NetworkOptions,
+    ) -> Box<WebhookLogger> {
+        let backoff = network.reconnect_backoff.0;
+        let poster = Arc::new(Poster {
+            config,
+            network,
+            url: String::from(url),
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            queue: Mutex::new(Queue {
+                bodies: VecDeque::new(),
+                retry_at: None,
+                backoff,
+                dropped: 0,
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let worker_poster = poster.clone();
+        let worker = thread::Builder::new()
+            .name(String::from("simplelog-webhook"))
+            .spawn(move || worker_poster.run())
+            .expect("failed to spawn thread");
+
+        Box::new(WebhookLogger {
+            level: AtomicLevel::new(log_level),
+            template: options.template,
+            poster,
+            worker: Mutex::new(Some(worker)),
+        })
+    }
+}
+
+impl Log for WebhookLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.poster.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let config = &self.poster.config;
+        if self.enabled(record.metadata()) && !sending() && !should_skip(config, record) {
+            throttled(config, record, |record| {
+                let mut body = Vec::new();
+                if write_json_template(&self.template, record, &mut body, config).is_err() {
+                    return;
+                }
+                let mut queue = self.poster.queue.lock().unwrap();
+                if queue.bodies.len() >= self.poster.network.buffer_limit.max(1) {
+                    queue.bodies.pop_front();
+                    queue.dropped += 1;
+                }
+                queue.bodies.push_back(body);
+                self.poster.changed.notify_one();
+            });
+        }
+    }
+
+    fn flush(&self) {
+        let queue = self.poster.queue.lock().unwrap();
+        if !queue.bodies.is_empty() {
+            drop(self.poster.post(queue));
+        }
+    }
+}
+
+impl Drop for WebhookLogger {
+    fn drop(&mut self) {
+        self.poster.queue.lock().unwrap().closed = true;
+        self.poster.changed.notify_one();
+        if let Some(worker) = self.worker.lock().unwrap().take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl SharedLogger for WebhookLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.poster.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.poster.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::Level;
+
+    #[test]
+    fn test_webhook_logger() {
+        use std::io::{BufRead, BufReader, Read, Write};
+        use std::net::TcpListener;
+
+        assert!(WebhookOptions::new()
+            .set_template("{{\"text\":\"{nope}\"}}")
+            .is_err());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let mut options = WebhookOptions::new();
+        options
+            .set_template("{{\"content\":\"{level}: {message}\"}}")
+            .unwrap();
+        let logger = WebhookLogger::new(
+            LevelFilter::Error,
+            Config::default(),
+            &url,
+            options,
+            NetworkOptions::default(),
+        );
+        log(&logger, Level::Error, "disk \"/\" full");
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.to_lowercase().strip_prefix("content-length: ") {
+                length = value.trim().parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        (&stream)
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        drop(logger);
+
+        assert_eq!(
+            String::from_utf8(body).unwrap(),
+            "{\"content\":\"ERROR: disk \\\"/\\\" full\"}"
+        );
+    }
+}
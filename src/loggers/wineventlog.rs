@@ -0,0 +1,193 @@
+// Copyright 2016 Victor Brekenfeld
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Module providing the WinEventLogger Implementation
+
+use super::logging::{enabled, max_level, try_log, AtomicLevel};
+use crate::{handle, Config, InitError, LoggerHandle, SharedLogger};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::ffi::OsStr;
+use std::io;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use windows_sys::Win32::Foundation::HANDLE;
+use windows_sys::Win32::System::EventLog::{
+    DeregisterEventSource, RegisterEventSourceW, ReportEventW, EVENTLOG_ERROR_TYPE,
+    EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE, REPORT_EVENT_TYPE,
+};
+
+/// Handle of a registered event source
+struct EventSource(HANDLE);
+
+// Event log handles may be used from any thread
+unsafe impl Send for EventSource {}
+unsafe impl Sync for EventSource {}
+
+impl Drop for EventSource {
+    fn drop(&mut self) {
+        unsafe {
+            DeregisterEventSource(self.0);
+        }
+    }
+}
+
+/// Maps a `Level` to the matching event type, there are no event types below information
+fn event_type(level: Level) -> REPORT_EVENT_TYPE {
+    match level {
+        Level::Error => EVENTLOG_ERROR_TYPE,
+        Level::Warn => EVENTLOG_WARNING_TYPE,
+        Level::Info | Level::Debug | Level::Trace => EVENTLOG_INFORMATION_TYPE,
+    }
+}
+
+fn to_wide(s: &OsStr) -> Vec<u16> {
+    s.encode_wide().chain(Some(0)).collect()
+}
+
+/// The WinEventLogger struct. Provides a Logger implementation for the Windows Event Log.
+///
+/// Records are reported to the "Application" log under the given source name,
+/// errors and warnings as the respective event types and all other levels as information events.
+///
+/// If the source is not registered in the registry (which requires administrative rights
+/// and is usually done by the installer of the service), the Event Viewer will prefix
+/// the records with a hint about the missing message file, but still show their content.
+pub struct WinEventLogger {
+    level: AtomicLevel,
+    config: Config,
+    source: EventSource,
+}
+
+impl WinEventLogger {
+    /// init function. Globally initializes the WinEventLogger as the one and only used log facility.
+    ///
+    /// Takes the desired `Level`, `Config` and the name of the event source as arguments.
+    /// The level can be changed later on using the returned handle.
+    /// Fails if the event source cannot be opened or another Logger was already initialized.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let _ = WinEventLogger::init(LevelFilter::Info, Config::default(), "MyService");
+    /// # }
+    /// ```
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        source: &str,
+    ) -> Result<LoggerHandle<WinEventLogger>, InitError> {
+        let logger = WinEventLogger::new(log_level, config, source)?;
+        Ok(handle::init(logger)?)
+    }
+
+    /// allows to create a new logger, that can be independently used, no matter what is globally set.
+    ///
+    /// no macros are provided for this case and you probably
+    /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
+    ///
+    /// Takes the desired `Level`, `Config` and the name of the event source as arguments.
+    /// Only the level can be changed later on, using `SharedLogger::set_level`.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let event_logger = WinEventLogger::new(LevelFilter::Info, Config::default(), "MyService").unwrap();
+    /// # }
+    /// ```
+    pub fn new(
+        log_level: LevelFilter,
+        config: Config,
+        source: &str,
+    ) -> io::Result<Box<WinEventLogger>> {
+        let name = to_wide(OsStr::new(source));
+        let handle = unsafe { RegisterEventSourceW(ptr::null(), name.as_ptr()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Box::new(WinEventLogger {
+            level: AtomicLevel::new(log_level),
+            config,
+            source: EventSource(handle),
+        }))
+    }
+}
+
+impl Log for WinEventLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        enabled(self.level.get(), &self.config, metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let mut msg = Vec::new();
+            if try_log(&self.config, record, &mut msg).is_err() || msg.is_empty() {
+                return;
+            }
+            while matches!(msg.last(), Some(b'\n') | Some(b'\r')) {
+                msg.pop();
+            }
+            let msg = to_wide(OsStr::new(&*String::from_utf8_lossy(&msg)));
+            let strings = [msg.as_ptr()];
+
+            unsafe {
+                ReportEventW(
+                    self.source.0,
+                    event_type(record.level()),
+                    0,
+                    0,
+                    ptr::null_mut(),
+                    1,
+                    0,
+                    strings.as_ptr(),
+                    ptr::null(),
+                );
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for WinEventLogger {
+    fn level(&self) -> LevelFilter {
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
+    }
+
+    fn config(&self) -> Option<&Config> {
+        Some(&self.config)
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::log;
+    use crate::Level;
+
+    #[test]
+    fn test_win_event_logger() {
+        let logger =
+            WinEventLogger::new(LevelFilter::Info, Config::default(), "simplelog-test").unwrap();
+        for level in &[Level::Error, Level::Warn, Level::Info] {
+            log(&logger, *level, "test event");
+        }
+        logger.flush();
+    }
+}
@@ -7,23 +7,55 @@
 
 //! Module providing the FileLogger Implementation
 
-use super::logging::try_log;
-use crate::{Config, SharedLogger};
-use log::{set_boxed_logger, set_max_level, LevelFilter, Log, Metadata, Record, SetLoggerError};
-use std::io::Write;
+use super::logging::{enabled, max_level, open_file, with_formatted, AtomicLevel};
+use crate::{handle, stats, Config, FileError, FlushPolicy, LoggerHandle, SharedLogger};
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
 use std::sync::Mutex;
+use std::time::Instant;
+
+/// Size of the buffer, after which it is written into the writer regardless of the policy
+const BUFFER_LIMIT: usize = 8 * 1024;
+
+struct Buffered<W: Write> {
+    writer: W,
+    buffer: Vec<u8>,
+    /// Records buffered since the buffer was last written
+    pending: usize,
+    last_flush: Instant,
+}
+
+impl<W: Write> Buffered<W> {
+    /// Writes the buffered records into the writer, without flushing the writer itself
+    fn write_buffer(&mut self) -> io::Result<()> {
+        self.pending = 0;
+        self.last_flush = Instant::now();
+        let res = self.writer.write_all(&self.buffer);
+        self.buffer.clear();
+        res
+    }
+}
 
 /// The WriteLogger struct. Provides a Logger implementation for structs implementing `Write`, e.g. File
+///
+/// By default every record is written into the writer right away. Records can be buffered instead,
+/// to be written according to the policy set by [`ConfigBuilder::set_flush_policy`].
+/// The writer itself, e.g. a `BufWriter` or an encoder, is only flushed when the logger is flushed.
+/// Dropping the logger writes the buffered records and flushes the writer.
+///
+/// [`ConfigBuilder::set_flush_policy`]: crate::ConfigBuilder::set_flush_policy
 pub struct WriteLogger<W: Write + Send + 'static> {
-    level: LevelFilter,
+    level: AtomicLevel,
     config: Config,
-    writable: Mutex<W>,
+    writable: Mutex<Buffered<W>>,
 }
 
 impl<W: Write + Send + 'static> WriteLogger<W> {
     /// init function. Globally initializes the WriteLogger as the one and only used log facility.
     ///
-    /// Takes the desired `Level`, `Config` and `Write` struct as arguments. They cannot be changed later on.
+    /// Takes the desired `Level`, `Config` and `Write` struct as arguments. The level can be changed later on using the returned handle.
     /// Fails if another Logger was already initialized.
     ///
     /// # Examples
@@ -35,9 +67,12 @@ impl<W: Write + Send + 'static> WriteLogger<W> {
     /// let _ = WriteLogger::init(LevelFilter::Info, Config::default(), File::create("my_rust_bin.log").unwrap());
     /// # }
     /// ```
-    pub fn init(log_level: LevelFilter, config: Config, writable: W) -> Result<(), SetLoggerError> {
-        set_max_level(log_level);
-        set_boxed_logger(WriteLogger::new(log_level, config, writable))
+    pub fn init(
+        log_level: LevelFilter,
+        config: Config,
+        writable: W,
+    ) -> Result<LoggerHandle<WriteLogger<W>>, SetLoggerError> {
+        handle::init(WriteLogger::new(log_level, config, writable))
     }
 
     /// allows to create a new logger, that can be independently used, no matter what is globally set.
@@ -45,7 +80,7 @@ impl<W: Write + Send + 'static> WriteLogger<W> {
     /// no macros are provided for this case and you probably
     /// dont want to use this function, but `init()`, if you dont want to build a `CombinedLogger`.
     ///
-    /// Takes the desired `Level`, `Config` and `Write` struct as arguments. They cannot be changed later on.
+    /// Takes the desired `Level`, `Config` and `Write` struct as arguments. Only the level can be changed later on, using `SharedLogger::set_level`.
     ///
     /// # Examples
     /// ```
@@ -59,39 +94,114 @@ impl<W: Write + Send + 'static> WriteLogger<W> {
     #[must_use]
     pub fn new(log_level: LevelFilter, config: Config, writable: W) -> Box<WriteLogger<W>> {
         Box::new(WriteLogger {
-            level: log_level,
+            level: AtomicLevel::new(log_level),
             config,
-            writable: Mutex::new(writable),
+            writable: Mutex::new(Buffered {
+                writer: writable,
+                buffer: Vec::new(),
+                pending: 0,
+                last_flush: Instant::now(),
+            }),
         })
     }
 }
 
+impl WriteLogger<File> {
+    /// Creates a WriteLogger writing to the file at `path`
+    ///
+    /// Takes the desired `Level`, `Config` and the path of the log file as arguments.
+    /// The file is appended to or truncated and missing parent directories are created,
+    /// as set by [`ConfigBuilder::set_file_truncate`] and [`ConfigBuilder::set_create_dirs`].
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # fn main() {
+    /// let config = ConfigBuilder::new().set_create_dirs(true).build();
+    /// let file_logger = WriteLogger::create(LevelFilter::Info, config, "logs/my_rust_bin.log").unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`ConfigBuilder::set_file_truncate`]: crate::ConfigBuilder::set_file_truncate
+    /// [`ConfigBuilder::set_create_dirs`]: crate::ConfigBuilder::set_create_dirs
+    pub fn create<P: AsRef<Path>>(
+        log_level: LevelFilter,
+        config: Config,
+        path: P,
+    ) -> Result<Box<WriteLogger<File>>, FileError> {
+        let file = open_file(path.as_ref(), &config)?;
+        Ok(WriteLogger::new(log_level, config, file))
+    }
+}
+
 impl<W: Write + Send + 'static> Log for WriteLogger<W> {
     fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        metadata.level() <= self.level
+        enabled(self.level.get(), &self.config, metadata)
     }
 
     fn log(&self, record: &Record<'_>) {
-        if self.enabled(record.metadata()) {
-            let mut write_lock = self.writable.lock().unwrap();
-            let _ = try_log(&self.config, record, &mut *write_lock);
+        if self.try_log(record).is_err() {
+            stats::count_write_error();
         }
     }
 
     fn flush(&self) {
-        let _ = self.writable.lock().unwrap().flush();
+        let mut buffered = self.writable.lock().unwrap();
+        if buffered.write_buffer().is_ok() {
+            let _ = buffered.writer.flush();
+        }
+    }
+}
+
+impl<W: Write + Send + 'static> Drop for WriteLogger<W> {
+    fn drop(&mut self) {
+        if let Ok(buffered) = self.writable.get_mut() {
+            if buffered.write_buffer().is_ok() {
+                let _ = buffered.writer.flush();
+            }
+        }
     }
 }
 
 impl<W: Write + Send + 'static> SharedLogger for WriteLogger<W> {
     fn level(&self) -> LevelFilter {
-        self.level
+        max_level(self.level.get(), &self.config)
+    }
+
+    fn set_level(&self, level: LevelFilter) {
+        self.level.set(level);
     }
 
     fn config(&self) -> Option<&Config> {
         Some(&self.config)
     }
 
+    fn try_log(&self, record: &Record<'_>) -> io::Result<()> {
+        if self.enabled(record.metadata()) {
+            with_formatted(&self.config, record, |line| {
+                let mut buffered = self.writable.lock().unwrap();
+                if self.config.flush_policy == FlushPolicy::EveryRecord {
+                    return buffered.writer.write_all(line);
+                }
+                buffered.buffer.extend_from_slice(line);
+                buffered.pending += 1;
+
+                let flush = match self.config.flush_policy {
+                    FlushPolicy::EveryRecord => true,
+                    FlushPolicy::EveryRecords(records) => buffered.pending >= records,
+                    FlushPolicy::Interval(interval) => buffered.last_flush.elapsed() >= interval,
+                    FlushPolicy::AtLevel(level) => record.level() <= level,
+                };
+                if flush || buffered.buffer.len() >= BUFFER_LIMIT {
+                    buffered.write_buffer()?;
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
     fn as_log(self: Box<Self>) -> Box<dyn Log> {
         Box::new(*self)
     }
@@ -0,0 +1,73 @@
+//! Module providing a panic hook, that logs panics through the active logger
+
+use log::{Level, Record};
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::panic::{self, PanicHookInfo};
+use std::thread;
+
+/// Installs a panic hook, that logs panics at `Error` level through the active logger.
+///
+/// The record has the target `panic` and the location of the panic, its message contains
+/// the name of the panicking thread, the panic message and a backtrace, if backtraces are
+/// enabled through the `RUST_BACKTRACE` environment variable.
+/// The logger is flushed afterwards, so the record is not lost if the panic terminates the process.
+///
+/// The hook replaces the previously installed one, which is still invoked
+/// if no logger is initialized or it does not accept `Error` records for the `panic` target.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let _ = SimpleLogger::init(LevelFilter::Info, Config::default());
+/// install_panic_hook();
+/// # }
+/// ```
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let metadata = log::Metadata::builder()
+            .level(Level::Error)
+            .target("panic")
+            .build();
+        if log::logger().enabled(&metadata) {
+            log_panic(info);
+        } else {
+            previous(info);
+        }
+    }));
+}
+
+fn log_panic(info: &PanicHookInfo<'_>) {
+    let message = match info.payload().downcast_ref::<&str>() {
+        Some(message) => *message,
+        None => match info.payload().downcast_ref::<String>() {
+            Some(message) => message.as_str(),
+            None => "Box<dyn Any>",
+        },
+    };
+    let thread = thread::current();
+    let name = thread.name().unwrap_or("<unnamed>");
+
+    let backtrace = Backtrace::capture();
+    let backtrace = match backtrace.status() {
+        BacktraceStatus::Captured => format!("\nstack backtrace:\n{}", backtrace),
+        _ => String::new(),
+    };
+
+    let logger = log::logger();
+    logger.log(
+        &Record::builder()
+            .args(format_args!(
+                "thread '{}' panicked: {}{}",
+                name, message, backtrace
+            ))
+            .level(Level::Error)
+            .target("panic")
+            .file(info.location().map(|location| location.file()))
+            .line(info.location().map(|location| location.line()))
+            .build(),
+    );
+    logger.flush();
+}
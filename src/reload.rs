@@ -0,0 +1,201 @@
+//! Module providing level filters, that can be changed at runtime, and a watcher reloading them from a file
+
+use crate::config::parse_level_filters;
+use crate::loggers::logging::AtomicLevel;
+use crate::loggers::update_runtime_levels;
+use log::LevelFilter;
+use std::fmt;
+#[cfg(feature = "notify")]
+use std::fs;
+#[cfg(feature = "notify")]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "notify")]
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, RwLock};
+#[cfg(feature = "notify")]
+use std::thread::{self, JoinHandle};
+#[cfg(feature = "notify")]
+use std::time::{Duration, SystemTime};
+
+#[derive(Default)]
+struct Filters {
+    /// Whether any filters are set, so the lock does not need to be taken for every record otherwise
+    active: AtomicBool,
+    filters: RwLock<Vec<(String, LevelFilter)>>,
+    /// The most verbose level of the filters, kept to read it without taking the lock
+    max_level: AtomicLevel,
+}
+
+/// Level filters, that can be changed at runtime, e.g. to turn on debug messages for one module without a restart.
+///
+/// Only the loggers, whose config opts in using
+/// [`ConfigBuilder::set_runtime_level_filters`](crate::ConfigBuilder::set_runtime_level_filters),
+/// follow the filters, every other logger keeps its level. The filters are shared by all clones.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let filters = RuntimeLevelFilters::new();
+/// let config = ConfigBuilder::new().set_runtime_level_filters(&filters).build();
+/// let _ = SimpleLogger::init(LevelFilter::Info, config);
+/// // e.g. after receiving a signal
+/// filters.set("my_app::db=debug");
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct RuntimeLevelFilters(Arc<Filters>);
+
+impl RuntimeLevelFilters {
+    /// Creates an empty set of filters
+    pub fn new() -> RuntimeLevelFilters {
+        RuntimeLevelFilters::default()
+    }
+
+    /// Replaces the filters with the ones parsed from `spec`.
+    ///
+    /// The filters are written in `env_logger`'s `RUST_LOG` directive syntax
+    /// (see [`ConfigBuilder::add_level_filters_from_spec`](crate::ConfigBuilder::add_level_filters_from_spec)).
+    /// They take precedence over the level and the level filters of the loggers following them,
+    /// pass an empty spec to remove them. The global max level of the `log` crate is raised, if the filters are more verbose.
+    pub fn set(&self, spec: &str) {
+        let filters = parse_level_filters(spec);
+        let max_level = filters
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(LevelFilter::Off, std::cmp::max);
+        let mut current = self.0.filters.write().unwrap();
+        self.0.active.store(!filters.is_empty(), Ordering::Relaxed);
+        self.0.max_level.set(max_level);
+        *current = filters;
+        drop(current);
+        update_runtime_levels();
+        if max_level > log::max_level() {
+            log::set_max_level(max_level);
+        }
+    }
+
+    /// Watches a file containing level filters and applies them using [`RuntimeLevelFilters::set`],
+    /// whenever it changes, until the returned watcher is dropped.
+    ///
+    /// The file is checked every `interval`. Its directives may be spread across lines,
+    /// everything following a `#` is a comment. A missing or unreadable file leaves the current filters in place.
+    /// Requires the `notify` feature.
+    ///
+    /// # Examples
+    /// ```no_run
+    /// # extern crate simplelog;
+    /// # use simplelog::*;
+    /// # use std::time::Duration;
+    /// # fn main() {
+    /// let filters = RuntimeLevelFilters::new();
+    /// let config = ConfigBuilder::new().set_runtime_level_filters(&filters).build();
+    /// let _ = SimpleLogger::init(LevelFilter::Info, config);
+    /// let _watcher = filters.watch("/etc/my_app/log_levels", Duration::from_secs(5));
+    /// # }
+    /// ```
+    #[cfg(feature = "notify")]
+    pub fn watch<P: Into<PathBuf>>(&self, path: P, interval: Duration) -> LevelFileWatcher {
+        let filters = self.clone();
+        LevelFileWatcher::spawn(path.into(), interval, move |spec| filters.set(spec))
+    }
+
+    /// Returns the level of `target`, if a filter applies to it
+    #[inline(always)]
+    pub(crate) fn target_level(&self, target: &str) -> Option<LevelFilter> {
+        if !self.0.active.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.0
+            .filters
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(&**prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+    }
+
+    /// The most verbose level of the filters
+    #[inline(always)]
+    pub(crate) fn max_level(&self) -> LevelFilter {
+        self.0.max_level.get()
+    }
+}
+
+impl fmt::Debug for RuntimeLevelFilters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RuntimeLevelFilters")
+            .field(&*self.0.filters.read().unwrap())
+            .finish()
+    }
+}
+
+/// Watches a file containing level filters, returned by [`RuntimeLevelFilters::watch`].
+///
+/// The file is polled for changes of its modification time and size, until the watcher is dropped,
+/// which works on every platform and file system without depending on a file notification library.
+#[cfg(feature = "notify")]
+#[must_use = "the file is no longer watched, when the watcher is dropped"]
+pub struct LevelFileWatcher {
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "notify")]
+impl LevelFileWatcher {
+    pub(crate) fn spawn<F>(path: PathBuf, interval: Duration, apply: F) -> LevelFileWatcher
+    where
+        F: Fn(&str) + Send + 'static,
+    {
+        let (stop, stopped) = mpsc::channel();
+        let thread = thread::Builder::new()
+            .name(String::from("simplelog-reload"))
+            .spawn(move || {
+                let mut seen: Option<(SystemTime, u64)> = None;
+                loop {
+                    let modified = fs::metadata(&path)
+                        .and_then(|meta| Ok((meta.modified()?, meta.len())))
+                        .ok();
+                    if modified.is_some() && modified != seen {
+                        if let Ok(spec) = fs::read_to_string(&path) {
+                            apply(&spec_of_file(&spec));
+                            seen = modified;
+                        }
+                    }
+                    match stopped.recv_timeout(interval) {
+                        Err(RecvTimeoutError::Timeout) => {}
+                        _ => return,
+                    }
+                }
+            })
+            .expect("failed to spawn thread");
+        LevelFileWatcher {
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(feature = "notify")]
+impl Drop for LevelFileWatcher {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Joins the directives of a file, that may be spread across lines and commented using `#`
+#[cfg(feature = "notify")]
+fn spec_of_file(contents: &str) -> String {
+    contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(",")
+}
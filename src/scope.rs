@@ -0,0 +1,109 @@
+//! Module providing timing scopes, logging when they are entered and left
+
+use log::{Level, Metadata, Record};
+use std::fmt;
+use std::time::Instant;
+
+/// Guard created by [`log_scope!`](crate::log_scope), logging the time passed since its creation when dropped.
+///
+/// Entering the scope is logged at `Trace`, leaving it at `Debug` level, both through the
+/// globally installed logger with the module of the scope as target.
+#[must_use = "the scope is left, when the guard is dropped"]
+pub struct LogScope {
+    target: &'static str,
+    file: &'static str,
+    line: u32,
+    /// Name of the scope, `None` if leaving it would not be logged anyway
+    name: Option<String>,
+    start: Instant,
+}
+
+impl LogScope {
+    #[doc(hidden)]
+    pub fn enter(
+        target: &'static str,
+        file: &'static str,
+        line: u32,
+        name: fmt::Arguments<'_>,
+    ) -> LogScope {
+        let mut scope = LogScope {
+            target,
+            file,
+            line,
+            name: None,
+            start: Instant::now(),
+        };
+        if scope.enabled(Level::Debug) {
+            let name = name.to_string();
+            if scope.enabled(Level::Trace) {
+                scope.log(Level::Trace, format_args!("entering {}", name));
+            }
+            scope.name = Some(name);
+        }
+        scope.start = Instant::now();
+        scope
+    }
+
+    fn enabled(&self, level: Level) -> bool {
+        level <= log::max_level()
+            && log::logger().enabled(&Metadata::builder().level(level).target(self.target).build())
+    }
+
+    fn log(&self, level: Level, args: fmt::Arguments<'_>) {
+        log::logger().log(
+            &Record::builder()
+                .args(args)
+                .level(level)
+                .target(self.target)
+                .module_path_static(Some(self.target))
+                .file_static(Some(self.file))
+                .line(Some(self.line))
+                .build(),
+        );
+    }
+}
+
+impl Drop for LogScope {
+    fn drop(&mut self) {
+        if let Some(name) = &self.name {
+            let elapsed = self.start.elapsed();
+            self.log(
+                Level::Debug,
+                format_args!("leaving {} after {:?}", name, elapsed),
+            );
+        }
+    }
+}
+
+/// Logs entering the current scope at `Trace` and leaving it, together with the time spent inside, at `Debug` level.
+///
+/// Takes the name of the scope, optionally with format arguments like `format!`.
+/// The scope is left at the end of the block the macro is used in.
+/// The name is only formatted, if the `Debug` level is enabled for the module.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let logger = MemoryLogger::init(LevelFilter::Trace, Config::default(), 10).unwrap();
+/// {
+///     log_scope!("loading {}", "assets");
+///     // ...
+/// }
+/// let records = logger.snapshot();
+/// assert!(records[0].ends_with("entering loading assets"));
+/// assert!(records[1].contains("leaving loading assets after "));
+/// # }
+/// ```
+#[macro_export]
+macro_rules! log_scope {
+    ($($arg:tt)+) => {
+        let _log_scope = $crate::LogScope::enter(
+            module_path!(),
+            file!(),
+            line!(),
+            format_args!($($arg)+),
+        );
+    };
+}
@@ -0,0 +1,175 @@
+//! Module providing counters of the records handled by all loggers
+
+use crate::handle::Periodic;
+use crate::SharedLogger;
+use log::{Level, Record};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+static EMITTED: [AtomicU64; 5] = [const { AtomicU64::new(0) }; 5];
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static SUPPRESSED: AtomicU64 = AtomicU64::new(0);
+static RATE_LIMITED: AtomicU64 = AtomicU64::new(0);
+static QUEUE_FULL: AtomicU64 = AtomicU64::new(0);
+static WRITE_ERRORS: AtomicU64 = AtomicU64::new(0);
+
+/// Counters of the records handled by all loggers since the start of the program, returned by [`stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    emitted: [u64; 5],
+    /// Records dropped by a [`RecordTransform`](crate::RecordTransform) or the sampling
+    /// (see [`ConfigBuilder::set_sampling`](crate::ConfigBuilder::set_sampling))
+    pub dropped: u64,
+    /// Records suppressed by the rate limit or folded into a summary of repeated messages
+    /// (see [`ConfigBuilder::set_rate_limit`](crate::ConfigBuilder::set_rate_limit) and
+    /// [`ConfigBuilder::set_fold_repeats`](crate::ConfigBuilder::set_fold_repeats))
+    pub suppressed: u64,
+    /// Records suppressed by the rate limit, a part of the `suppressed` ones
+    pub rate_limited: u64,
+    /// Records dropped by an `AsyncLogger`, because its queue was full
+    pub queue_full: u64,
+    /// Records, that could not be written by a logger
+    pub write_errors: u64,
+}
+
+impl Stats {
+    /// Returns the amount of records of the given level passed on to be written
+    pub fn emitted(&self, level: Level) -> u64 {
+        self.emitted[level as usize - 1]
+    }
+
+    /// Returns the amount of records of all levels passed on to be written
+    pub fn total(&self) -> u64 {
+        self.emitted.iter().sum()
+    }
+
+    /// Returns the amount of records lost unintentionally, i.e. the ones rate limited,
+    /// dropped by a full queue or not written due to errors
+    pub fn lost(&self) -> u64 {
+        self.rate_limited + self.queue_full + self.write_errors
+    }
+}
+
+/// Returns the counters of the records handled by all loggers.
+///
+/// Every logger counts the records it writes, so a record written by multiple loggers
+/// of a `CombinedLogger` is counted once per logger. Records rejected by the level
+/// or the filters of a logger are not counted. The counters are never reset,
+/// rates like errors per minute are the difference of two snapshots.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let before = stats();
+/// // some time later
+/// let errors = stats().emitted(Level::Error) - before.emitted(Level::Error);
+/// # }
+/// ```
+pub fn stats() -> Stats {
+    let mut stats = Stats {
+        dropped: DROPPED.load(Ordering::Relaxed),
+        suppressed: SUPPRESSED.load(Ordering::Relaxed),
+        rate_limited: RATE_LIMITED.load(Ordering::Relaxed),
+        queue_full: QUEUE_FULL.load(Ordering::Relaxed),
+        write_errors: WRITE_ERRORS.load(Ordering::Relaxed),
+        ..Stats::default()
+    };
+    for (count, emitted) in stats.emitted.iter_mut().zip(&EMITTED) {
+        *count = emitted.load(Ordering::Relaxed);
+    }
+    stats
+}
+
+pub(crate) fn count_emitted(level: Level) {
+    EMITTED[level as usize - 1].fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_dropped() {
+    DROPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_suppressed() {
+    SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_rate_limited() {
+    SUPPRESSED.fetch_add(1, Ordering::Relaxed);
+    RATE_LIMITED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_queue_full() {
+    QUEUE_FULL.fetch_add(1, Ordering::Relaxed);
+}
+
+pub(crate) fn count_write_error() {
+    WRITE_ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Describes the records lost between the snapshots `before` and `after`, if any were lost
+pub(crate) fn loss_summary(before: &Stats, after: &Stats, interval: Duration) -> Option<String> {
+    let lost = after.lost() - before.lost();
+    if lost == 0 {
+        return None;
+    }
+    let mut summary = format!("lost {} log records in the last {:?}:", lost, interval);
+    let causes = [
+        (after.rate_limited - before.rate_limited, "rate limited"),
+        (
+            after.queue_full - before.queue_full,
+            "dropped by a full queue",
+        ),
+        (
+            after.write_errors - before.write_errors,
+            "not written due to errors",
+        ),
+    ];
+    let mut first = true;
+    for (count, cause) in causes.iter().filter(|(count, _)| *count > 0) {
+        let _ = write!(
+            summary,
+            "{} {} {}",
+            if first { "" } else { "," },
+            count,
+            cause
+        );
+        first = false;
+    }
+    Some(summary)
+}
+
+/// Logs a summary of the lost records periodically, returned by
+/// [`LoggerHandle::report_lost_records`](crate::LoggerHandle::report_lost_records).
+///
+/// Records are lost silently otherwise, the summary makes a full queue or a failing disk show up in the logs.
+#[must_use = "the lost records are no longer reported, when the reporter is dropped"]
+pub struct LossReporter {
+    _thread: Periodic,
+}
+
+impl LossReporter {
+    pub(crate) fn spawn<L: SharedLogger + 'static>(
+        logger: Arc<L>,
+        interval: Duration,
+    ) -> LossReporter {
+        let mut before = stats();
+        LossReporter {
+            _thread: Periodic::spawn("simplelog-losses", interval, move || {
+                let after = stats();
+                if let Some(summary) = loss_summary(&before, &after, interval) {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("{}", summary))
+                            .level(Level::Warn)
+                            .target("simplelog")
+                            .build(),
+                    );
+                }
+                before = after;
+            }),
+        }
+    }
+}
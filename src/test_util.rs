@@ -0,0 +1,52 @@
+//! Helpers shared by the tests of the loggers
+
+use crate::{Config, LevelFilter, WriteLogger};
+use log::{Level, Log, Record};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Returns an empty directory for the files of the test `name`, removing what a previous run left
+pub(crate) fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("simplelog_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Logs `msg` at `level` to `logger`
+pub(crate) fn log(logger: &dyn Log, level: Level, msg: &str) {
+    logger.log(
+        &Record::builder()
+            .args(format_args!("{}", msg))
+            .level(level)
+            .build(),
+    );
+}
+
+/// Writer handing out its contents to the test, that created it
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuf(pub(crate) Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuf {
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl std::io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Returns `record` as written by a `WriteLogger` using `config`
+pub(crate) fn log_to_string(config: Config, record: &Record<'_>) -> String {
+    let buf = SharedBuf::default();
+    WriteLogger::new(LevelFilter::Trace, config, buf.clone()).log(record);
+    buf.contents()
+}
@@ -0,0 +1,173 @@
+//! Module providing a `tracing` subscriber, that passes events on to the loggers
+
+use crate::SharedLogger;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing_core::field::{Field, Visit};
+use tracing_core::span::{Attributes, Id, Record as SpanRecord};
+use tracing_core::subscriber::{Interest, Subscriber};
+use tracing_core::{dispatcher, Dispatch, Event};
+
+fn to_level(level: &tracing_core::Level) -> Level {
+    match *level {
+        tracing_core::Level::ERROR => Level::Error,
+        tracing_core::Level::WARN => Level::Warn,
+        tracing_core::Level::INFO => Level::Info,
+        tracing_core::Level::DEBUG => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+/// Collects the message and the other fields of an event
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            name => self.fields.push((String::from(name), String::from(value))),
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name => self
+                .fields
+                .push((String::from(name), format!("{:?}", value))),
+        }
+    }
+}
+
+/// A `tracing` subscriber passing events on as records to the loggers, so libraries using `tracing`
+/// show up in the same formatted output as the ones using `log`. Requires the `tracing` feature.
+///
+/// The level, target, module and location of an event are kept, its `message` becomes the message
+/// of the record and the other fields become its key-values. Without the `kv` feature
+/// the fields are appended to the message as `key=value` pairs instead.
+/// Spans are not logged, the fields of events are not enriched with the ones of their spans.
+///
+/// Usually the bridge is installed using [`init_tracing_bridge`] after initializing the loggers.
+pub struct TracingBridge {
+    /// Logger to pass the records to, the globally installed one if `None`
+    logger: Option<Box<dyn SharedLogger>>,
+    next_id: AtomicU64,
+}
+
+impl TracingBridge {
+    /// Creates a bridge passing events on to the globally installed logger
+    pub fn new() -> TracingBridge {
+        TracingBridge {
+            logger: None,
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Creates a bridge passing events on to `logger` instead of the globally installed logger
+    pub fn with_logger(logger: Box<dyn SharedLogger>) -> TracingBridge {
+        TracingBridge {
+            logger: Some(logger),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        match &self.logger {
+            Some(logger) => logger.level(),
+            None => log::max_level(),
+        }
+    }
+
+    fn logger(&self) -> &dyn Log {
+        match &self.logger {
+            Some(logger) => logger.as_ref(),
+            None => log::logger(),
+        }
+    }
+}
+
+impl Default for TracingBridge {
+    fn default() -> TracingBridge {
+        TracingBridge::new()
+    }
+}
+
+impl Subscriber for TracingBridge {
+    fn register_callsite(&self, metadata: &'static tracing_core::Metadata<'static>) -> Interest {
+        match metadata.is_span() {
+            true => Interest::never(),
+            // levels of the loggers may change at any time, so check every event
+            false => Interest::sometimes(),
+        }
+    }
+
+    fn enabled(&self, metadata: &tracing_core::Metadata<'_>) -> bool {
+        let level = to_level(metadata.level());
+        !metadata.is_span()
+            && level <= self.max_level()
+            && self.logger().enabled(
+                &Metadata::builder()
+                    .level(level)
+                    .target(metadata.target())
+                    .build(),
+            )
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &SpanRecord<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        #[cfg(not(feature = "kv"))]
+        for (key, value) in &fields.fields {
+            let _ = write!(fields.message, " {}={}", key, value);
+        }
+
+        let mut builder = Record::builder();
+        builder
+            .level(to_level(metadata.level()))
+            .target(metadata.target())
+            .module_path(metadata.module_path())
+            .file(metadata.file())
+            .line(metadata.line());
+        #[cfg(feature = "kv")]
+        builder.key_values(&fields.fields);
+        self.logger()
+            .log(&builder.args(format_args!("{}", fields.message)).build());
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Installs a [`TracingBridge`] as the global `tracing` subscriber, passing events on to the globally installed logger.
+///
+/// Fails if another global subscriber was already installed.
+///
+/// # Examples
+/// ```
+/// # extern crate simplelog;
+/// # use simplelog::*;
+/// # fn main() {
+/// let _ = SimpleLogger::init(LevelFilter::Info, Config::default());
+/// let _ = init_tracing_bridge();
+/// # }
+/// ```
+pub fn init_tracing_bridge() -> Result<(), dispatcher::SetGlobalDefaultError> {
+    dispatcher::set_global_default(Dispatch::new(TracingBridge::new()))
+}
@@ -0,0 +1,88 @@
+//! `ureq` logs through the global logger while sending requests, so the HTTP loggers
+//! are tested as the global logger in their own process.
+#![cfg(feature = "http")]
+
+use log::LevelFilter;
+use simplelog::{
+    CombinedLogger, Config, HttpLogger, HttpOptions, NetworkOptions, SharedLogger, WebhookLogger,
+    WebhookOptions,
+};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+/// Answers every request to the returned URL with `200 OK`, passing on the bodies of the requests
+fn serve(path: &str) -> (String, Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}{}", listener.local_addr().unwrap(), path);
+    let (received, bodies) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            let received = received.clone();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                loop {
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                            return;
+                        }
+                        if line == "\r\n" {
+                            break;
+                        }
+                        if let Some(value) = line.to_lowercase().strip_prefix("content-length: ") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    (&stream)
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n")
+                        .unwrap();
+                    let _ = received.send(String::from_utf8(body).unwrap());
+                }
+            });
+        }
+    });
+    (url, bodies)
+}
+
+#[test]
+fn test_http_loggers_at_debug() {
+    let (http_url, batches) = serve("/ingest");
+    let (webhook_url, posts) = serve("/hook");
+    let mut options = HttpOptions::new();
+    options.set_batch_size(1).add_header("X-Api-Key", "secret");
+    let loggers: Vec<Box<dyn SharedLogger>> = vec![
+        HttpLogger::new(
+            LevelFilter::Debug,
+            Config::default(),
+            &http_url,
+            options,
+            NetworkOptions::default(),
+        ),
+        WebhookLogger::new(
+            LevelFilter::Debug,
+            Config::default(),
+            &webhook_url,
+            WebhookOptions::default(),
+            NetworkOptions::default(),
+        ),
+    ];
+    CombinedLogger::init(loggers).unwrap();
+
+    log::info!("shipped at debug");
+    for bodies in &[batches, posts] {
+        let body = bodies.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(body.contains("shipped at debug"), "{}", body);
+        // the records logged by ureq while sending must not be sent in requests of their own
+        assert!(
+            bodies.recv_timeout(Duration::from_secs(1)).is_err(),
+            "records logged by ureq were sent"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! The `kafka` crate logs through `tracing` while publishing, which reaches the global logger
+//! by the tracing bridge. So the KafkaLogger is tested as the global logger in its own process,
+//! against a minimal broker.
+#![cfg(all(feature = "kafka", feature = "tracing"))]
+
+use log::LevelFilter;
+use simplelog::{init_tracing_bridge, Config, KafkaKey, KafkaLogger};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+
+const API_KEY_PRODUCE: i16 = 0;
+const API_KEY_METADATA: i16 = 3;
+
+fn put_str(buf: &mut Vec<u8>, value: &str) {
+    buf.extend_from_slice(&(value.len() as i16).to_be_bytes());
+    buf.extend_from_slice(value.as_bytes());
+}
+
+/// Answers the metadata and produce requests (version 0) of one connection,
+/// passing the produce requests on to `produced`
+fn serve(mut stream: TcpStream, port: u16, produced: Sender<Vec<u8>>) {
+    loop {
+        let mut size = [0u8; 4];
+        if stream.read_exact(&mut size).is_err() {
+            return;
+        }
+        let mut request = vec![0u8; i32::from_be_bytes(size) as usize];
+        stream.read_exact(&mut request).unwrap();
+        let api_key = i16::from_be_bytes([request[0], request[1]]);
+
+        // correlation id
+        let mut body = request[4..8].to_vec();
+        match api_key {
+            API_KEY_METADATA => {
+                // one broker
+                body.extend_from_slice(&1i32.to_be_bytes());
+                body.extend_from_slice(&0i32.to_be_bytes());
+                put_str(&mut body, "127.0.0.1");
+                body.extend_from_slice(&i32::from(port).to_be_bytes());
+                // one topic with one partition led by the broker
+                body.extend_from_slice(&1i32.to_be_bytes());
+                body.extend_from_slice(&0i16.to_be_bytes());
+                put_str(&mut body, "logs");
+                body.extend_from_slice(&1i32.to_be_bytes());
+                body.extend_from_slice(&0i16.to_be_bytes());
+                body.extend_from_slice(&0i32.to_be_bytes());
+                body.extend_from_slice(&0i32.to_be_bytes());
+                for _ in 0..2 {
+                    // replicas and in-sync replicas
+                    body.extend_from_slice(&1i32.to_be_bytes());
+                    body.extend_from_slice(&0i32.to_be_bytes());
+                }
+            }
+            API_KEY_PRODUCE => {
+                body.extend_from_slice(&1i32.to_be_bytes());
+                put_str(&mut body, "logs");
+                body.extend_from_slice(&1i32.to_be_bytes());
+                body.extend_from_slice(&0i32.to_be_bytes());
+                body.extend_from_slice(&0i16.to_be_bytes());
+                body.extend_from_slice(&0i64.to_be_bytes());
+                let _ = produced.send(request);
+            }
+            _ => return,
+        }
+        stream
+            .write_all(&(body.len() as i32).to_be_bytes())
+            .unwrap();
+        stream.write_all(&body).unwrap();
+    }
+}
+
+#[test]
+fn test_publish_at_trace() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (produced, requests) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let produced = produced.clone();
+            thread::spawn(move || serve(stream.unwrap(), port, produced));
+        }
+    });
+
+    KafkaLogger::init(
+        LevelFilter::Trace,
+        Config::default(),
+        &[format!("127.0.0.1:{}", port)],
+        "logs",
+        KafkaKey::None,
+    )
+    .unwrap();
+    init_tracing_bridge().unwrap();
+
+    // the records of the kafka crate must neither deadlock nor be published
+    let (done, finished) = mpsc::channel();
+    thread::spawn(move || {
+        log::info!("published at trace");
+        done.send(()).unwrap();
+    });
+    finished
+        .recv_timeout(Duration::from_secs(10))
+        .expect("logging deadlocked");
+
+    let request = requests.recv_timeout(Duration::from_secs(1)).unwrap();
+    let message = b"published at trace";
+    assert!(request
+        .windows(message.len())
+        .any(|window| window == message));
+    assert!(requests.try_recv().is_err());
+}
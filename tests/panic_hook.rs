@@ -0,0 +1,34 @@
+//! The panic hook logs through the global logger, so it is tested in its own process,
+//! apart from the unit tests initializing a logger of their own.
+#![cfg(feature = "test")]
+
+use log::{Level, LevelFilter};
+use simplelog::{install_panic_hook, Config, TestLogger};
+use std::panic;
+use std::thread;
+
+#[test]
+fn test_panic_hook() {
+    TestLogger::init(LevelFilter::Error, Config::default()).unwrap();
+    install_panic_hook();
+    let (records, line) = thread::Builder::new()
+        .name("worker".into())
+        .spawn(|| {
+            let line = line!() + 1;
+            let res = panic::catch_unwind(|| panic!("invalid state"));
+            assert!(res.is_err());
+            (TestLogger::records(), line)
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(records.len(), 1, "{:?}", records);
+    assert_eq!(records[0].level, Level::Error);
+    assert_eq!(records[0].target, "panic");
+    assert!(records[0]
+        .message
+        .starts_with("thread 'worker' panicked: invalid state"));
+    assert_eq!(records[0].file.as_deref(), Some(file!()));
+    assert_eq!(records[0].line, Some(line));
+}